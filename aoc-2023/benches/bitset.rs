@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+
+use aoc_2023::common::bitset::BitSet;
+
+mod common;
+
+use common::{random_values, time};
+
+// the growable bitset against HashSet<usize> as a visited set: inserting, looking up,
+// combining and walking the elements. The values are dense (below 4 * n), like grid cells
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+fn main() {
+    for size in SIZES {
+        let values = random_values(size, size * 4);
+        let (first, second) = values.split_at(size / 2);
+
+        let hashed = time("hash set: insert", size, || values.iter().copied().collect::<HashSet<usize>>());
+        let bits = time("bitset: insert", size, || values.iter().copied().collect::<BitSet>());
+
+        time("hash set: contains 0..4n", size, || (0..size * 4).filter(|value| hashed.contains(value)).count());
+        time("bitset: contains 0..4n", size, || (0..size * 4).filter(|&value| bits.contains(value)).count());
+
+        let (hashed_a, hashed_b): (HashSet<usize>, HashSet<usize>) = (first.iter().copied().collect(), second.iter().copied().collect());
+        let (bits_a, bits_b): (BitSet, BitSet) = (first.iter().copied().collect(), second.iter().copied().collect());
+        time("hash set: union + intersection", size, || {
+            hashed_a.union(&hashed_b).count() + hashed_a.intersection(&hashed_b).count()
+        });
+        time("bitset: union + intersection", size, || (&bits_a | &bits_b).len() + (&bits_a & &bits_b).len());
+
+        time("hash set: sum of elements", size, || hashed.iter().sum::<usize>());
+        time("bitset: sum of elements", size, || bits.iter().sum::<usize>());
+        println!();
+    }
+}
@@ -0,0 +1,23 @@
+// Helpers the benches share, each of them pulls this in with mod common. Not every bench
+// uses every helper, which would warn about dead code once per bench otherwise
+#![allow(dead_code)]
+
+use std::{hint::black_box, time::Instant};
+
+use aoc_2023::common::random::Rng;
+
+// count values below below, the same ones on every run so the timings stay comparable
+pub fn random_values(count: usize, below: usize) -> Vec<usize> {
+    let mut rng = Rng::new(count as u64);
+    (0..count).map(|_| rng.gen_range(0..below)).collect()
+}
+
+// prints how long run took and hands its result back, so whatever run built is dropped
+// by the caller outside of the timed part
+pub fn time<R>(name: &str, size: usize, run: impl FnOnce() -> R) -> R {
+    let start = Instant::now();
+    let result = black_box(run());
+    let elapsed = start.elapsed();
+    println!("{:<45} n = {:>8}: {:>10.3?}", name, size, elapsed);
+    result
+}
@@ -0,0 +1,55 @@
+use aoc_2023::linked_lists::{skip_list::SkipList, sorted_doubly_linked_list};
+
+mod common;
+
+use common::{random_values, time};
+
+// skip list against the sorted doubly linked list, for growing sizes the sorted list
+// should fall behind quadratically on inserts while the skip list stays close to n log n
+
+const SIZES: [usize; 3] = [1_000, 5_000, 20_000];
+
+fn main() {
+    for size in SIZES {
+        let values = random_values(size, size * 10);
+
+        time("sorted list: insert random", size, || {
+            let mut list = sorted_doubly_linked_list::List::new();
+            for value in &values {
+                list.insert(*value);
+            }
+            list
+        });
+        time("skip list: insert random", size, || {
+            let mut list = SkipList::new();
+            for value in &values {
+                list.insert(*value);
+            }
+            list
+        });
+
+        // the sorted list has no search, popping everything in order is what both can do
+        let mut sorted: sorted_doubly_linked_list::List<usize> = values.iter().copied().collect();
+        time("sorted list: pop all in order", size, || {
+            let mut sum = 0;
+            while let Some(value) = sorted.pop_front() {
+                sum += value;
+            }
+            sum
+        });
+        let mut skip: SkipList<usize> = values.iter().copied().collect();
+        time("skip list: pop all in order", size, || {
+            let mut sum = 0;
+            while let Some(value) = skip.pop_first() {
+                sum += value;
+            }
+            sum
+        });
+
+        let skip: SkipList<usize> = values.iter().copied().collect();
+        time("skip list: contains + rank for every value", size, || {
+            values.iter().filter(|value| skip.contains(value)).map(|value| skip.rank(value)).sum::<usize>()
+        });
+        println!();
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use aoc_2023::linked_lists::{bad_but_safe_doubly_linked_deq, ok_singly_linked_stack, unrolled::UnrolledList};
+
+mod common;
+
+use common::time;
+
+// one heap node per element against chunks of elements against one ring buffer,
+// for filling the collection up, iterating over it and emptying it again
+
+const SIZE: usize = 1_000_000;
+
+fn main() {
+    let mut stack = time("ok singly linked stack: push", SIZE, || {
+        let mut stack = ok_singly_linked_stack::List::new();
+        for value in 0..SIZE {
+            stack.push(value);
+        }
+        stack
+    });
+    time("ok singly linked stack: iterate", SIZE, || stack.iter().sum::<usize>());
+    time("ok singly linked stack: pop", SIZE, || {
+        let mut sum = 0;
+        while let Some(value) = stack.pop() {
+            sum += value;
+        }
+        sum
+    });
+    println!();
+
+    // its Iter can't lend elements out of the RefCells, so it only gets iterated by value
+    let deq = time("bad but safe doubly linked deq: push back", SIZE, || {
+        let mut deq = bad_but_safe_doubly_linked_deq::List::new();
+        for value in 0..SIZE {
+            deq.push_back(value);
+        }
+        deq
+    });
+    time("bad but safe doubly linked deq: pop front", SIZE, || deq.into_iter().sum::<usize>());
+    println!();
+
+    let mut unrolled = time("unrolled list: push back", SIZE, || {
+        let mut list = UnrolledList::new();
+        for value in 0..SIZE {
+            list.push_back(value);
+        }
+        list
+    });
+    time("unrolled list: iterate", SIZE, || unrolled.iter().sum::<usize>());
+    time("unrolled list: pop front", SIZE, || {
+        let mut sum = 0;
+        while let Some(value) = unrolled.pop_front() {
+            sum += value;
+        }
+        sum
+    });
+    println!();
+
+    let mut ring = time("VecDeque: push back", SIZE, || {
+        let mut ring = VecDeque::new();
+        for value in 0..SIZE {
+            ring.push_back(value);
+        }
+        ring
+    });
+    time("VecDeque: iterate", SIZE, || ring.iter().sum::<usize>());
+    time("VecDeque: pop front", SIZE, || {
+        let mut sum = 0;
+        while let Some(value) = ring.pop_front() {
+            sum += value;
+        }
+        sum
+    });
+}
@@ -0,0 +1,333 @@
+use std::{fmt::Debug, iter::FusedIterator, ops::{BitAnd, BitOr, BitXor, Div, Not, Shl, Shr}};
+
+// Iterating over the bits of unsigned integers, without formatting them into strings first.
+//
+// BitIter goes over a fixed number of bits, lowest first, .rev() gives them highest first.
+// Asking for more bits than the type has just pads with zeros. SetBits gives the indices of
+// the ones, Subsets every submask of a mask and Combinations the submasks with exactly k
+// bits set - handy for puzzles that try every selection of a few items.
+
+pub trait Bits:
+    Copy + Eq + Ord + Debug
+    + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+    + Shl<u32, Output = Self> + Shr<u32, Output = Self> + Div<Output = Self>
+{
+    const WIDTH: u32;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn count_ones(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_neg(self) -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    // bits past the width of the type are zero
+    fn bit(self, index: u32) -> bool {
+        index < Self::WIDTH && self >> index & Self::ONE == Self::ONE
+    }
+
+    fn with_bit(self, index: u32) -> Self {
+        self | Self::ONE << index
+    }
+
+    // the lowest `width` bits set, all of them for a width of WIDTH or more
+    fn low_mask(width: u32) -> Self {
+        if width >= Self::WIDTH { !Self::ZERO } else { (Self::ONE << width).wrapping_add(!Self::ZERO) }
+    }
+
+    // how many bits it takes to write the number down, none for 0
+    fn significant_bits(self) -> u32 {
+        Self::WIDTH - self.leading_zeros()
+    }
+}
+
+macro_rules! impl_bits {
+    ($($t:ty),*) => {$(
+        impl Bits for $t {
+            const WIDTH: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn count_ones(self) -> u32 { <$t>::count_ones(self) }
+            fn leading_zeros(self) -> u32 { <$t>::leading_zeros(self) }
+            fn trailing_zeros(self) -> u32 { <$t>::trailing_zeros(self) }
+            fn wrapping_add(self, other: Self) -> Self { <$t>::wrapping_add(self, other) }
+            fn wrapping_neg(self) -> Self { <$t>::wrapping_neg(self) }
+            fn checked_add(self, other: Self) -> Option<Self> { <$t>::checked_add(self, other) }
+        }
+    )*};
+}
+
+impl_bits!(u8, u16, u32, u64, u128, usize);
+
+#[derive(Clone, Debug)]
+pub struct BitIter<T> {
+    value: T,
+    // the bits still to go are start..end
+    start: u32,
+    end: u32
+}
+
+impl<T: Bits> BitIter<T> {
+    // the lowest `width` bits, zero padded when the number is shorter than that
+    pub fn new(value: T, width: u32) -> Self {
+        BitIter { value, start: 0, end: width }
+    }
+
+    // just the significant bits, so 0b1010 gives four bits and 0 none at all
+    pub fn significant(value: T) -> Self {
+        BitIter::new(value, value.significant_bits())
+    }
+
+    // all WIDTH bits of the type
+    pub fn all(value: T) -> Self {
+        BitIter::new(value, T::WIDTH)
+    }
+}
+
+impl<T: Bits> Iterator for BitIter<T> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(self.value.bit(self.start - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<T: Bits> DoubleEndedIterator for BitIter<T> {
+    fn next_back(&mut self) -> Option<bool> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.value.bit(self.end))
+    }
+}
+
+impl<T: Bits> ExactSizeIterator for BitIter<T> {}
+
+impl<T: Bits> FusedIterator for BitIter<T> {}
+
+// indices of the set bits, lowest first (highest first with .rev())
+#[derive(Clone, Debug)]
+pub struct SetBits<T> {
+    remaining: T
+}
+
+impl<T: Bits> SetBits<T> {
+    pub fn new(value: T) -> Self {
+        SetBits { remaining: value }
+    }
+}
+
+impl<T: Bits> Iterator for SetBits<T> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.remaining == T::ZERO {
+            return None;
+        }
+        let index = self.remaining.trailing_zeros();
+        // x & (x - 1) clears the lowest set bit
+        self.remaining = self.remaining & self.remaining.wrapping_add(!T::ZERO);
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl<T: Bits> DoubleEndedIterator for SetBits<T> {
+    fn next_back(&mut self) -> Option<u32> {
+        if self.remaining == T::ZERO {
+            return None;
+        }
+        let index = T::WIDTH - 1 - self.remaining.leading_zeros();
+        self.remaining = self.remaining ^ T::ONE << index;
+        Some(index)
+    }
+}
+
+impl<T: Bits> ExactSizeIterator for SetBits<T> {}
+
+impl<T: Bits> FusedIterator for SetBits<T> {}
+
+// every submask of mask in increasing order, from 0 up to mask itself
+#[derive(Clone, Debug)]
+pub struct Subsets<T> {
+    mask: T,
+    next: Option<T>
+}
+
+impl<T: Bits> Subsets<T> {
+    pub fn new(mask: T) -> Self {
+        Subsets { mask, next: Some(T::ZERO) }
+    }
+}
+
+impl<T: Bits> Iterator for Subsets<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let current = self.next?;
+        // filling the holes with ones makes the carry of +1 skip over them
+        let next = (current | !self.mask).wrapping_add(T::ONE) & self.mask;
+        self.next = if next == T::ZERO { None } else { Some(next) };
+        Some(current)
+    }
+}
+
+impl<T: Bits> FusedIterator for Subsets<T> {}
+
+// the submasks of mask with exactly k bits set, in increasing order
+#[derive(Clone, Debug)]
+pub struct Combinations<T> {
+    mask: T,
+    // the combination as if the bits of mask were packed next to each other at the bottom
+    packed: Option<T>,
+    available: u32
+}
+
+impl<T: Bits> Combinations<T> {
+    pub fn new(mask: T, k: u32) -> Self {
+        let available = mask.count_ones();
+        let packed = if k <= available { Some(T::low_mask(k)) } else { None };
+        Combinations { mask, packed, available }
+    }
+
+    // k out of the lowest n bits
+    pub fn of_width(n: u32, k: u32) -> Self {
+        Combinations::new(T::low_mask(n), k)
+    }
+
+    // spreads the packed bits out to the positions of the set bits of mask
+    fn unpack(&self, packed: T) -> T {
+        SetBits::new(self.mask)
+            .enumerate()
+            .filter(|&(i, _)| packed.bit(i as u32))
+            .fold(T::ZERO, |value, (_, index)| value.with_bit(index))
+    }
+}
+
+impl<T: Bits> Iterator for Combinations<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let current = self.packed?;
+        // Gosper's hack: the next bigger number with the same amount of ones
+        self.packed = if current == T::ZERO {
+            None
+        } else {
+            let lowest = current & current.wrapping_neg();
+            current.checked_add(lowest)
+                .map(|ripple| (((ripple ^ current) >> 2) / lowest) | ripple)
+                .filter(|next| next.significant_bits() <= self.available)
+        };
+        Some(self.unpack(current))
+    }
+}
+
+impl<T: Bits> FusedIterator for Combinations<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::random::Rng;
+
+    fn to_string(bits: impl Iterator<Item = bool>) -> String {
+        bits.map(|bit| if bit { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn test_bit_order() {
+        assert_eq!(BitIter::new(10u32, 4).collect::<Vec<_>>(), vec![false, true, false, true]);
+        assert_eq!(to_string(BitIter::new(42u8, 6)), "010101");
+        assert_eq!(to_string(BitIter::new(42u8, 6).rev()), "101010");
+        assert_eq!(to_string(BitIter::significant(7u16)), "111");
+        // the low bits when the width is smaller than the number
+        assert_eq!(to_string(BitIter::new(0b1101u32, 2)), "10");
+    }
+
+    #[test]
+    fn test_padding() {
+        // more bits than the number has used to run off the end of a Vec
+        assert_eq!(to_string(BitIter::new(1u32, 4)), "1000");
+        assert_eq!(to_string(BitIter::new(1u32, 4).rev()), "0001");
+        assert_eq!(to_string(BitIter::new(0u8, 3)), "000");
+        // and past the width of the type
+        assert_eq!(to_string(BitIter::new(u8::MAX, 10).rev()), "0011111111");
+        assert_eq!(BitIter::new(5u64, 200).len(), 200);
+    }
+
+    #[test]
+    fn test_edge_values() {
+        assert_eq!(BitIter::significant(0u32).count(), 0);
+        assert_eq!(BitIter::all(0u32).filter(|&bit| bit).count(), 0);
+        assert!(BitIter::all(u32::MAX).all(|bit| bit));
+        assert_eq!(BitIter::significant(u32::MAX).len(), 32);
+        assert_eq!(BitIter::all(u128::MAX).len(), 128);
+        assert_eq!(to_string(BitIter::all(1u128 << 127).rev().take(2)), "10");
+        assert_eq!(SetBits::new(0u32).next(), None);
+        assert_eq!(SetBits::new(u32::MAX).collect::<Vec<_>>(), (0..32).collect::<Vec<_>>());
+        assert_eq!(SetBits::new(u128::MAX).next_back(), Some(127));
+        assert_eq!(Subsets::new(0u8).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(Subsets::new(u8::MAX).count(), 256);
+        assert_eq!(Combinations::new(u64::MAX, 64).collect::<Vec<_>>(), vec![u64::MAX]);
+        assert_eq!(Combinations::new(u64::MAX, 0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(Combinations::new(u128::MAX, 1).last(), Some(1 << 127));
+    }
+
+    #[test]
+    fn test_matches_formatting() {
+        let mut rng = Rng::new(46);
+        for _ in 0..1000 {
+            let value = rng.next_u64() >> rng.gen_range(0..64);
+            let formatted = if value == 0 { String::new() } else { format!("{:b}", value) };
+            assert_eq!(to_string(BitIter::significant(value).rev()), formatted);
+            assert_eq!(to_string(BitIter::all(value).rev()), format!("{:064b}", value));
+            let set: Vec<u32> = formatted.chars().rev().enumerate()
+                .filter(|&(_, c)| c == '1')
+                .map(|(i, _)| i as u32)
+                .collect();
+            assert_eq!(SetBits::new(value).collect::<Vec<_>>(), set);
+            assert_eq!(SetBits::new(value).rev().collect::<Vec<_>>(), set.iter().rev().copied().collect::<Vec<_>>());
+            assert_eq!(SetBits::new(value).len(), set.len());
+        }
+    }
+
+    #[test]
+    fn test_subsets() {
+        assert_eq!(Subsets::new(0b1010u8).collect::<Vec<_>>(), vec![0b0000, 0b0010, 0b1000, 0b1010]);
+        let mask = 0b1011_0110u16;
+        let expected: Vec<u16> = (0..=u16::MAX).filter(|&value| value & !mask == 0).collect();
+        assert_eq!(Subsets::new(mask).collect::<Vec<_>>(), expected);
+        assert_eq!(Subsets::new(1u128 << 127 | 1).collect::<Vec<_>>(), vec![0, 1, 1 << 127, 1 << 127 | 1]);
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(Combinations::<u8>::of_width(4, 2).collect::<Vec<_>>(), vec![0b0011, 0b0101, 0b0110, 0b1001, 0b1010, 0b1100]);
+        assert_eq!(Combinations::new(0b1011_0100u8, 2).collect::<Vec<_>>(), vec![0b0001_0100, 0b0010_0100, 0b0011_0000, 0b1000_0100, 0b1001_0000, 0b1010_0000]);
+        assert_eq!(Combinations::<u32>::of_width(3, 4).next(), None);
+        for mask in [0u16, 0b1, 0b1111, 0b1010_0101_1100, u16::MAX] {
+            for k in 0..=17 {
+                let expected: Vec<u16> = Subsets::new(mask).filter(|value| value.count_ones() == k).collect();
+                assert_eq!(Combinations::new(mask, k).collect::<Vec<_>>(), expected, "{:b} choose {}", mask, k);
+            }
+        }
+        // 32 choose 3
+        assert_eq!(Combinations::<u32>::of_width(32, 3).count(), 4960);
+    }
+}
@@ -0,0 +1,520 @@
+use std::{fmt::{self, Debug}, hash::{Hash, Hasher}, ops::{BitAnd, BitOr, Sub}};
+
+use super::bits::SetBits;
+
+// Sets of small non negative integers, one bit per possible element.
+// Much smaller and faster than a HashSet<usize> when the elements are dense, like the
+// cells of a grid (row * width + col) in a visited set.
+//
+// FixedBitSet<WORDS> holds 0..WORDS * 64 and is Copy, Hash and Ord, so it can be the key
+// of a subset DP (the items picked so far). BitSet grows to fit whatever is inserted, two
+// of them are equal when they hold the same elements, however much room they have.
+//
+// The method names follow HashSet, so one can replace the other without touching callers.
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+fn position(element: usize) -> (usize, u64) {
+    (element / WORD_BITS, 1 << (element % WORD_BITS))
+}
+
+fn contains(words: &[u64], element: usize) -> bool {
+    let (word, bit) = position(element);
+    words.get(word).is_some_and(|word| word & bit != 0)
+}
+
+fn count(words: &[u64]) -> usize {
+    words.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+fn is_subset(words: &[u64], other: &[u64]) -> bool {
+    words.iter().enumerate().all(|(i, word)| word & !other.get(i).copied().unwrap_or(0) == 0)
+}
+
+// the elements in increasing order
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    word: usize,
+    bits: SetBits<u64>
+}
+
+impl<'a> Iter<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        Iter { words, word: 0, bits: SetBits::new(words.first().copied().unwrap_or(0)) }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(bit) = self.bits.next() {
+                return Some(self.word * WORD_BITS + bit as usize);
+            }
+            self.word += 1;
+            self.bits = SetBits::new(*self.words.get(self.word)?);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FixedBitSet<const WORDS: usize> {
+    words: [u64; WORDS]
+}
+
+impl<const WORDS: usize> FixedBitSet<WORDS> {
+    pub const CAPACITY: usize = WORDS * WORD_BITS;
+
+    pub fn new() -> Self {
+        FixedBitSet { words: [0; WORDS] }
+    }
+
+    // 0..n, the set of everything for subset DPs over n items
+    pub fn full(n: usize) -> Self {
+        (0..n).collect()
+    }
+
+    // true when the element wasn't there yet, panics past CAPACITY
+    pub fn insert(&mut self, element: usize) -> bool {
+        assert!(element < Self::CAPACITY, "{} doesn't fit into a set of {} bits", element, Self::CAPACITY);
+        let (word, bit) = position(element);
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        added
+    }
+
+    pub fn remove(&mut self, element: usize) -> bool {
+        let present = self.contains(element);
+        if present {
+            let (word, bit) = position(element);
+            self.words[word] &= !bit;
+        }
+        present
+    }
+
+    // a copy with the element added, handy for the next state of a DP
+    pub fn with(mut self, element: usize) -> Self {
+        self.insert(element);
+        self
+    }
+
+    pub fn contains(&self, element: usize) -> bool {
+        contains(&self.words, element)
+    }
+
+    pub fn len(&self) -> usize {
+        count(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words = [0; WORDS];
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.words)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        is_subset(&self.words, &other.words)
+    }
+
+    fn combine(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        FixedBitSet { words: std::array::from_fn(|i| op(self.words[i], other.words[i])) }
+    }
+}
+
+impl<const WORDS: usize> Default for FixedBitSet<WORDS> {
+    fn default() -> Self {
+        FixedBitSet::new()
+    }
+}
+
+impl<const WORDS: usize> Debug for FixedBitSet<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for FixedBitSet<WORDS> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = FixedBitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const WORDS: usize> Extend<usize> for FixedBitSet<WORDS> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+impl<'a, const WORDS: usize> IntoIterator for &'a FixedBitSet<WORDS> {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<const WORDS: usize> BitOr for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(&other)
+    }
+}
+
+impl<const WORDS: usize> BitAnd for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.intersection(&other)
+    }
+}
+
+impl<const WORDS: usize> Sub for FixedBitSet<WORDS> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.difference(&other)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet { words: Vec::new() }
+    }
+
+    // room for 0..bits without growing
+    pub fn with_capacity(bits: usize) -> Self {
+        BitSet { words: vec![0; bits.div_ceil(WORD_BITS)] }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.words.len() * WORD_BITS
+    }
+
+    pub fn insert(&mut self, element: usize) -> bool {
+        let (word, bit) = position(element);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        added
+    }
+
+    pub fn remove(&mut self, element: usize) -> bool {
+        let present = self.contains(element);
+        if present {
+            let (word, bit) = position(element);
+            self.words[word] &= !bit;
+        }
+        present
+    }
+
+    pub fn contains(&self, element: usize) -> bool {
+        contains(&self.words, element)
+    }
+
+    pub fn len(&self) -> usize {
+        count(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.significant_words().is_empty()
+    }
+
+    // keeps the capacity, like HashSet::clear
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.words)
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        for (i, word) in self.words.iter_mut().enumerate() {
+            *word &= other.words.get(i).copied().unwrap_or(0);
+        }
+    }
+
+    pub fn difference_with(&mut self, other: &BitSet) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        let mut union = self.clone();
+        union.union_with(other);
+        union
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        let mut intersection = self.clone();
+        intersection.intersect_with(other);
+        intersection
+    }
+
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        let mut difference = self.clone();
+        difference.difference_with(other);
+        difference
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        is_subset(&self.words, &other.words)
+    }
+
+    // the words without the empty ones at the end, what Eq and Hash look at
+    fn significant_words(&self) -> &[u64] {
+        let len = self.words.iter().rposition(|&word| word != 0).map_or(0, |last| last + 1);
+        &self.words[..len]
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant_words() == other.significant_words()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant_words().hash(state);
+    }
+}
+
+impl Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        self.difference(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+    use super::*;
+    use crate::common::{graph::{Graph, Neighborhood}, random::Rng};
+
+    #[test]
+    fn test_basics() {
+        let mut set = BitSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(64));
+        assert!(set.insert(1000));
+        assert!(set.contains(64) && !set.contains(63) && !set.contains(5000));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 64, 1000]);
+        assert_eq!(format!("{:?}", set), "{3, 64, 1000}");
+        assert!(set.remove(1000));
+        assert!(!set.remove(1000));
+        assert!(!set.remove(1_000_000));
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.iter().next(), None);
+
+        let mut fixed = FixedBitSet::<2>::new();
+        assert!(fixed.insert(0) && fixed.insert(127));
+        assert!(!fixed.contains(128));
+        assert_eq!(fixed.iter().collect::<Vec<_>>(), vec![0, 127]);
+        assert_eq!(FixedBitSet::<2>::CAPACITY, 128);
+        assert_eq!(FixedBitSet::<1>::full(64).len(), 64);
+        assert_eq!(FixedBitSet::<1>::full(3), [0, 1, 2].into_iter().collect());
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn test_fixed_overflow() {
+        FixedBitSet::<1>::new().insert(64);
+    }
+
+    #[test]
+    fn test_equality_ignores_capacity() {
+        let small: BitSet = [1, 2].into_iter().collect();
+        let mut large = BitSet::with_capacity(1000);
+        large.extend([2, 1]);
+        large.insert(700);
+        large.remove(700);
+        assert_eq!(small, large);
+        let hashes: HashSet<BitSet> = [small, large].into_iter().collect();
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(BitSet::new(), BitSet::with_capacity(128));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut rng = Rng::new(47);
+        for _ in 0..200 {
+            let a: BTreeSet<usize> = (0..rng.gen_range(0..50)).map(|_| rng.gen_range(0..200)).collect();
+            let b: BTreeSet<usize> = (0..rng.gen_range(0..50)).map(|_| rng.gen_range(0..300)).collect();
+            let (set_a, set_b): (BitSet, BitSet) = (a.iter().copied().collect(), b.iter().copied().collect());
+            let sorted = |set: BitSet| set.iter().collect::<Vec<_>>();
+            assert_eq!(sorted(&set_a | &set_b), a.union(&b).copied().collect::<Vec<_>>());
+            assert_eq!(sorted(&set_a & &set_b), a.intersection(&b).copied().collect::<Vec<_>>());
+            assert_eq!(sorted(&set_a - &set_b), a.difference(&b).copied().collect::<Vec<_>>());
+            assert_eq!(set_a.is_subset(&set_b), a.is_subset(&b));
+            assert!(set_a.intersection(&set_b).is_subset(&set_a));
+            assert_eq!(set_a.len(), a.len());
+
+            let (fixed_a, fixed_b): (FixedBitSet<5>, FixedBitSet<5>) = (a.iter().copied().collect(), b.iter().copied().collect());
+            assert_eq!((fixed_a | fixed_b).iter().collect::<Vec<_>>(), sorted(&set_a | &set_b));
+            assert_eq!((fixed_a & fixed_b).iter().collect::<Vec<_>>(), sorted(&set_a & &set_b));
+            assert_eq!((fixed_a - fixed_b).iter().collect::<Vec<_>>(), sorted(&set_a - &set_b));
+            assert_eq!(fixed_a.is_subset(&fixed_b), a.is_subset(&b));
+        }
+    }
+
+    // flood fill of a grid, the bitset gives the same answer as a HashSet
+    #[test]
+    fn test_visited_set() {
+        let lines = ["..#....", ".##.##.", "...#...", "####.##", "......."];
+        let graph = Graph::from_lines(&lines, Neighborhood::Orthogonal, |&from, &to| (from == '.' && to == '.').then_some(()));
+        let start = graph.cell_id(0, 0).unwrap();
+        let mut visited = BitSet::with_capacity(graph.node_count());
+        let mut hashed = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        visited.insert(start);
+        hashed.insert(start);
+        while let Some(id) = queue.pop_front() {
+            for next in graph.neighbors(id) {
+                if visited.insert(next) {
+                    assert!(hashed.insert(next));
+                    queue.push_back(next);
+                }
+            }
+        }
+        assert_eq!(visited.len(), hashed.len());
+        assert_eq!(visited.len(), 6);
+        assert!(visited.iter().all(|id| hashed.contains(&id)));
+    }
+
+    // cheapest route through every city and back (Held-Karp), keyed by the cities seen
+    #[test]
+    fn test_subset_dp() {
+        let distances = [
+            [0, 2, 9, 10, 7],
+            [1, 0, 6, 4, 3],
+            [15, 7, 0, 8, 3],
+            [6, 3, 12, 0, 11],
+            [9, 7, 5, 6, 0]
+        ];
+        let n = distances.len();
+        let mut best: HashMap<(FixedBitSet<1>, usize), u32> = HashMap::new();
+        best.insert((FixedBitSet::new().with(0), 0), 0);
+        for size in 1..n {
+            let states: Vec<_> = best.iter().filter(|((seen, _), _)| seen.len() == size).map(|(&key, &cost)| (key, cost)).collect();
+            for ((seen, at), cost) in states {
+                for next in (0..n).filter(|&next| !seen.contains(next)) {
+                    let entry = best.entry((seen.with(next), next)).or_insert(u32::MAX);
+                    *entry = (*entry).min(cost + distances[at][next]);
+                }
+            }
+        }
+        let all = FixedBitSet::full(n);
+        let tour = (1..n).map(|last| best[&(all, last)] + distances[last][0]).min().unwrap();
+
+        // every order of the other four cities
+        let mut brute_force = u32::MAX;
+        for order in 0..4u32.pow(4) {
+            let cities: Vec<usize> = (0..4).map(|i| (order / 4u32.pow(i) % 4) as usize + 1).collect();
+            if cities.iter().collect::<HashSet<_>>().len() == 4 {
+                let route = [0].into_iter().chain(cities).chain([0]).collect::<Vec<_>>();
+                brute_force = brute_force.min(route.windows(2).map(|pair| distances[pair[0]][pair[1]]).sum());
+            }
+        }
+        assert_eq!(tour, brute_force);
+    }
+}
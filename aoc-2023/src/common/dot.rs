@@ -0,0 +1,184 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, ops::Deref, rc::Rc};
+
+// Builder for Graphviz DOT text, for dumping the internals of the linked structures.
+// Paste the output into `dot -Tsvg` (or any online viewer) to see the nodes and their links.
+//
+// Nodes are usually identified by their address, id_of hands out n0, n1, ... in the order
+// the addresses are first seen, so the same structure always gives the same text and the
+// output can be compared in tests. Links to a node that was never declared still show up,
+// graphviz draws them to a bare node, which is exactly what a broken link looks like.
+
+pub struct Dot {
+    lines: Vec<String>,
+    ids: HashMap<usize, usize>
+}
+
+impl Dot {
+    pub fn new(name: &str) -> Self {
+        Dot {
+            lines: vec![
+                format!("digraph {} {{", name),
+                "    rankdir=LR;".to_owned(),
+                "    node [shape=box];".to_owned()
+            ],
+            ids: HashMap::new()
+        }
+    }
+
+    pub fn id_of<P: ?Sized>(&mut self, ptr: *const P) -> String {
+        let next = self.ids.len();
+        let id = *self.ids.entry(ptr as *const () as usize).or_insert(next);
+        format!("n{}", id)
+    }
+
+    pub fn node(&mut self, id: &str, label: &str) {
+        self.lines.push(format!("    {} [label=\"{}\"];", id, escape(label)));
+    }
+
+    // attrs go into the brackets as they are, e.g. "shape=plaintext"
+    pub fn node_with(&mut self, id: &str, label: &str, attrs: &str) {
+        self.lines.push(format!("    {} [label=\"{}\", {}];", id, escape(label), attrs));
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, label: &str) {
+        if label.is_empty() {
+            self.lines.push(format!("    {} -> {};", from, to));
+        } else {
+            self.lines.push(format!("    {} -> {} [label=\"{}\"];", from, to, escape(label)));
+        }
+    }
+
+    pub fn edge_with(&mut self, from: &str, to: &str, label: &str, attrs: &str) {
+        self.lines.push(format!("    {} -> {} [label=\"{}\", {}];", from, to, escape(label), attrs));
+    }
+
+    // a label without a box, for things like "head" that point into the structure
+    pub fn marker(&mut self, id: &str, label: &str) {
+        self.node_with(id, label, "shape=plaintext");
+    }
+
+    pub fn finish(mut self) -> String {
+        self.lines.push("}".to_owned());
+        self.lines.join("\n") + "\n"
+    }
+}
+
+// The singly linked stacks: a head marker and the nodes with their next links. N is what the
+// list keeps its nodes behind (Box, Rc, Arc), the ids come from the nodes it points to.
+// label gets a node and the Debug text of its element and returns what goes into the box
+pub fn singly_linked<'a, N: Deref + 'a, E: Debug + 'a>(
+    name: &str,
+    head: Option<&'a N>,
+    elem: impl Fn(&'a N) -> &'a E,
+    next: impl Fn(&'a N) -> Option<&'a N>,
+    label: impl Fn(&'a N, String) -> String
+) -> String {
+    let mut dot = Dot::new(name);
+    dot.marker("head", "head");
+    if let Some(first) = head {
+        let first_id = dot.id_of(&**first);
+        dot.edge("head", &first_id, "");
+    }
+    let mut cur = head;
+    while let Some(node) = cur {
+        let id = dot.id_of(&**node);
+        dot.node(&id, &label(node, format!("{:?}", elem(node))));
+        if let Some(next) = next(node) {
+            let next_id = dot.id_of(&**next);
+            dot.edge(&id, &next_id, "next");
+        }
+        cur = next(node);
+    }
+    dot.finish()
+}
+
+type Link<N> = Option<Rc<RefCell<N>>>;
+
+// The Rc<RefCell> doubly linked lists all draw the same way: the nodes from head to tail with
+// their next links, dashed prev links back, and head and tail markers. The closures pick the
+// element and the two links out of a node. The strong count in a label is how many links
+// point at the node, the walk's own clone is already taken off
+pub fn doubly_linked<N, E: Debug>(
+    name: &str,
+    head: &Link<N>,
+    tail: &Link<N>,
+    elem: impl Fn(&N) -> &E,
+    next: impl Fn(&N) -> &Link<N>,
+    prev: impl Fn(&N) -> &Link<N>
+) -> String {
+    let mut dot = Dot::new(name);
+    let nodes = std::iter::successors(head.clone(), |node| next(&node.borrow()).clone());
+    for node in nodes {
+        let id = dot.id_of(Rc::as_ptr(&node));
+        let borrowed = node.borrow();
+        dot.node(&id, &format!("{:?}\nstrong: {}", elem(&borrowed), Rc::strong_count(&node) - 1));
+        if let Some(next) = next(&borrowed) {
+            let next_id = dot.id_of(Rc::as_ptr(next));
+            dot.edge(&id, &next_id, "next");
+        }
+        if let Some(prev) = prev(&borrowed) {
+            let prev_id = dot.id_of(Rc::as_ptr(prev));
+            dot.edge_with(&id, &prev_id, "prev", "style=dashed");
+        }
+    }
+    for (marker, end) in [("head", head), ("tail", tail)] {
+        if let Some(node) = end {
+            let id = dot.id_of(Rc::as_ptr(node));
+            dot.marker(marker, marker);
+            dot.edge(marker, &id, "");
+        }
+    }
+    dot.finish()
+}
+
+// labels are double quoted strings in DOT, \n inside them is a line break
+pub fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output() {
+        let first = 1;
+        let second = 2;
+        let mut dot = Dot::new("example");
+        let (a, b) = (dot.id_of(&first), dot.id_of(&second));
+        assert_eq!(dot.id_of(&first), a);
+        dot.marker("head", "head");
+        dot.edge("head", &a, "");
+        dot.node(&a, "\"one\"\nstrong: 1");
+        dot.node(&b, "two");
+        dot.edge(&a, &b, "next");
+        dot.edge_with(&b, &a, "prev", "style=dashed");
+        assert_eq!(dot.finish(), concat!(
+            "digraph example {\n",
+            "    rankdir=LR;\n",
+            "    node [shape=box];\n",
+            "    head [label=\"head\", shape=plaintext];\n",
+            "    head -> n0;\n",
+            "    n0 [label=\"\\\"one\\\"\\nstrong: 1\"];\n",
+            "    n1 [label=\"two\"];\n",
+            "    n0 -> n1 [label=\"next\"];\n",
+            "    n1 -> n0 [label=\"prev\", style=dashed];\n",
+            "}\n"
+        ));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r"a\b"), r"a\\b");
+        assert_eq!(escape("plain"), "plain");
+    }
+}
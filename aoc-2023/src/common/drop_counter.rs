@@ -0,0 +1,149 @@
+use std::{rc::Rc, cell::RefCell, cmp::Ordering, fmt::{Debug, Display}, ops::Deref};
+
+// Test helper for finding leaks and double drops in the lists.
+// Every value created through a DropCounter gets its own id, the counter remembers
+// which ids were created and in which order they were dropped, so a test can check that
+// everything it put into a list was dropped exactly once - nothing leaked in an Rc cycle
+// and nothing was dropped twice.
+
+#[derive(Default)]
+struct Counts {
+    created: usize,
+    // ids in the order they were dropped
+    dropped: Vec<usize>
+}
+
+#[derive(Clone, Default)]
+pub struct DropCounter {
+    counts: Rc<RefCell<Counts>>
+}
+
+impl DropCounter {
+    pub fn new() -> Self {
+        DropCounter::default()
+    }
+
+    pub fn track<T>(&self, value: T) -> Tracked<T> {
+        let mut counts = self.counts.borrow_mut();
+        let id = counts.created;
+        counts.created += 1;
+        Tracked { value, id, counts: self.counts.clone() }
+    }
+
+    pub fn created(&self) -> usize {
+        self.counts.borrow().created
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.counts.borrow().dropped.len()
+    }
+
+    pub fn alive(&self) -> usize {
+        self.created() - self.dropped()
+    }
+
+    pub fn dropped_ids(&self) -> Vec<usize> {
+        self.counts.borrow().dropped.clone()
+    }
+
+    pub fn assert_all_dropped_once(&self) {
+        let counts = self.counts.borrow();
+        let mut dropped = counts.dropped.clone();
+        dropped.sort_unstable();
+        let expected: Vec<usize> = (0..counts.created).collect();
+        assert_eq!(dropped, expected, "every tracked value has to be dropped exactly once");
+    }
+}
+
+// the value is reachable through Deref and all the comparison/formatting traits just look at it
+pub struct Tracked<T> {
+    value: T,
+    id: usize,
+    counts: Rc<RefCell<Counts>>
+}
+
+impl<T> Tracked<T> {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        let mut counts = self.counts.borrow_mut();
+        assert!(!counts.dropped.contains(&self.id), "value {} dropped twice", self.id);
+        counts.dropped.push(self.id);
+    }
+}
+
+// a clone is a new value, it gets a new id and has to be dropped on its own
+impl<T: Clone> Clone for Tracked<T> {
+    fn clone(&self) -> Self {
+        DropCounter { counts: self.counts.clone() }.track(self.value.clone())
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: PartialEq> PartialEq for Tracked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Tracked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Display> Display for Tracked<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: Debug> Debug for Tracked<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let counter = DropCounter::new();
+        let first = counter.track(1);
+        let second = counter.track(2);
+        let clone = second.clone();
+        assert_eq!((first.id(), second.id(), clone.id()), (0, 1, 2));
+        assert_eq!(*clone, 2);
+        assert_eq!(counter.alive(), 3);
+
+        drop(second);
+        drop(first);
+        assert_eq!(counter.dropped_ids(), vec![1, 0]);
+        assert_eq!(counter.alive(), 1);
+        drop(clone);
+        assert_eq!(counter.created(), 3);
+        assert_eq!(counter.dropped(), 3);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    #[should_panic(expected = "dropped exactly once")]
+    fn test_detects_leak() {
+        let counter = DropCounter::new();
+        std::mem::forget(counter.track("leaked"));
+        counter.assert_all_dropped_once();
+    }
+}
@@ -0,0 +1,401 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, VecDeque}, ops::{Add, Sub}};
+
+use super::{Graph, NodeId};
+
+// Max flow between two nodes and the smallest cut of the whole graph. Edge weights are the
+// capacities. An undirected edge can carry its capacity in either direction.
+//
+// max_flow_dinic and max_flow_edmonds_karp give the same answer, Dinic's is the faster one
+// (it pushes flow along all shortest paths of a length at once), Edmonds-Karp the simpler one.
+// By max-flow min-cut, the flow also gives the cheapest set of edges whose removal separates
+// the source from the sink.
+//
+// min_cut is Stoer-Wagner: the cheapest way to split an undirected graph in two, without
+// choosing a source and a sink - the "which three wires to cut" kind of puzzle.
+
+// capacities have to start at Default (0 for the numbers), add up and subtract
+pub trait Capacity: Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Default {}
+
+impl<C: Copy + Ord + Add<Output = C> + Sub<Output = C> + Default> Capacity for C {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxFlow<C> {
+    pub value: C,
+    // nodes still reachable from the source once the flow is maxed out, the rest is the sink side
+    pub source_side: Vec<bool>,
+    // edges going from the source side to the sink side, their capacities add up to the value
+    pub cut_edges: Vec<(NodeId, NodeId)>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlobalCut<C> {
+    pub weight: C,
+    // the nodes on one side of the cut, sorted, everything else is on the other side
+    pub side: Vec<NodeId>,
+    pub cut_edges: Vec<(NodeId, NodeId)>
+}
+
+// The residual network. Arcs are stored in pairs, arc ^ 1 is the reverse of arc, and cap
+// is what is left of the capacity - pushing flow along an arc moves capacity to its reverse
+struct Network<C> {
+    to: Vec<NodeId>,
+    cap: Vec<C>,
+    arcs_from: Vec<Vec<usize>>
+}
+
+impl<C: Capacity> Network<C> {
+    fn new<N>(graph: &Graph<N, C>) -> Self {
+        let mut network = Network { to: Vec::new(), cap: Vec::new(), arcs_from: vec![Vec::new(); graph.node_count()] };
+        for (from, to, &cap) in graph.edges() {
+            // the reverse arc of an undirected edge has the full capacity as well
+            let reverse_cap = if graph.is_directed() { C::default() } else { cap };
+            network.add_arc(from, to, cap);
+            network.add_arc(to, from, reverse_cap);
+        }
+        network
+    }
+
+    fn add_arc(&mut self, from: NodeId, to: NodeId, cap: C) {
+        self.arcs_from[from].push(self.to.len());
+        self.to.push(to);
+        self.cap.push(cap);
+    }
+
+    fn push(&mut self, arc: usize, amount: C) {
+        self.cap[arc] = self.cap[arc] - amount;
+        self.cap[arc ^ 1] = self.cap[arc ^ 1] + amount;
+    }
+
+    fn has_room(&self, arc: usize) -> bool {
+        self.cap[arc] > C::default()
+    }
+
+    // BFS distance from the source over arcs with room left
+    fn levels(&self, source: NodeId) -> Vec<Option<usize>> {
+        let mut levels = vec![None; self.arcs_from.len()];
+        levels[source] = Some(0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(id) = queue.pop_front() {
+            for &arc in &self.arcs_from[id] {
+                let to = self.to[arc];
+                if self.has_room(arc) && levels[to].is_none() {
+                    levels[to] = levels[id].map(|level| level + 1);
+                    queue.push_back(to);
+                }
+            }
+        }
+        levels
+    }
+
+    fn into_max_flow<N>(self, graph: &Graph<N, C>, source: NodeId, value: C) -> MaxFlow<C> {
+        let source_side: Vec<bool> = self.levels(source).iter().map(Option::is_some).collect();
+        let mut cut_edges = Vec::new();
+        for (from, to, _) in graph.edges() {
+            if source_side[from] && !source_side[to] {
+                cut_edges.push((from, to));
+            } else if !graph.is_directed() && source_side[to] && !source_side[from] {
+                cut_edges.push((to, from));
+            }
+        }
+        MaxFlow { value, source_side, cut_edges }
+    }
+}
+
+impl<N, C: Capacity> Graph<N, C> {
+    pub fn max_flow_dinic(&self, source: NodeId, sink: NodeId) -> MaxFlow<C> {
+        assert_ne!(source, sink, "the source has to be different from the sink");
+        let mut network = Network::new(self);
+        let mut value = C::default();
+        loop {
+            let mut levels = network.levels(source);
+            if levels[sink].is_none() {
+                break;
+            }
+            // the next arc to try from every node, arcs that lead nowhere aren't tried again
+            let mut next_arc = vec![0; self.node_count()];
+            let mut path: Vec<usize> = Vec::new();
+            let mut id = source;
+            loop {
+                if id == sink {
+                    let bottleneck = path.iter().map(|&arc| network.cap[arc]).min().unwrap();
+                    for &arc in &path {
+                        network.push(arc, bottleneck);
+                    }
+                    value = value + bottleneck;
+                    path.clear();
+                    id = source;
+                    continue;
+                }
+                let arcs = &network.arcs_from[id];
+                while next_arc[id] < arcs.len() {
+                    let arc = arcs[next_arc[id]];
+                    let to = network.to[arc];
+                    if network.has_room(arc) && levels[to].is_some() && levels[to] == levels[id].map(|level| level + 1) {
+                        break;
+                    }
+                    next_arc[id] += 1;
+                }
+                if let Some(&arc) = arcs.get(next_arc[id]) {
+                    path.push(arc);
+                    id = network.to[arc];
+                    continue;
+                }
+                // a dead end, nothing more goes through here in this phase
+                let Some(arc) = path.pop() else { break };
+                levels[id] = None;
+                id = network.to[arc ^ 1];
+                next_arc[id] += 1;
+            }
+        }
+        network.into_max_flow(self, source, value)
+    }
+
+    pub fn max_flow_edmonds_karp(&self, source: NodeId, sink: NodeId) -> MaxFlow<C> {
+        assert_ne!(source, sink, "the source has to be different from the sink");
+        let mut network = Network::new(self);
+        let mut value = C::default();
+        loop {
+            // shortest augmenting path by BFS, remembering the arc every node was reached by
+            let mut reached_by: Vec<Option<usize>> = vec![None; self.node_count()];
+            let mut queue = VecDeque::from([source]);
+            while let Some(id) = queue.pop_front() {
+                for &arc in &network.arcs_from[id] {
+                    let to = network.to[arc];
+                    if network.has_room(arc) && to != source && reached_by[to].is_none() {
+                        reached_by[to] = Some(arc);
+                        queue.push_back(to);
+                    }
+                }
+            }
+            if reached_by[sink].is_none() {
+                break;
+            }
+            let path: Vec<usize> = std::iter::successors(reached_by[sink], |&arc| reached_by[network.to[arc ^ 1]]).collect();
+            let bottleneck = path.iter().map(|&arc| network.cap[arc]).min().unwrap();
+            for &arc in &path {
+                network.push(arc, bottleneck);
+            }
+            value = value + bottleneck;
+        }
+        network.into_max_flow(self, source, value)
+    }
+
+    // Stoer-Wagner. Every phase orders the nodes by how strongly they are connected to the
+    // ones before them, the last node's connection is a cut, and the last two get merged.
+    // None when there are fewer than two nodes, there is nothing to cut then
+    pub fn min_cut(&self) -> Option<GlobalCut<C>> {
+        assert!(!self.is_directed(), "the global min cut is for undirected graphs");
+        let n = self.node_count();
+        if n < 2 {
+            return None;
+        }
+        // merged nodes keep the id of one of their members
+        let mut weights: Vec<HashMap<NodeId, C>> = vec![HashMap::new(); n];
+        for (from, to, &weight) in self.edges() {
+            if from != to {
+                for (a, b) in [(from, to), (to, from)] {
+                    let total = weights[a].entry(b).or_default();
+                    *total = *total + weight;
+                }
+            }
+        }
+        let mut members: Vec<Vec<NodeId>> = (0..n).map(|id| vec![id]).collect();
+        let mut active: Vec<NodeId> = (0..n).collect();
+        let mut best: Option<(C, Vec<NodeId>)> = None;
+        while active.len() > 1 {
+            // connection of every node to the ones already taken, the heap has stale entries
+            let mut connection: HashMap<NodeId, C> = active.iter().map(|&id| (id, C::default())).collect();
+            let mut heap: BinaryHeap<(C, Reverse<NodeId>)> = active.iter().map(|&id| (C::default(), Reverse(id))).collect();
+            let mut taken = Vec::with_capacity(active.len());
+            let mut cut_of_phase = C::default();
+            while let Some((strength, Reverse(id))) = heap.pop() {
+                if connection.get(&id) != Some(&strength) {
+                    continue;
+                }
+                connection.remove(&id);
+                taken.push(id);
+                cut_of_phase = strength;
+                for (&to, &weight) in &weights[id] {
+                    if let Some(strength) = connection.get_mut(&to) {
+                        *strength = *strength + weight;
+                        heap.push((*strength, Reverse(to)));
+                    }
+                }
+            }
+            let (last, before_last) = (taken[taken.len() - 1], taken[taken.len() - 2]);
+            if best.as_ref().is_none_or(|(weight, _)| cut_of_phase < *weight) {
+                best = Some((cut_of_phase, members[last].clone()));
+            }
+            // merge the last node into the one before it
+            let merged = std::mem::take(&mut weights[last]);
+            for (to, weight) in merged {
+                weights[to].remove(&last);
+                if to != before_last {
+                    let total = weights[before_last].get(&to).copied().unwrap_or_default() + weight;
+                    weights[before_last].insert(to, total);
+                    weights[to].insert(before_last, total);
+                }
+            }
+            let moved = std::mem::take(&mut members[last]);
+            members[before_last].extend(moved);
+            active.retain(|&id| id != last);
+        }
+        let (weight, mut side) = best?;
+        side.sort_unstable();
+        let mut on_side = vec![false; n];
+        for &id in &side {
+            on_side[id] = true;
+        }
+        let cut_edges = self.edges().filter(|&(from, to, _)| on_side[from] != on_side[to]).map(|(from, to, _)| (from, to)).collect();
+        Some(GlobalCut { weight, side, cut_edges })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{graph::{Direction, Graph, NodeId}, random::Rng};
+
+    fn network(direction: Direction, n: usize, edges: &[(NodeId, NodeId, u32)]) -> Graph<NodeId, u32> {
+        let mut graph = Graph::new(direction);
+        for id in 0..n {
+            graph.add_node(id);
+        }
+        for &(from, to, cap) in edges {
+            graph.add_edge(from, to, cap);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_max_flow() {
+        // the textbook network, source 0 and sink 5
+        let graph = network(Direction::Directed, 6, &[
+            (0, 1, 16), (0, 2, 13), (1, 3, 12), (2, 1, 4), (2, 4, 14),
+            (3, 2, 9), (3, 5, 20), (4, 3, 7), (4, 5, 4)
+        ]);
+        for flow in [graph.max_flow_dinic(0, 5), graph.max_flow_edmonds_karp(0, 5)] {
+            assert_eq!(flow.value, 23);
+            assert_eq!(flow.source_side, vec![true, true, true, false, true, false]);
+            assert_eq!(flow.cut_edges, vec![(1, 3), (4, 3), (4, 5)]);
+        }
+        // nothing goes back from the sink
+        assert_eq!(graph.max_flow_dinic(5, 0).value, 0);
+        assert_eq!(graph.max_flow_dinic(5, 0).cut_edges, vec![]);
+    }
+
+    #[test]
+    fn test_undirected_max_flow() {
+        // a square with a diagonal, every edge can carry 1 in either direction
+        let graph = network(Direction::Undirected, 4, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 0, 1), (1, 3, 1)]);
+        let flow = graph.max_flow_dinic(0, 2);
+        assert_eq!(flow.value, 2);
+        assert_eq!(flow.cut_edges.len(), 2);
+        assert_eq!(graph.max_flow_edmonds_karp(1, 3).value, 3);
+    }
+
+    // the wiring diagram from day 25 of 2023, three wires split it in two
+    const WIRING: &str = "\
+jqt: rhn xhk nvd
+rsh: frs pzl lsr
+xhk: hfx
+cmg: qnr nvd lhk bvb
+rhn: xhk bvb hfx
+bvb: xhk hfx
+pzl: lsr hfx nvd
+qnr: nvd
+ntq: jqt hfx bvb xhk
+nvd: lhk
+lsr: lhk
+rzs: qnr cmg lsr rsh
+frs: qnr lhk lsr";
+
+    #[test]
+    fn test_three_wires() {
+        let wires = WIRING.lines().flat_map(|line| {
+            let (from, to) = line.split_once(": ").unwrap();
+            to.split(' ').map(move |to| (from, to, 1u32))
+        });
+        let (graph, ids) = Graph::from_edges(Direction::Undirected, wires);
+        let cut = graph.min_cut().unwrap();
+        assert_eq!(cut.weight, 3);
+        assert_eq!(cut.side.len() * (graph.node_count() - cut.side.len()), 54);
+        let mut wires: Vec<(&str, &str)> = cut.cut_edges.iter()
+            .map(|&(from, to)| {
+                let (from, to) = (*graph.node(from), *graph.node(to));
+                (from.min(to), from.max(to))
+            })
+            .collect();
+        wires.sort();
+        assert_eq!(wires, vec![("bvb", "cmg"), ("hfx", "pzl"), ("jqt", "nvd")]);
+
+        // the same three wires, found as a flow between two nodes on different sides
+        let flow = graph.max_flow_dinic(ids["hfx"], ids["cmg"]);
+        assert_eq!((flow.value, flow.cut_edges.len()), (3, 3));
+    }
+
+    #[test]
+    fn test_weighted_min_cut() {
+        // the example from the Stoer-Wagner paper, the cheapest cut takes {2, 3, 6, 7} off
+        let graph = network(Direction::Undirected, 8, &[
+            (0, 1, 2), (0, 4, 3), (1, 2, 3), (1, 4, 2), (1, 5, 2), (2, 3, 4),
+            (2, 6, 2), (3, 6, 2), (3, 7, 2), (4, 5, 3), (5, 6, 1), (6, 7, 3)
+        ]);
+        let cut = graph.min_cut().unwrap();
+        assert_eq!(cut.weight, 4);
+        let other_side: Vec<NodeId> = graph.node_ids().filter(|id| !cut.side.contains(id)).collect();
+        assert!(cut.side == vec![2, 3, 6, 7] || other_side == vec![2, 3, 6, 7], "{:?}", cut);
+        assert_eq!(cut.cut_edges, vec![(1, 2), (5, 6)]);
+
+        assert_eq!(network(Direction::Undirected, 1, &[]).min_cut(), None);
+        // apart already, nothing has to be cut
+        assert_eq!(network(Direction::Undirected, 3, &[(0, 1, 5)]).min_cut().unwrap().weight, 0);
+    }
+
+    // brute force over every split of small random graphs
+    #[test]
+    fn test_random_graphs() {
+        let mut rng = Rng::new(45);
+        for round in 0..300 {
+            let n = rng.gen_range(2..8);
+            let direction = if round % 2 == 0 { Direction::Directed } else { Direction::Undirected };
+            let edges: Vec<_> = (0..rng.gen_range(0..3 * n))
+                .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n), rng.gen_range(0..10) as u32))
+                .collect();
+            let graph = network(direction, n, &edges);
+            let cut_weight = |side: u32| -> u32 {
+                let on_side = |id: NodeId| side & (1 << id) != 0;
+                graph.edges().map(|(from, to, &cap)| {
+                    match (on_side(from), on_side(to)) {
+                        (true, false) => cap,
+                        (false, true) if direction == Direction::Undirected => cap,
+                        _ => 0
+                    }
+                }).sum()
+            };
+            let (source, sink) = (0, n - 1);
+            let best_st_cut = (0..1u32 << n)
+                .filter(|side| side & (1 << source) != 0 && side & (1 << sink) == 0)
+                .map(cut_weight)
+                .min()
+                .unwrap();
+            let dinic = graph.max_flow_dinic(source, sink);
+            let edmonds_karp = graph.max_flow_edmonds_karp(source, sink);
+            assert_eq!(dinic.value, best_st_cut, "{:?} {:?}", direction, edges);
+            assert_eq!(edmonds_karp.value, best_st_cut, "{:?} {:?}", direction, edges);
+            let source_side = dinic.source_side.iter().enumerate().fold(0, |side, (id, &on)| side | (on as u32) << id);
+            assert_eq!(cut_weight(source_side), dinic.value, "{:?} {:?}", direction, edges);
+            assert_eq!(dinic.cut_edges.len(), graph.edges()
+                .filter(|&(from, to, _)| dinic.source_side[from] != dinic.source_side[to])
+                .filter(|&(from, _, _)| direction == Direction::Undirected || dinic.source_side[from])
+                .count());
+
+            if direction == Direction::Undirected {
+                let best_cut = (1..(1u32 << n) - 1).map(cut_weight).min().unwrap();
+                let cut = graph.min_cut().unwrap();
+                assert_eq!(cut.weight, best_cut, "{:?}", edges);
+                let side = cut.side.iter().fold(0, |side, &id| side | 1 << id);
+                assert_eq!(cut_weight(side), best_cut, "{:?}", edges);
+            }
+        }
+    }
+}
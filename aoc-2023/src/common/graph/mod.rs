@@ -0,0 +1,397 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash, ops::Range};
+
+use super::dot::Dot;
+
+pub mod flow;
+pub mod topology;
+
+// Adjacency list graph where nodes are plain indices into a Vec.
+// Building one is just pushing nodes and edges, no Rc<RefCell<...>> juggling, a node is
+// identified by its NodeId instead of its address, and every edge can carry a weight
+// (use () when there is nothing to carry, that's the default).
+//
+// An undirected edge is stored in the adjacency lists of both of its ends, but counted
+// and listed by edges() only once. Node ids are handed out in insertion order and stay
+// valid forever, nodes and edges can't be removed.
+
+pub type NodeId = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Directed,
+    Undirected
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge<W> {
+    pub to: NodeId,
+    pub weight: W
+}
+
+#[derive(Clone, Debug)]
+pub struct Graph<N, W = ()> {
+    direction: Direction,
+    nodes: Vec<N>,
+    adjacency: Vec<Vec<Edge<W>>>,
+    edge_count: usize
+}
+
+// which cells of a grid count as neighbors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    // up, down, left and right
+    Orthogonal,
+    // the diagonals too, like the symbols around an engine part in day 3
+    WithDiagonals
+}
+
+impl Neighborhood {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Neighborhood::Orthogonal => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+            Neighborhood::WithDiagonals => &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+        }
+    }
+}
+
+// node data of a graph built from a grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell<T> {
+    pub row: usize,
+    pub col: usize,
+    pub value: T
+}
+
+impl<N, W> Graph<N, W> {
+    pub fn new(direction: Direction) -> Self {
+        Graph { direction, nodes: Vec::new(), adjacency: Vec::new(), edge_count: 0 }
+    }
+
+    pub fn directed() -> Self {
+        Graph::new(Direction::Directed)
+    }
+
+    pub fn undirected() -> Self {
+        Graph::new(Direction::Undirected)
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.direction == Direction::Directed
+    }
+
+    pub fn add_node(&mut self, data: N) -> NodeId {
+        self.nodes.push(data);
+        self.adjacency.push(Vec::new());
+        self.nodes.len() - 1
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn node_ids(&self) -> Range<NodeId> {
+        0..self.nodes.len()
+    }
+
+    // panics on an id that isn't in the graph, like indexing a Vec
+    pub fn node(&self, id: NodeId) -> &N {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut N {
+        &mut self.nodes[id]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> {
+        self.nodes.iter().enumerate()
+    }
+
+    // first node whose data matches
+    pub fn find_node(&self, matches: impl FnMut(&N) -> bool) -> Option<NodeId> {
+        self.nodes.iter().position(matches)
+    }
+
+    // outgoing edges, for an undirected graph all edges touching the node
+    pub fn edges_from(&self, id: NodeId) -> &[Edge<W>] {
+        &self.adjacency[id]
+    }
+
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.adjacency[id].iter().map(|edge| edge.to)
+    }
+
+    pub fn degree(&self, id: NodeId) -> usize {
+        self.adjacency[id].len()
+    }
+
+    // every edge once as (from, to, weight), undirected ones from their smaller end
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &W)> {
+        let directed = self.is_directed();
+        self.adjacency.iter().enumerate().flat_map(move |(from, edges)| {
+            edges.iter()
+                .filter(move |edge| directed || from <= edge.to)
+                .map(move |edge| (from, edge.to, &edge.weight))
+        })
+    }
+
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.weight(from, to).is_some()
+    }
+
+    // weight of the first edge between the nodes, if there are parallel ones
+    pub fn weight(&self, from: NodeId, to: NodeId) -> Option<&W> {
+        self.adjacency[from].iter().find(|edge| edge.to == to).map(|edge| &edge.weight)
+    }
+
+    // nodes reachable from start in depth first pre-order
+    pub fn dfs(&self, start: NodeId) -> Vec<NodeId> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if visited[id] {
+                continue;
+            }
+            visited[id] = true;
+            order.push(id);
+            // reversed, so the first neighbor is popped first
+            stack.extend(self.adjacency[id].iter().rev().map(|edge| edge.to).filter(|&to| !visited[to]));
+        }
+        order
+    }
+
+    // nodes reachable from start, closest first
+    pub fn bfs(&self, start: NodeId) -> Vec<NodeId> {
+        let mut visited = vec![false; self.nodes.len()];
+        visited[start] = true;
+        let mut order = vec![start];
+        let mut i = 0;
+        while i < order.len() {
+            for edge in &self.adjacency[order[i]] {
+                if !visited[edge.to] {
+                    visited[edge.to] = true;
+                    order.push(edge.to);
+                }
+            }
+            i += 1;
+        }
+        order
+    }
+}
+
+impl<N, W: Clone> Graph<N, W> {
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: W) {
+        assert!(from < self.nodes.len() && to < self.nodes.len(), "edge {} -> {} between nodes that don't exist", from, to);
+        if self.direction == Direction::Undirected && from != to {
+            self.adjacency[to].push(Edge { to: from, weight: weight.clone() });
+        }
+        self.adjacency[from].push(Edge { to, weight });
+        self.edge_count += 1;
+    }
+
+    // same nodes with every edge turned around, an undirected graph stays as it is
+    pub fn reversed(&self) -> Self where N: Clone {
+        let mut reversed = Graph::new(self.direction);
+        for data in &self.nodes {
+            reversed.add_node(data.clone());
+        }
+        for (from, to, weight) in self.edges() {
+            reversed.add_edge(to, from, weight.clone());
+        }
+        reversed
+    }
+}
+
+impl<K: Eq + Hash + Clone, W: Clone> Graph<K, W> {
+    // Nodes are the keys that show up in the edges, in the order they first show up.
+    // The map says which node every key ended up as
+    pub fn from_edges(direction: Direction, edges: impl IntoIterator<Item = (K, K, W)>) -> (Self, HashMap<K, NodeId>) {
+        let mut graph = Graph::new(direction);
+        let mut ids = HashMap::new();
+        for (from, to, weight) in edges {
+            let from = *ids.entry(from).or_insert_with_key(|key| graph.add_node(key.clone()));
+            let to = *ids.entry(to).or_insert_with_key(|key| graph.add_node(key.clone()));
+            graph.add_edge(from, to, weight);
+        }
+        (graph, ids)
+    }
+}
+
+impl<K: Eq + Hash + Clone> Graph<K> {
+    pub fn from_pairs(direction: Direction, edges: impl IntoIterator<Item = (K, K)>) -> (Self, HashMap<K, NodeId>) {
+        Graph::from_edges(direction, edges.into_iter().map(|(from, to)| (from, to, ())))
+    }
+}
+
+impl<T: Clone, W: Clone> Graph<Cell<T>, W> {
+    // One node per cell, the node of (row, col) is row * width + col. For every cell and every
+    // neighbor of it inside the grid, edge decides if there is an edge from the cell to the
+    // neighbor and what it weighs. The graph is directed since the rule doesn't have to be
+    // symmetric (think "can only climb one step up").
+    pub fn from_grid(rows: &[Vec<T>], neighborhood: Neighborhood, mut edge: impl FnMut(&T, &T) -> Option<W>) -> Self {
+        let width = rows.first().map_or(0, |row| row.len());
+        assert!(rows.iter().all(|row| row.len() == width), "all rows of a grid need the same length");
+        let mut graph = Graph::directed();
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, value) in cells.iter().enumerate() {
+                graph.add_node(Cell { row, col, value: value.clone() });
+            }
+        }
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, value) in cells.iter().enumerate() {
+                for &(row_offset, col_offset) in neighborhood.offsets() {
+                    let (Some(to_row), Some(to_col)) = (row.checked_add_signed(row_offset), col.checked_add_signed(col_offset)) else {
+                        continue;
+                    };
+                    if to_row >= rows.len() || to_col >= width {
+                        continue;
+                    }
+                    if let Some(weight) = edge(value, &rows[to_row][to_col]) {
+                        graph.add_edge(row * width + col, to_row * width + to_col, weight);
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    // the node of a cell, for a graph built by from_grid
+    pub fn cell_id(&self, row: usize, col: usize) -> Option<NodeId> {
+        let width = self.nodes.iter().take_while(|cell| cell.row == 0).count();
+        let id = row * width + col;
+        (col < width && id < self.nodes.len()).then_some(id)
+    }
+}
+
+impl<W: Clone> Graph<Cell<char>, W> {
+    // the puzzle inputs come as lines of text, one char per cell
+    pub fn from_lines<S: AsRef<str>>(lines: &[S], neighborhood: Neighborhood, edge: impl FnMut(&char, &char) -> Option<W>) -> Self {
+        let rows: Vec<Vec<char>> = lines.iter().map(|line| line.as_ref().chars().collect()).collect();
+        Graph::from_grid(&rows, neighborhood, edge)
+    }
+}
+
+impl<N: Debug, W: Debug> Graph<N, W> {
+    // undirected edges are drawn without arrow heads
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("graph");
+        for (id, data) in self.nodes() {
+            dot.node(&format!("n{}", id), &format!("{}: {:?}", id, data));
+        }
+        for (from, to, weight) in self.edges() {
+            let label = format!("{:?}", weight);
+            // () weights say nothing
+            let label = if label == "()" { String::new() } else { label };
+            if self.is_directed() {
+                dot.edge(&format!("n{}", from), &format!("n{}", to), &label);
+            } else {
+                dot.edge_with(&format!("n{}", from), &format!("n{}", to), &label, "dir=none");
+            }
+        }
+        dot.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directed() {
+        let mut graph = Graph::directed();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        graph.add_edge(a, b, 1);
+        graph.add_edge(a, c, 5);
+        graph.add_edge(b, c, 2);
+        graph.add_edge(c, c, 0);
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 4));
+        assert_eq!(graph.neighbors(a).collect::<Vec<_>>(), vec![b, c]);
+        assert_eq!(graph.weight(a, c), Some(&5));
+        assert!(!graph.has_edge(c, a));
+        assert_eq!(graph.edges().collect::<Vec<_>>(), vec![(a, b, &1), (a, c, &5), (b, c, &2), (c, c, &0)]);
+        assert_eq!(graph.find_node(|name| *name == "c"), Some(c));
+
+        let reversed = graph.reversed();
+        assert!(reversed.has_edge(c, a) && !reversed.has_edge(a, c));
+        assert_eq!(reversed.edge_count(), 4);
+    }
+
+    #[test]
+    fn test_undirected() {
+        let (graph, ids) = Graph::from_edges(Direction::Undirected, vec![("x", "y", 3), ("y", "z", 4), ("z", "z", 1)]);
+        let (x, y, z) = (ids["x"], ids["y"], ids["z"]);
+        assert_eq!((x, y, z), (0, 1, 2));
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.weight(y, x), Some(&3));
+        assert_eq!(graph.neighbors(y).collect::<Vec<_>>(), vec![x, z]);
+        // the self loop is stored once
+        assert_eq!(graph.degree(z), 2);
+        assert_eq!(graph.edges().count(), 3);
+        assert_eq!(*graph.node(z), "z");
+    }
+
+    #[test]
+    fn test_traversals() {
+        let (graph, ids) = Graph::from_pairs(Direction::Directed, vec![(1, 2), (1, 3), (2, 4), (3, 5), (5, 1), (6, 1)]);
+        let keys = |order: Vec<NodeId>| order.into_iter().map(|id| *graph.node(id)).collect::<Vec<_>>();
+        assert_eq!(keys(graph.dfs(ids[&1])), vec![1, 2, 4, 3, 5]);
+        assert_eq!(keys(graph.bfs(ids[&1])), vec![1, 2, 3, 4, 5]);
+        assert_eq!(keys(graph.bfs(ids[&6])), vec![6, 1, 2, 3, 4, 5]);
+        assert_eq!(keys(graph.dfs(ids[&4])), vec![4]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let lines = ["ab.", "c.d"];
+        // only letters connect, and only to letters
+        let graph = Graph::from_lines(&lines, Neighborhood::Orthogonal, |from, to| {
+            (from.is_alphabetic() && to.is_alphabetic()).then_some(())
+        });
+        assert_eq!(graph.node_count(), 6);
+        let (a, b, c, d) = (graph.cell_id(0, 0).unwrap(), graph.cell_id(0, 1).unwrap(), graph.cell_id(1, 0).unwrap(), graph.cell_id(1, 2).unwrap());
+        assert_eq!(graph.node(d), &Cell { row: 1, col: 2, value: 'd' });
+        assert_eq!(graph.neighbors(a).collect::<Vec<_>>(), vec![b, c]);
+        assert_eq!(graph.degree(d), 0);
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.cell_id(0, 3), None);
+        assert_eq!(graph.cell_id(2, 0), None);
+
+        let diagonal = Graph::from_lines(&lines, Neighborhood::WithDiagonals, |_, _| Some(1));
+        // corners have 3 neighbors, the middle ones 5
+        assert_eq!(diagonal.degree(a), 3);
+        assert_eq!(diagonal.degree(b), 5);
+        assert_eq!(diagonal.edge_count(), 3 * 4 + 5 * 2);
+    }
+
+    #[test]
+    fn test_grid_asymmetric_rule() {
+        // can step at most one higher, like hill climbing
+        let rows = vec![vec![0, 1, 3], vec![0, 2, 2]];
+        let graph = Graph::from_grid(&rows, Neighborhood::Orthogonal, |from, to| (*to <= *from + 1).then_some(()));
+        let at = |row, col| graph.cell_id(row, col).unwrap();
+        assert!(graph.has_edge(at(0, 0), at(0, 1)));
+        assert!(!graph.has_edge(at(0, 1), at(0, 2)));
+        assert!(graph.has_edge(at(0, 2), at(0, 1)));
+        assert_eq!(graph.bfs(at(0, 0)).len(), 6);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let (graph, _) = Graph::from_edges(Direction::Undirected, vec![('a', 'b', 7)]);
+        let dot = graph.to_dot();
+        assert!(dot.contains("n0 [label=\"0: 'a'\"];"), "{}", dot);
+        assert!(dot.contains("n0 -> n1 [label=\"7\", dir=none];"), "{}", dot);
+        let (unweighted, _) = Graph::from_pairs(Direction::Directed, vec![(1, 2)]);
+        assert!(unweighted.to_dot().contains("n0 -> n1;"));
+    }
+}
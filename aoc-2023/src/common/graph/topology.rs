@@ -0,0 +1,413 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::{Graph, NodeId};
+
+// Ordering and cycle structure of a graph: topological sorts, strongly connected components
+// and the DAG they form. Everything is iterative, a path of a million nodes is no problem.
+//
+// The sorts only make sense for directed graphs. On an undirected graph the components are
+// just the connected components, and find_cycle looks for a cycle that doesn't go back over
+// the edge it came in through.
+
+// the parts of the graph that can reach each other, collapsed into single nodes
+pub struct Condensation {
+    // one node per component, holding its members. Node ids are in topological order,
+    // every edge goes from a smaller id to a bigger one
+    pub dag: Graph<Vec<NodeId>>,
+    // the dag node of every node of the original graph
+    pub component_of: Vec<NodeId>
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    // not reached yet
+    White,
+    // on the current path
+    Gray,
+    // done with everything below it
+    Black
+}
+
+impl<N, W> Graph<N, W> {
+    // Kahn's algorithm: keep taking nodes nothing points to anymore. Among the nodes that are
+    // ready at the same time the smaller id goes first. On a cycle there is no order, the error
+    // holds one of the cycles instead
+    pub fn topological_sort_kahn(&self) -> Result<Vec<NodeId>, Vec<NodeId>> {
+        assert!(self.is_directed(), "only directed graphs have a topological order");
+        let mut in_degree = vec![0; self.node_count()];
+        for (_, to, _) in self.edges() {
+            in_degree[to] += 1;
+        }
+        let mut ready: BinaryHeap<Reverse<NodeId>> = self.node_ids().filter(|&id| in_degree[id] == 0).map(Reverse).collect();
+        let mut order = Vec::with_capacity(self.node_count());
+        while let Some(Reverse(id)) = ready.pop() {
+            order.push(id);
+            for to in self.neighbors(id) {
+                in_degree[to] -= 1;
+                if in_degree[to] == 0 {
+                    ready.push(Reverse(to));
+                }
+            }
+        }
+        if order.len() == self.node_count() {
+            Ok(order)
+        } else {
+            Err(self.find_cycle().expect("nodes were left over, so there is a cycle"))
+        }
+    }
+
+    // reverse DFS post-order, the same order a recursive "visit children, then push myself"
+    // would give. The error holds the cycle the first back edge closes
+    pub fn topological_sort_dfs(&self) -> Result<Vec<NodeId>, Vec<NodeId>> {
+        assert!(self.is_directed(), "only directed graphs have a topological order");
+        let mut colors = vec![Color::White; self.node_count()];
+        let mut post_order = Vec::with_capacity(self.node_count());
+        for root in self.node_ids() {
+            if colors[root] == Color::White {
+                if let Some(cycle) = self.dfs_colors(root, &mut colors, &mut post_order) {
+                    return Err(cycle);
+                }
+            }
+        }
+        post_order.reverse();
+        Ok(post_order)
+    }
+
+    pub fn is_acyclic(&self) -> bool {
+        self.find_cycle().is_none()
+    }
+
+    // some cycle as the nodes along it, the first one isn't repeated at the end.
+    // A self loop is a cycle of one node
+    pub fn find_cycle(&self) -> Option<Vec<NodeId>> {
+        let mut colors = vec![Color::White; self.node_count()];
+        let mut post_order = Vec::new();
+        self.node_ids().find_map(|root| {
+            if colors[root] != Color::White {
+                return None;
+            }
+            self.dfs_colors(root, &mut colors, &mut post_order)
+        })
+    }
+
+    // DFS from root over the white nodes, pushing every node to post_order when it is done.
+    // Stops at the first edge back to a gray node and returns the cycle it closes
+    fn dfs_colors(&self, root: NodeId, colors: &mut [Color], post_order: &mut Vec<NodeId>) -> Option<Vec<NodeId>> {
+        // (node, next edge to look at, the node we came from)
+        let mut stack: Vec<(NodeId, usize, Option<NodeId>)> = vec![(root, 0, None)];
+        colors[root] = Color::Gray;
+        // an undirected edge shows up from both ends, the way back to the parent is skipped once
+        let mut skipped_parent = vec![false];
+        while let Some(&mut (id, ref mut next_edge, parent)) = stack.last_mut() {
+            let Some(edge) = self.edges_from(id).get(*next_edge) else {
+                colors[id] = Color::Black;
+                post_order.push(id);
+                stack.pop();
+                skipped_parent.pop();
+                continue;
+            };
+            *next_edge += 1;
+            let to = edge.to;
+            if !self.is_directed() && Some(to) == parent && !*skipped_parent.last().unwrap() {
+                *skipped_parent.last_mut().unwrap() = true;
+                continue;
+            }
+            match colors[to] {
+                Color::White => {
+                    colors[to] = Color::Gray;
+                    stack.push((to, 0, Some(id)));
+                    skipped_parent.push(false);
+                },
+                Color::Gray => {
+                    let start = stack.iter().position(|&(on_path, _, _)| on_path == to).unwrap();
+                    return Some(stack[start..].iter().map(|&(on_path, _, _)| on_path).collect());
+                },
+                Color::Black => {}
+            }
+        }
+        None
+    }
+
+    // Tarjan's algorithm, one DFS. Components come out in reverse topological order,
+    // a component is finished only after everything it points to
+    pub fn tarjan_scc(&self) -> Vec<Vec<NodeId>> {
+        const UNVISITED: usize = usize::MAX;
+        let n = self.node_count();
+        // order of discovery, and the earliest discovered node reachable through the DFS subtree
+        let mut index = vec![UNVISITED; n];
+        let mut low_link = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut component_stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+        for root in self.node_ids() {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            let mut call_stack: Vec<(NodeId, usize)> = vec![(root, 0)];
+            index[root] = next_index;
+            low_link[root] = next_index;
+            next_index += 1;
+            component_stack.push(root);
+            on_stack[root] = true;
+            while let Some(&mut (id, ref mut next_edge)) = call_stack.last_mut() {
+                if let Some(edge) = self.edges_from(id).get(*next_edge) {
+                    *next_edge += 1;
+                    let to = edge.to;
+                    if index[to] == UNVISITED {
+                        index[to] = next_index;
+                        low_link[to] = next_index;
+                        next_index += 1;
+                        component_stack.push(to);
+                        on_stack[to] = true;
+                        call_stack.push((to, 0));
+                    } else if on_stack[to] {
+                        low_link[id] = low_link[id].min(index[to]);
+                    }
+                    continue;
+                }
+                // all edges done, "return" to the caller
+                call_stack.pop();
+                if let Some(&(caller, _)) = call_stack.last() {
+                    low_link[caller] = low_link[caller].min(low_link[id]);
+                }
+                if low_link[id] == index[id] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = component_stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == id {
+                            break;
+                        }
+                    }
+                    component.reverse();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    // Kosaraju's algorithm: DFS finish order on the graph, then DFS on the reversed graph in
+    // reverse finish order, every tree of the second pass is a component. Components come out
+    // in topological order
+    pub fn kosaraju_scc(&self) -> Vec<Vec<NodeId>> {
+        let n = self.node_count();
+        let mut visited = vec![false; n];
+        let mut finish_order = Vec::with_capacity(n);
+        for root in self.node_ids() {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some(&mut (id, ref mut next_edge)) = stack.last_mut() {
+                match self.edges_from(id).get(*next_edge) {
+                    Some(edge) => {
+                        *next_edge += 1;
+                        if !visited[edge.to] {
+                            visited[edge.to] = true;
+                            stack.push((edge.to, 0));
+                        }
+                    },
+                    None => {
+                        finish_order.push(id);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // the reversed edges only, the node data isn't needed
+        let mut incoming = vec![Vec::new(); n];
+        for (from, to, _) in self.edges() {
+            incoming[to].push(from);
+            if !self.is_directed() {
+                incoming[from].push(to);
+            }
+        }
+        let mut assigned = vec![false; n];
+        let mut components = Vec::new();
+        for &root in finish_order.iter().rev() {
+            if assigned[root] {
+                continue;
+            }
+            assigned[root] = true;
+            let mut component = vec![root];
+            let mut stack = vec![root];
+            while let Some(id) = stack.pop() {
+                for &from in &incoming[id] {
+                    if !assigned[from] {
+                        assigned[from] = true;
+                        component.push(from);
+                        stack.push(from);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    pub fn condensation(&self) -> Condensation {
+        let mut dag = Graph::directed();
+        let mut component_of = vec![0; self.node_count()];
+        for component in self.kosaraju_scc() {
+            for &member in &component {
+                component_of[member] = dag.node_count();
+            }
+            dag.add_node(component);
+        }
+        let mut edges: Vec<(NodeId, NodeId)> = self.edges()
+            .map(|(from, to, _)| (component_of[from], component_of[to]))
+            .filter(|(from, to)| from != to)
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        for (from, to) in edges {
+            dag.add_edge(from, to, ());
+        }
+        Condensation { dag, component_of }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{graph::{Direction, Graph, NodeId}, random::Rng};
+
+    fn graph(n: usize, edges: &[(NodeId, NodeId)]) -> Graph<NodeId> {
+        let mut graph = Graph::directed();
+        for id in 0..n {
+            graph.add_node(id);
+        }
+        for &(from, to) in edges {
+            graph.add_edge(from, to, ());
+        }
+        graph
+    }
+
+    fn is_topological<N>(graph: &Graph<N>, order: &[NodeId]) -> bool {
+        let mut position = vec![usize::MAX; graph.node_count()];
+        for (i, &id) in order.iter().enumerate() {
+            position[id] = i;
+        }
+        order.len() == graph.node_count() && graph.edges().all(|(from, to, _)| position[from] < position[to])
+    }
+
+    fn is_cycle(graph: &Graph<NodeId>, cycle: &[NodeId]) -> bool {
+        !cycle.is_empty() && (0..cycle.len()).all(|i| graph.has_edge(cycle[i], cycle[(i + 1) % cycle.len()]))
+    }
+
+    fn sorted(mut components: Vec<Vec<NodeId>>) -> Vec<Vec<NodeId>> {
+        for component in components.iter_mut() {
+            component.sort_unstable();
+        }
+        components.sort();
+        components
+    }
+
+    #[test]
+    fn test_topological_sorts() {
+        // shirt before tie before jacket, socks before shoes...
+        let dressing = graph(6, &[(0, 1), (1, 2), (3, 4), (5, 4), (0, 2)]);
+        assert_eq!(dressing.topological_sort_kahn(), Ok(vec![0, 1, 2, 3, 5, 4]));
+        let by_dfs = dressing.topological_sort_dfs().unwrap();
+        assert!(is_topological(&dressing, &by_dfs), "{:?}", by_dfs);
+        assert_eq!(by_dfs, vec![5, 3, 4, 0, 1, 2]);
+        assert!(dressing.is_acyclic());
+        assert_eq!(graph(0, &[]).topological_sort_kahn(), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let cyclic = graph(5, &[(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)]);
+        assert_eq!(cyclic.find_cycle(), Some(vec![1, 2, 3]));
+        assert_eq!(cyclic.topological_sort_dfs(), Err(vec![1, 2, 3]));
+        let from_kahn = cyclic.topological_sort_kahn().unwrap_err();
+        assert!(is_cycle(&cyclic, &from_kahn), "{:?}", from_kahn);
+
+        let self_loop = graph(2, &[(0, 1), (1, 1)]);
+        assert_eq!(self_loop.find_cycle(), Some(vec![1]));
+
+        // a diamond has two paths to the same node, but no cycle
+        let diamond = graph(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        assert_eq!(diamond.find_cycle(), None);
+    }
+
+    #[test]
+    fn test_undirected_cycles() {
+        let (tree, _) = Graph::from_pairs(Direction::Undirected, vec![(0, 1), (1, 2), (1, 3)]);
+        assert_eq!(tree.find_cycle(), None);
+        let (triangle, _) = Graph::from_pairs(Direction::Undirected, vec![(0, 1), (1, 2), (2, 0), (2, 3)]);
+        assert_eq!(triangle.find_cycle().map(|cycle| cycle.len()), Some(3));
+        // two edges between the same nodes go around in a circle too
+        let (double, _) = Graph::from_pairs(Direction::Undirected, vec![(0, 1), (1, 0)]);
+        assert_eq!(double.find_cycle(), Some(vec![0, 1]));
+        assert_eq!(sorted(triangle.tarjan_scc()), vec![vec![0, 1, 2, 3]]);
+        assert_eq!(sorted(triangle.kosaraju_scc()), vec![vec![0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_scc() {
+        // the classic three component example: {0, 1, 2} -> {3, 4} -> {5}, {0, 1, 2} -> {5}
+        let graph = graph(6, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (4, 5), (1, 5)]);
+        let expected = vec![vec![0, 1, 2], vec![3, 4], vec![5]];
+        let tarjan = graph.tarjan_scc();
+        // reverse topological order
+        assert_eq!(tarjan.last().map(|c| c.len()), Some(3));
+        assert_eq!(sorted(tarjan), expected);
+        assert_eq!(graph.kosaraju_scc(), expected);
+
+        let condensation = graph.condensation();
+        assert_eq!(condensation.dag.node_count(), 3);
+        assert_eq!(condensation.component_of, vec![0, 0, 0, 1, 1, 2]);
+        assert_eq!(condensation.dag.edges().map(|(from, to, _)| (from, to)).collect::<Vec<_>>(), vec![(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(condensation.dag.node(1), &vec![3, 4]);
+        assert!(condensation.dag.is_acyclic());
+    }
+
+    #[test]
+    fn test_long_path() {
+        const N: usize = 500_000;
+        let edges: Vec<_> = (0..N - 1).map(|id| (id, id + 1)).chain([(N - 1, 0)]).collect();
+        let ring = graph(N, &edges);
+        assert_eq!(ring.tarjan_scc().len(), 1);
+        assert_eq!(ring.kosaraju_scc().len(), 1);
+        assert_eq!(ring.find_cycle().map(|cycle| cycle.len()), Some(N));
+        let path = graph(N, &edges[..N - 1]);
+        assert_eq!(path.topological_sort_dfs(), Ok((0..N).collect()));
+    }
+
+    // both component algorithms agree, the condensation is a DAG, and a graph without
+    // cycles is exactly one that can be sorted
+    #[test]
+    fn test_random_graphs() {
+        let mut rng = Rng::new(44);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..12);
+            let edge_count = rng.gen_range(0..2 * n);
+            let edges: Vec<_> = (0..edge_count).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+            let graph = graph(n, &edges);
+
+            let components = graph.kosaraju_scc();
+            assert_eq!(sorted(graph.tarjan_scc()), sorted(components.clone()), "{:?}", edges);
+            let condensation = graph.condensation();
+            assert!(condensation.dag.edges().all(|(from, to, _)| from < to));
+            assert!(is_topological(&condensation.dag, &condensation.dag.topological_sort_kahn().unwrap()));
+            assert_eq!(condensation.dag.node_count(), components.len());
+
+            match (graph.topological_sort_kahn(), graph.topological_sort_dfs()) {
+                (Ok(kahn), Ok(dfs)) => {
+                    assert!(is_topological(&graph, &kahn) && is_topological(&graph, &dfs), "{:?}", edges);
+                    assert!(components.iter().all(|component| component.len() == 1));
+                },
+                (Err(kahn), Err(dfs)) => {
+                    assert!(is_cycle(&graph, &kahn) && is_cycle(&graph, &dfs), "{:?}", edges);
+                },
+                (kahn, dfs) => panic!("the sorts disagree on {:?}: {:?} {:?}", edges, kahn, dfs)
+            }
+        }
+    }
+}
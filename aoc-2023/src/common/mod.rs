@@ -1,2 +1,14 @@
 pub mod file;
-pub mod strings;
\ No newline at end of file
+pub mod strings;
+pub mod task;
+pub mod bits;
+pub mod bitset;
+pub mod dot;
+pub mod graph;
+pub mod num;
+pub mod random;
+pub mod search;
+#[cfg(test)]
+pub mod property;
+#[cfg(test)]
+pub mod drop_counter;
\ No newline at end of file
@@ -0,0 +1,284 @@
+use std::{fmt::{Debug, Display}, ops::{Add, Div, Mul, Neg, Rem, Sub}};
+
+// Number theory helpers for every primitive integer, the things puzzle solvers keep needing:
+// sums and products that report overflow instead of wrapping in release builds, gcd and lcm,
+// modular exponentiation, and for the signed types extended Euclid and the chinese remainder
+// theorem. Everything that could overflow gives an Option, or says in a comment that it panics.
+
+pub trait Integer:
+    Copy + Ord + Debug + Display
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_rem(self, other: Self) -> Option<Self>;
+    // the remainder in 0..|other|, also for negative numbers
+    fn rem_euclid(self, other: Self) -> Self;
+    fn is_negative(self) -> bool;
+
+    // never negative. Panics like abs when the answer doesn't fit, gcd(i32::MIN, 0) is 2^31
+    fn gcd(self, other: Self) -> Self {
+        let (mut a, mut b) = (self, other);
+        while b != Self::ZERO {
+            // MIN % -1 overflows even though the remainder is just 0
+            (a, b) = (b, a.checked_rem(b).unwrap_or(Self::ZERO));
+        }
+        if a.is_negative() { Self::ZERO - a } else { a }
+    }
+
+    // the smallest non negative common multiple, 0 when either is 0, None when it doesn't fit
+    fn lcm(self, other: Self) -> Option<Self> {
+        if self == Self::ZERO || other == Self::ZERO {
+            return Some(Self::ZERO);
+        }
+        let lcm = (self / self.gcd(other)).checked_mul(other)?;
+        if lcm.is_negative() { Self::ZERO.checked_sub(lcm) } else { Some(lcm) }
+    }
+
+    // self * other % modulus without overflowing, for a positive modulus
+    fn mul_mod(self, other: Self, modulus: Self) -> Self {
+        let (a, mut b) = (self.rem_euclid(modulus), other.rem_euclid(modulus));
+        if let Some(product) = a.checked_mul(b) {
+            return product % modulus;
+        }
+        // too big to multiply directly, double and add instead. Both stay below the modulus,
+        // so a + b is computed as a - (modulus - b) when it would go past it
+        let add_mod = |a: Self, b: Self| if a >= modulus - b { a - (modulus - b) } else { a + b };
+        let two = Self::ONE + Self::ONE;
+        let (mut result, mut doubled) = (Self::ZERO, a);
+        while b > Self::ZERO {
+            if b % two == Self::ONE {
+                result = add_mod(result, doubled);
+            }
+            doubled = add_mod(doubled, doubled);
+            b = b / two;
+        }
+        result
+    }
+
+    // self^exp % modulus by squaring, for a positive modulus and a non negative exponent
+    fn mod_pow(self, mut exp: Self, modulus: Self) -> Self {
+        assert!(modulus > Self::ZERO, "the modulus has to be positive, got {}", modulus);
+        assert!(!exp.is_negative(), "the exponent can't be negative, got {}", exp);
+        let two = Self::ONE + Self::ONE;
+        let mut base = self.rem_euclid(modulus);
+        let mut result = Self::ONE % modulus;
+        while exp > Self::ZERO {
+            if exp % two == Self::ONE {
+                result = result.mul_mod(base, modulus);
+            }
+            base = base.mul_mod(base, modulus);
+            exp = exp / two;
+        }
+        result
+    }
+}
+
+pub trait Signed: Integer + Neg<Output = Self> {
+    // (g, x, y) with self * x + other * y = g = gcd(self, other)
+    fn extended_gcd(self, other: Self) -> (Self, Self, Self) {
+        let (mut old_r, mut r) = (self, other);
+        let (mut old_x, mut x) = (Self::ONE, Self::ZERO);
+        let (mut old_y, mut y) = (Self::ZERO, Self::ONE);
+        while r != Self::ZERO {
+            let quotient = old_r / r;
+            (old_r, r) = (r, old_r - quotient * r);
+            (old_x, x) = (x, old_x - quotient * x);
+            (old_y, y) = (y, old_y - quotient * y);
+        }
+        if old_r.is_negative() { (-old_r, -old_x, -old_y) } else { (old_r, old_x, old_y) }
+    }
+
+    // x in 0..modulus with self * x % modulus == 1, None when they share a factor
+    fn mod_inverse(self, modulus: Self) -> Option<Self> {
+        let (g, x, _) = self.rem_euclid(modulus).extended_gcd(modulus);
+        (g == Self::ONE).then(|| x.rem_euclid(modulus))
+    }
+}
+
+macro_rules! integers {
+    ($($t:ty: $negative:expr),*) => {$(
+        impl Integer for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn checked_add(self, other: Self) -> Option<Self> { <$t>::checked_add(self, other) }
+            fn checked_sub(self, other: Self) -> Option<Self> { <$t>::checked_sub(self, other) }
+            fn checked_mul(self, other: Self) -> Option<Self> { <$t>::checked_mul(self, other) }
+            fn checked_rem(self, other: Self) -> Option<Self> { <$t>::checked_rem(self, other) }
+            fn rem_euclid(self, other: Self) -> Self { <$t>::rem_euclid(self, other) }
+            #[allow(unused_comparisons)]
+            fn is_negative(self) -> bool { $negative && self < 0 }
+        }
+    )*};
+}
+
+integers!(u8: false, u16: false, u32: false, u64: false, u128: false, usize: false);
+integers!(i8: true, i16: true, i32: true, i64: true, i128: true, isize: true);
+
+impl Signed for i8 {}
+impl Signed for i16 {}
+impl Signed for i32 {}
+impl Signed for i64 {}
+impl Signed for i128 {}
+impl Signed for isize {}
+
+// The x with x % modulus == residue for every (residue, modulus) pair, as (x, lcm of the moduli),
+// so every x + k * lcm works too. The moduli have to be positive but don't need to be coprime.
+// None when the congruences contradict each other or the lcm doesn't fit
+pub fn crt<T: Signed>(congruences: impl IntoIterator<Item = (T, T)>) -> Option<(T, T)> {
+    let (mut x, mut lcm) = (T::ZERO, T::ONE);
+    for (residue, modulus) in congruences {
+        assert!(modulus > T::ZERO, "the moduli have to be positive, got {}", modulus);
+        let residue = residue.rem_euclid(modulus);
+        // x + lcm * k = residue (mod modulus), solvable when the gcd divides the difference
+        let (g, inverse, _) = lcm.extended_gcd(modulus);
+        let difference = residue - x.rem_euclid(modulus);
+        if difference % g != T::ZERO {
+            return None;
+        }
+        let step = modulus / g;
+        let k = (difference / g).mul_mod(inverse, step);
+        let combined = lcm.checked_mul(step)?;
+        // lcm * k < combined, and x < lcm, so none of this overflows
+        x = (x + lcm * k).rem_euclid(combined);
+        lcm = combined;
+    }
+    Some((x, lcm))
+}
+
+// sum and product of integers that give None instead of overflowing
+pub trait CheckedIterator: Iterator {
+    fn checked_sum(self) -> Option<Self::Item>;
+    fn checked_product(self) -> Option<Self::Item>;
+}
+
+impl<T: Integer, I: Iterator<Item = T>> CheckedIterator for I {
+    fn checked_sum(mut self) -> Option<T> {
+        self.try_fold(T::ZERO, T::checked_add)
+    }
+
+    fn checked_product(mut self) -> Option<T> {
+        self.try_fold(T::ONE, T::checked_mul)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::random::Rng;
+
+    #[test]
+    fn test_checked_accumulation() {
+        assert_eq!([1u8, 2, 3].into_iter().checked_sum(), Some(6));
+        assert_eq!([200u8, 56].into_iter().checked_sum(), None);
+        assert_eq!([255u8].into_iter().checked_sum(), Some(255));
+        assert_eq!(std::iter::empty::<u32>().checked_sum(), Some(0));
+        assert_eq!(std::iter::empty::<u32>().checked_product(), Some(1));
+        assert_eq!([i64::MIN, -1].into_iter().checked_sum(), None);
+        assert_eq!([i64::MIN, 1].into_iter().checked_sum(), Some(i64::MIN + 1));
+        assert_eq!((1..=20u64).checked_product(), Some(2_432_902_008_176_640_000));
+        assert_eq!((1..=21u64).checked_product(), None);
+        assert_eq!((1..=34u128).checked_product().map(|product| product % 1_000), Some(0));
+        assert_eq!([u32::MAX, 0].into_iter().checked_product(), Some(0));
+        assert_eq!([-2i8, -64].into_iter().checked_product(), None);
+        assert_eq!([-2i8, 64].into_iter().checked_product(), Some(-128));
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(12u32.gcd(18), 6);
+        assert_eq!(0u8.gcd(0), 0);
+        assert_eq!(0u64.gcd(7), 7);
+        assert_eq!((-12i32).gcd(18), 6);
+        assert_eq!(12i32.gcd(-18), 6);
+        assert_eq!(i32::MIN.gcd(-1), 1);
+        assert_eq!(i32::MIN.gcd(6), 2);
+        assert_eq!(u128::MAX.gcd(u128::MAX), u128::MAX);
+        assert_eq!(4u32.lcm(6), Some(12));
+        assert_eq!((-4i32).lcm(6), Some(12));
+        assert_eq!(0i32.lcm(6), Some(0));
+        assert_eq!(u8::MAX.lcm(2), None);
+        assert_eq!(i8::MIN.lcm(1), None);
+        // when loops of these lengths all line up again
+        assert_eq!((1..=20u64).try_fold(1, |lcm: u64, n| lcm.lcm(n)), Some(232_792_560));
+        assert_eq!((1..=46u64).try_fold(1, |lcm: u64, n| lcm.lcm(n)), Some(9_419_588_158_802_421_600));
+        assert_eq!((1..=47u64).try_fold(1, |lcm: u64, n| lcm.lcm(n)), None);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(2u32.mod_pow(10, 1_000), 24);
+        assert_eq!(3u8.mod_pow(0, 7), 1);
+        assert_eq!(5u8.mod_pow(0, 1), 0);
+        assert_eq!((-2i32).mod_pow(3, 5), 2);
+        assert_eq!(i64::MIN.mod_pow(1, 7), i64::MIN.rem_euclid(7));
+        // Fermat: a^(p-1) = 1 mod p, with a prime close to the top of the type
+        let p = 18_446_744_073_709_551_557u64;
+        assert_eq!(123_456_789u64.mod_pow(p - 1, p), 1);
+        assert_eq!(250u8.mod_pow(250, 251), 1);
+        let p = 340_282_366_920_938_463_463_374_607_431_768_211_297u128;
+        assert_eq!(u128::MAX.mod_pow(p - 1, p), 1);
+    }
+
+    #[test]
+    fn test_mul_mod_matches_wide_arithmetic() {
+        let mut rng = Rng::new(50);
+        for _ in 0..2_000 {
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            let modulus = rng.next_u64() >> rng.gen_range(0..63) | 1;
+            assert_eq!(a.mul_mod(b, modulus) as u128, a as u128 * b as u128 % modulus as u128, "{} * {} % {}", a, b, modulus);
+            let (a, b, modulus) = (a as i64, b as i64, modulus as i64 & i64::MAX | 1);
+            assert_eq!(a.mul_mod(b, modulus) as i128, (a as i128 * b as i128).rem_euclid(modulus as i128), "{} * {} % {}", a, b, modulus);
+        }
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let mut rng = Rng::new(51);
+        for _ in 0..1_000 {
+            let a = rng.next_u64() as i32 >> rng.gen_range(0..31);
+            let b = rng.next_u64() as i32 >> rng.gen_range(0..31);
+            let (g, x, y) = a.extended_gcd(b);
+            assert_eq!(g, a.gcd(b));
+            assert_eq!(a as i64 * x as i64 + b as i64 * y as i64, g as i64, "{} {}", a, b);
+        }
+        assert_eq!(0i8.extended_gcd(0), (0, 1, 0));
+        assert_eq!(3i64.mod_inverse(7), Some(5));
+        assert_eq!((-3i64).mod_inverse(7), Some(2));
+        assert_eq!(4i64.mod_inverse(8), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt([(2i64, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // not coprime, but consistent
+        assert_eq!(crt([(2i32, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt([(1i32, 4), (2, 6)]), None);
+        assert_eq!(crt([(-1i32, 5)]), Some((4, 5)));
+        assert_eq!(crt(Vec::<(i32, i32)>::new()), Some((0, 1)));
+        // the lcm doesn't fit an i8, but it would fit an i16
+        assert_eq!(crt([(1i8, 11), (2, 13)]), None);
+        assert_eq!(crt([(1i16, 11), (2, 13)]), Some((67, 143)));
+        // moduli near the top of i64, the intermediate products would overflow without mul_mod
+        let (p, q) = (3_037_000_493i64, 3_037_000_453);
+        let (x, lcm) = crt([(123, p), (456, q)]).unwrap();
+        assert_eq!((x % p, x % q, lcm), (123, 456, p * q));
+
+        let mut rng = Rng::new(52);
+        for _ in 0..500 {
+            let congruences: Vec<(i64, i64)> = (0..rng.gen_range(1..4)).map(|_| (rng.gen_range(0..100) as i64, rng.gen_range(1..20) as i64)).collect();
+            let lcm = congruences.iter().fold(1, |lcm: i64, &(_, modulus)| lcm.lcm(modulus).unwrap());
+            let brute_force = (0..lcm).find(|x| congruences.iter().all(|&(residue, modulus)| x % modulus == residue % modulus));
+            assert_eq!(crt(congruences.iter().copied()), brute_force.map(|x| (x, lcm)), "{:?}", congruences);
+        }
+    }
+}
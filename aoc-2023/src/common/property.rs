@@ -0,0 +1,177 @@
+use std::{fmt::Debug, panic::{self, AssertUnwindSafe}};
+
+use super::random::Rng;
+
+// Tiny property testing harness: generates random inputs from a seed, checks a property on
+// each of them and when one fails, shrinks it to a (locally) minimal input that still fails.
+// A property fails either by returning Err or by panicking.
+
+#[derive(Debug)]
+pub struct Failure<T> {
+    pub seed: u64,
+    pub original: T,
+    pub shrunk: T,
+    pub message: String
+}
+
+// upper bound on shrinking steps, so a shrinker that keeps producing failing inputs can't loop forever
+const MAX_SHRINK_STEPS: usize = 10_000;
+
+fn run_property<T, P: Fn(&T) -> Result<(), String>>(property: &P, value: &T) -> Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| property(value))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic with a non string payload".to_owned());
+            Err(format!("panicked: {}", message))
+        }
+    }
+}
+
+// Runs `cases` random inputs, case i is generated from seed + i, so a failure can be replayed
+// by running just that one seed again.
+pub fn find_failure<T, G, S, P>(seed: u64, cases: u64, generate: G, shrink: S, property: P) -> Option<Failure<T>>
+where
+    T: Clone,
+    G: Fn(&mut Rng) -> T,
+    S: Fn(&T) -> Vec<T>,
+    P: Fn(&T) -> Result<(), String>
+{
+    for case_seed in seed..seed + cases {
+        let original = generate(&mut Rng::new(case_seed));
+        if let Err(message) = run_property(&property, &original) {
+            let (shrunk, message) = shrink_failure(original.clone(), message, &shrink, &property);
+            return Some(Failure { seed: case_seed, original, shrunk, message });
+        }
+    }
+    None
+}
+
+// greedy - take the first smaller candidate that still fails and start over from it
+fn shrink_failure<T, S, P>(mut value: T, mut message: String, shrink: &S, property: &P) -> (T, String)
+where
+    S: Fn(&T) -> Vec<T>,
+    P: Fn(&T) -> Result<(), String>
+{
+    let mut steps = 0;
+    'shrinking: while steps < MAX_SHRINK_STEPS {
+        for candidate in shrink(&value) {
+            steps += 1;
+            if let Err(candidate_message) = run_property(property, &candidate) {
+                value = candidate;
+                message = candidate_message;
+                continue 'shrinking;
+            }
+        }
+        break;
+    }
+    (value, message)
+}
+
+// same as find_failure, but panics with a readable report, for use directly in tests
+pub fn check<T, G, S, P>(seed: u64, cases: u64, generate: G, shrink: S, property: P)
+where
+    T: Clone + Debug,
+    G: Fn(&mut Rng) -> T,
+    S: Fn(&T) -> Vec<T>,
+    P: Fn(&T) -> Result<(), String>
+{
+    if let Some(failure) = find_failure(seed, cases, generate, shrink, property) {
+        panic!("property failed for seed {}: {}\nminimal input: {:?}\noriginal input: {:?}",
+            failure.seed, failure.message, failure.shrunk, failure.original);
+    }
+}
+
+pub fn gen_vec<T>(rng: &mut Rng, max_len: usize, mut gen_elem: impl FnMut(&mut Rng) -> T) -> Vec<T> {
+    let len = rng.gen_range(0..max_len + 1);
+    (0..len).map(|_| gen_elem(rng)).collect()
+}
+
+// Smaller versions of a sequence: first with whole chunks removed (halves, quarters, ... single
+// elements), so long inputs shrink fast, then with single elements replaced by smaller ones.
+pub fn shrink_vec<T: Clone>(values: &[T], shrink_elem: impl Fn(&T) -> Vec<T>) -> Vec<Vec<T>> {
+    let mut candidates = Vec::new();
+    let mut chunk = values.len();
+    while chunk > 0 {
+        let mut start = 0;
+        while start < values.len() {
+            let end = (start + chunk).min(values.len());
+            let mut candidate = values[..start].to_vec();
+            candidate.extend_from_slice(&values[end..]);
+            candidates.push(candidate);
+            start += chunk;
+        }
+        chunk /= 2;
+    }
+    for (i, value) in values.iter().enumerate() {
+        for smaller in shrink_elem(value) {
+            let mut candidate = values.to_vec();
+            candidate[i] = smaller;
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+// towards zero: 0, then half of the value, then one step closer
+pub fn shrink_i64(value: i64) -> Vec<i64> {
+    let mut smaller = Vec::new();
+    if value != 0 {
+        smaller.push(0);
+    }
+    if value / 2 != 0 {
+        smaller.push(value / 2);
+    }
+    if value.abs() > 1 {
+        smaller.push(value - value.signum());
+    }
+    smaller
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passing_property() {
+        check(0, 100,
+            |rng| gen_vec(rng, 20, |rng| rng.gen_range(0..100) as i64),
+            |values| shrink_vec(values, |value| shrink_i64(*value)),
+            |values| {
+                let mut reversed = values.clone();
+                reversed.reverse();
+                reversed.reverse();
+                if reversed == *values { Ok(()) } else { Err("double reverse changed the vec".to_owned()) }
+            });
+    }
+
+    #[test]
+    fn test_shrinks_to_minimal_input() {
+        // fails as soon as some value >= 10 is followed by any value
+        let failure = find_failure(0, 1_000,
+            |rng| gen_vec(rng, 30, |rng| rng.gen_range(0..50) as i64),
+            |values| shrink_vec(values, |value| shrink_i64(*value)),
+            |values: &Vec<i64>| {
+                match values.iter().position(|value| *value >= 10) {
+                    Some(index) if index + 1 < values.len() => Err(format!("{} at {} is followed by more", values[index], index)),
+                    _ => Ok(())
+                }
+            }).expect("random inputs should hit the failure");
+        assert_eq!(failure.shrunk, vec![10, 0]);
+        assert!(failure.original.len() >= 2);
+    }
+
+    #[test]
+    fn test_panics_count_as_failures() {
+        let failure = find_failure(5, 10,
+            |rng| rng.gen_range(1..1_000) as i64,
+            |value| shrink_i64(*value),
+            |value| {
+                assert!(*value < 3, "value too big");
+                Ok(())
+            }).unwrap();
+        assert_eq!(failure.shrunk, 3);
+        assert!(failure.message.contains("value too big"));
+    }
+}
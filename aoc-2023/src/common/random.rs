@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+// Small deterministic pseudo random generator (xorshift64*), good enough for shuffling
+// test inputs and randomized tests, so we don't need to pull in the rand crate.
+// Same seed gives the same sequence, which makes failing randomized tests reproducible.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // state must never be zero, xorshift would get stuck there.
+        // The seed is scrambled by splitmix64, so similar seeds give unrelated sequences
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Rng { state: if z == 0 { 0x2545_F491_4F6C_DD1D } else { z } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // uniform enough for tests, the modulo bias is negligible for small ranges
+    pub fn gen_range(&mut self, range: Range<usize>) -> usize {
+        assert!(range.start < range.end, "cannot sample from an empty range");
+        range.start + (self.next_u64() % (range.end - range.start) as u64) as usize
+    }
+
+    // only tests shuffle things so far
+    #[cfg(test)]
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.gen_range(0..i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let first: Vec<u64> = (0..5).scan(Rng::new(42), |rng, _| Some(rng.next_u64())).collect();
+        let second: Vec<u64> = (0..5).scan(Rng::new(42), |rng, _| Some(rng.next_u64())).collect();
+        let other: Vec<u64> = (0..5).scan(Rng::new(43), |rng, _| Some(rng.next_u64())).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 10];
+        for _ in 0..1000 {
+            let value = rng.gen_range(5..15);
+            assert!((5..15).contains(&value));
+            seen[value - 5] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut rng = Rng::new(7);
+        let mut values: Vec<u32> = (0..100).collect();
+        rng.shuffle(&mut values);
+        assert_ne!(values, (0..100).collect::<Vec<_>>());
+        values.sort();
+        assert_eq!(values, (0..100).collect::<Vec<_>>());
+    }
+}
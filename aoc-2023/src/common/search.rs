@@ -0,0 +1,551 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet, VecDeque}, hash::Hash, ops::Add};
+
+// Shortest path search over any state space. A state is anything that can be cloned, compared
+// and hashed - a NodeId of a common::graph::Graph, grid coordinates, (position, direction)
+// pairs, a whole puzzle board... - and the search only needs to know where it can go from a
+// state, so the states don't have to exist up front.
+//
+//   bfs        every step costs 1
+//   dijkstra   steps cost any non negative amount
+//   astar      like dijkstra, guided by a heuristic that never overestimates the remaining cost
+//
+// Each comes in two flavors: one shortest path, or (the _all variants) every path that is
+// as short as the shortest one, which is what "count the best routes" or "which tiles are on
+// some best route" puzzles want.
+//
+// Internally states get an index when they are first seen, the bookkeeping is done on the
+// indices so every state is cloned and hashed only once.
+
+// costs have to start at Default (0 for the numbers) and add up
+pub trait Cost: Copy + Ord + Add<Output = Self> + Default {}
+
+impl<C: Copy + Ord + Add<Output = C> + Default> Cost for C {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S, C> {
+    // from the start to the goal, both included
+    pub states: Vec<S>,
+    pub cost: C
+}
+
+// every shortest path to every goal that is at the shortest distance. They are kept as the
+// predecessors of each state on them, since there can be exponentially many paths
+#[derive(Clone, Debug)]
+pub struct AllShortestPaths<S, C> {
+    states: Vec<S>,
+    parents: Vec<Vec<usize>>,
+    goals: Vec<usize>,
+    cost: C
+}
+
+// states seen so far, by index
+struct Seen<S> {
+    states: Vec<S>,
+    indices: HashMap<S, usize>
+}
+
+impl<S: Clone + Eq + Hash> Seen<S> {
+    fn new(start: S) -> Self {
+        let mut seen = Seen { states: Vec::new(), indices: HashMap::new() };
+        seen.index_of(start);
+        seen
+    }
+
+    // (index, true if the state is new)
+    fn index_of(&mut self, state: S) -> (usize, bool) {
+        if let Some(&index) = self.indices.get(&state) {
+            return (index, false);
+        }
+        self.states.push(state.clone());
+        self.indices.insert(state, self.states.len() - 1);
+        (self.states.len() - 1, true)
+    }
+
+    fn path_to(&self, goal: usize, parents: &[Option<usize>]) -> Vec<S> {
+        let mut path: Vec<S> = std::iter::successors(Some(goal), |&index| parents[index])
+            .map(|index| self.states[index].clone())
+            .collect();
+        path.reverse();
+        path
+    }
+}
+
+pub fn bfs<S, I>(start: S, mut successors: impl FnMut(&S) -> I, mut is_goal: impl FnMut(&S) -> bool) -> Option<Path<S, usize>>
+where S: Clone + Eq + Hash, I: IntoIterator<Item = S> {
+    let mut seen = Seen::new(start);
+    let mut parents = vec![None];
+    let mut distances = vec![0];
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        if is_goal(&seen.states[index]) {
+            return Some(Path { states: seen.path_to(index, &parents), cost: distances[index] });
+        }
+        for next in successors(&seen.states[index]) {
+            let (next, new) = seen.index_of(next);
+            if new {
+                parents.push(Some(index));
+                distances.push(distances[index] + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+pub fn bfs_all<S, I>(start: S, mut successors: impl FnMut(&S) -> I, mut is_goal: impl FnMut(&S) -> bool) -> Option<AllShortestPaths<S, usize>>
+where S: Clone + Eq + Hash, I: IntoIterator<Item = S> {
+    let mut seen = Seen::new(start);
+    let mut parents = vec![Vec::new()];
+    let mut distances = vec![0];
+    let mut queue = VecDeque::from([0]);
+    let mut goals = Vec::new();
+    while let Some(index) = queue.pop_front() {
+        // the whole level of the first goal is done, every parent of it is known
+        if goals.first().is_some_and(|&goal| distances[index] > distances[goal]) {
+            break;
+        }
+        if is_goal(&seen.states[index]) {
+            goals.push(index);
+            continue;
+        }
+        for next in successors(&seen.states[index]) {
+            let (next, new) = seen.index_of(next);
+            if new {
+                parents.push(vec![index]);
+                distances.push(distances[index] + 1);
+                queue.push_back(next);
+            } else if distances[next] == distances[index] + 1 && !parents[next].contains(&index) {
+                parents[next].push(index);
+            }
+        }
+    }
+    let cost = distances[*goals.first()?];
+    Some(AllShortestPaths { states: seen.states, parents, goals, cost })
+}
+
+// distance to every state reachable from start
+pub fn bfs_distances<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> HashMap<S, usize>
+where S: Clone + Eq + Hash, I: IntoIterator<Item = S> {
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        let distance = distances[&state];
+        for next in successors(&state) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+pub fn dijkstra<S, C, I>(start: S, successors: impl FnMut(&S) -> I, is_goal: impl FnMut(&S) -> bool) -> Option<Path<S, C>>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)> {
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+pub fn dijkstra_all<S, C, I>(start: S, successors: impl FnMut(&S) -> I, is_goal: impl FnMut(&S) -> bool) -> Option<AllShortestPaths<S, C>>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)> {
+    astar_all(start, successors, |_| C::default(), is_goal)
+}
+
+// cost of the cheapest path to every state reachable from start
+pub fn dijkstra_distances<S, C, I>(start: S, successors: impl FnMut(&S) -> I) -> HashMap<S, C>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)> {
+    let mut search = BestFirst::new(start, successors, |_| C::default());
+    while let Some(index) = search.pop() {
+        search.expand(index, false);
+    }
+    let BestFirst { seen, costs, .. } = search;
+    seen.states.into_iter().zip(costs).collect()
+}
+
+// The heuristic has to be admissible: never more than the real remaining cost, or the path
+// found may not be the shortest. A consistent one (that never drops by more than the cost of
+// a step) keeps every state from being expanded more than once, but isn't required
+pub fn astar<S, C, I>(start: S, successors: impl FnMut(&S) -> I, heuristic: impl FnMut(&S) -> C, mut is_goal: impl FnMut(&S) -> bool) -> Option<Path<S, C>>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)> {
+    let mut search = BestFirst::new(start, successors, heuristic);
+    while let Some(index) = search.pop() {
+        if is_goal(&search.seen.states[index]) {
+            let parents: Vec<Option<usize>> = search.parents.iter().map(|parents| parents.first().copied()).collect();
+            return Some(Path { states: search.seen.path_to(index, &parents), cost: search.costs[index] });
+        }
+        search.expand(index, false);
+    }
+    None
+}
+
+pub fn astar_all<S, C, I>(start: S, successors: impl FnMut(&S) -> I, heuristic: impl FnMut(&S) -> C, mut is_goal: impl FnMut(&S) -> bool) -> Option<AllShortestPaths<S, C>>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)> {
+    let mut search = BestFirst::new(start, successors, heuristic);
+    let mut goals: Vec<usize> = Vec::new();
+    let mut best = None;
+    // everything that could still be as cheap as the first goal has to be expanded,
+    // it may be another goal or another way into one
+    while search.peek_estimate().is_some_and(|estimate| best.is_none_or(|best| estimate <= best)) {
+        let Some(index) = search.pop() else { break };
+        if is_goal(&search.seen.states[index]) {
+            let cost = search.costs[index];
+            best = best.or(Some(cost));
+            if best == Some(cost) && !goals.contains(&index) {
+                goals.push(index);
+            }
+        }
+        search.expand(index, true);
+    }
+    let BestFirst { seen, parents, .. } = search;
+    Some(AllShortestPaths { states: seen.states, parents, goals, cost: best? })
+}
+
+// the heap and bookkeeping shared by dijkstra and astar
+struct BestFirst<S, C, F, H> {
+    seen: Seen<S>,
+    // cheapest known cost of every state
+    costs: Vec<C>,
+    // the predecessors a state is reached from at its cheapest known cost
+    parents: Vec<Vec<usize>>,
+    // (cost so far + estimate, cost so far, state), smallest first
+    heap: BinaryHeap<Reverse<(C, C, usize)>>,
+    successors: F,
+    heuristic: H
+}
+
+impl<S, C, I, F, H> BestFirst<S, C, F, H>
+where S: Clone + Eq + Hash, C: Cost, I: IntoIterator<Item = (S, C)>, F: FnMut(&S) -> I, H: FnMut(&S) -> C {
+    fn new(start: S, successors: F, mut heuristic: H) -> Self {
+        let estimate = heuristic(&start);
+        BestFirst {
+            seen: Seen::new(start),
+            costs: vec![C::default()],
+            parents: vec![Vec::new()],
+            heap: BinaryHeap::from([Reverse((estimate, C::default(), 0))]),
+            successors,
+            heuristic
+        }
+    }
+
+    fn peek_estimate(&mut self) -> Option<C> {
+        self.skip_stale();
+        self.heap.peek().map(|Reverse((estimate, _, _))| *estimate)
+    }
+
+    // states get pushed again when a cheaper way to them shows up, the old entries are skipped
+    fn skip_stale(&mut self) {
+        while let Some(Reverse((_, cost, index))) = self.heap.peek() {
+            if *cost == self.costs[*index] {
+                return;
+            }
+            self.heap.pop();
+        }
+    }
+
+    // the cheapest state that wasn't expanded at its current cost yet
+    fn pop(&mut self) -> Option<usize> {
+        self.skip_stale();
+        let Reverse((_, _, index)) = self.heap.pop()?;
+        Some(index)
+    }
+
+    fn expand(&mut self, index: usize, keep_ties: bool) {
+        let cost = self.costs[index];
+        for (next, step) in (self.successors)(&self.seen.states[index]) {
+            let next_cost = cost + step;
+            let (next, new) = self.seen.index_of(next);
+            if new {
+                self.costs.push(next_cost);
+                self.parents.push(vec![index]);
+            } else if next_cost == self.costs[next] {
+                if keep_ties && !self.parents[next].contains(&index) {
+                    self.parents[next].push(index);
+                }
+                continue;
+            } else if next_cost < self.costs[next] {
+                self.costs[next] = next_cost;
+                self.parents[next] = vec![index];
+            } else {
+                continue;
+            }
+            let estimate = next_cost + (self.heuristic)(&self.seen.states[next]);
+            self.heap.push(Reverse((estimate, next_cost, next)));
+        }
+    }
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> AllShortestPaths<S, C> {
+    pub fn cost(&self) -> C {
+        self.cost
+    }
+
+    pub fn goals(&self) -> impl Iterator<Item = &S> {
+        self.goals.iter().map(|&goal| &self.states[goal])
+    }
+
+    // how many different shortest paths there are, without listing them
+    pub fn count(&self) -> u128 {
+        let mut counts: HashMap<usize, u128> = HashMap::new();
+        self.goals.iter().map(|&goal| self.count_to(goal, &mut counts)).sum()
+    }
+
+    fn count_to(&self, index: usize, counts: &mut HashMap<usize, u128>) -> u128 {
+        // post-order without recursion: a state is counted once all of its parents are,
+        // the start is the only state without parents
+        let mut stack = vec![(index, false)];
+        let mut entered = HashSet::new();
+        while let Some((cur, parents_done)) = stack.pop() {
+            if counts.contains_key(&cur) || (!parents_done && !entered.insert(cur)) {
+                continue;
+            }
+            if parents_done {
+                let count = if self.parents[cur].is_empty() {
+                    1
+                } else {
+                    // a parent can only be missing on a cycle of zero cost steps, which isn't counted
+                    self.parents[cur].iter().map(|parent| counts.get(parent).copied().unwrap_or(0)).sum()
+                };
+                counts.insert(cur, count);
+            } else {
+                stack.push((cur, true));
+                stack.extend(self.parents[cur].iter().filter(|parent| !counts.contains_key(parent)).map(|&parent| (parent, false)));
+            }
+        }
+        counts[&index]
+    }
+
+    // every state that is on at least one of the paths
+    pub fn states_on_paths(&self) -> HashSet<S> {
+        let mut on_paths = HashSet::new();
+        let mut stack = self.goals.clone();
+        while let Some(index) = stack.pop() {
+            if on_paths.insert(index) {
+                stack.extend(&self.parents[index]);
+            }
+        }
+        on_paths.into_iter().map(|index| self.states[index].clone()).collect()
+    }
+
+    // every path from the start to a goal, careful - there can be exponentially many,
+    // and infinitely many if zero cost steps go around in a circle
+    pub fn paths(&self) -> Vec<Vec<S>> {
+        let mut paths = Vec::new();
+        // the path from a goal back to where we are, and which parent to try next at every step
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for &goal in &self.goals {
+            stack.push((goal, 0));
+            while let Some(&mut (index, ref mut next_parent)) = stack.last_mut() {
+                if self.parents[index].is_empty() {
+                    paths.push(stack.iter().rev().map(|&(index, _)| self.states[index].clone()).collect());
+                    stack.pop();
+                } else if let Some(&parent) = self.parents[index].get(*next_parent) {
+                    *next_parent += 1;
+                    stack.push((parent, 0));
+                } else {
+                    stack.pop();
+                }
+            }
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{graph::{Direction, Graph}, random::Rng};
+
+    // '#' are walls, S and E the start and the end
+    const MAZE: [&str; 5] = [
+        "S..#....",
+        ".#.#.##.",
+        ".#...#..",
+        ".####.#.",
+        "......#E"
+    ];
+
+    fn find(grid: &[&str], c: char) -> (i32, i32) {
+        grid.iter().enumerate()
+            .find_map(|(row, line)| line.find(c).map(|col| (row as i32, col as i32)))
+            .unwrap()
+    }
+
+    fn open_neighbors<'a>(grid: &'a [&str]) -> impl FnMut(&(i32, i32)) -> Vec<(i32, i32)> + 'a {
+        move |&(row, col)| {
+            [(row - 1, col), (row + 1, col), (row, col - 1), (row, col + 1)].into_iter()
+                .filter(|&(row, col)| {
+                    row >= 0 && col >= 0
+                        && grid.get(row as usize).and_then(|line| line.as_bytes().get(col as usize)).is_some_and(|&c| c != b'#')
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_bfs_grid() {
+        let (start, end) = (find(&MAZE, 'S'), find(&MAZE, 'E'));
+        let path = bfs(start, open_neighbors(&MAZE), |&state| state == end).unwrap();
+        assert_eq!(path.cost, 15);
+        assert_eq!(path.states.len(), 16);
+        assert_eq!((path.states[0], path.states[15]), (start, end));
+        assert!(path.states.windows(2).all(|pair| (pair[0].0 - pair[1].0).abs() + (pair[0].1 - pair[1].1).abs() == 1));
+
+        assert!(bfs(start, open_neighbors(&MAZE), |&state| state == (0, 3)).is_none());
+        assert_eq!(bfs(start, open_neighbors(&MAZE), |&state| state == start).unwrap().states, vec![start]);
+
+        let distances = bfs_distances(start, open_neighbors(&MAZE));
+        assert_eq!(distances[&end], 15);
+        assert_eq!(distances.len(), MAZE.iter().map(|line| line.chars().filter(|&c| c != '#').count()).sum::<usize>());
+    }
+
+    #[test]
+    fn test_all_paths_grid() {
+        // open 3x3 room, corner to corner: 6 paths of length 4
+        let room = ["...", "...", "..."];
+        let all = bfs_all((0, 0), open_neighbors(&room), |&state| state == (2, 2)).unwrap();
+        assert_eq!(all.cost(), 4);
+        assert_eq!(all.count(), 6);
+        let paths = all.paths();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.iter().all(|path| path.len() == 5 && path[0] == (0, 0) && path[4] == (2, 2)));
+        assert_eq!(all.states_on_paths().len(), 9);
+
+        // both routes around the wall are equally long, the dead end isn't on either of them
+        let loop_room = ["...", ".#.", "...", "#.#"];
+        let all = bfs_all((0, 1), open_neighbors(&loop_room), |&state| state == (2, 1)).unwrap();
+        assert_eq!((all.cost(), all.count()), (4, 2));
+        assert!(!all.states_on_paths().contains(&(3, 1)));
+    }
+
+    #[test]
+    fn test_several_goals() {
+        let line = ["....."];
+        // two goals at the same distance, one further away
+        let all = bfs_all((0, 2), open_neighbors(&line), |&(_, col)| col != 2).unwrap();
+        assert_eq!(all.cost(), 1);
+        let mut goals: Vec<_> = all.goals().copied().collect();
+        goals.sort();
+        assert_eq!(goals, vec![(0, 1), (0, 3)]);
+        let weighted = dijkstra_all((0, 2), |&(row, col): &(i32, i32)| {
+            open_neighbors(&line)(&(row, col)).into_iter().map(move |next| (next, if next.1 < col { 1 } else { 2 }))
+        }, |&(_, col)| col != 2).unwrap();
+        assert_eq!((weighted.cost(), weighted.goals().collect::<Vec<_>>()), (1, vec![&(0, 1)]));
+    }
+
+    // weighted: stepping onto a digit costs the digit
+    const COSTS: [&str; 4] = [
+        "1163751",
+        "1381373",
+        "2136511",
+        "3694931"
+    ];
+
+    fn weighted_neighbors(&(row, col): &(i32, i32)) -> Vec<((i32, i32), u32)> {
+        open_neighbors(&COSTS)(&(row, col)).into_iter()
+            .map(|(row, col)| ((row, col), COSTS[row as usize].as_bytes()[col as usize] as u32 - '0' as u32))
+            .collect()
+    }
+
+    #[test]
+    fn test_dijkstra_and_astar_agree() {
+        let end = (3, 6);
+        let by_dijkstra = dijkstra((0, 0), weighted_neighbors, |&state| state == end).unwrap();
+        // every step costs at least 1, so the manhattan distance never overestimates
+        let manhattan = |&(row, col): &(i32, i32)| ((end.0 - row).abs() + (end.1 - col).abs()) as u32;
+        let by_astar = astar((0, 0), weighted_neighbors, manhattan, |&state| state == end).unwrap();
+        assert_eq!(by_dijkstra.cost, by_astar.cost);
+        let path_cost = |path: &Path<(i32, i32), u32>| path.states.windows(2)
+            .map(|pair| weighted_neighbors(&pair[0]).into_iter().find(|(next, _)| *next == pair[1]).unwrap().1)
+            .sum::<u32>();
+        assert_eq!(path_cost(&by_dijkstra), by_dijkstra.cost);
+        assert_eq!(path_cost(&by_astar), by_astar.cost);
+
+        let all_dijkstra = dijkstra_all((0, 0), weighted_neighbors, |&state| state == end).unwrap();
+        let all_astar = astar_all((0, 0), weighted_neighbors, manhattan, |&state| state == end).unwrap();
+        assert_eq!((all_dijkstra.cost(), all_dijkstra.count()), (all_astar.cost(), all_astar.count()));
+        assert_eq!(all_dijkstra.states_on_paths(), all_astar.states_on_paths());
+        assert!(all_dijkstra.paths().contains(&by_dijkstra.states));
+
+        let distances = dijkstra_distances((0, 0), weighted_neighbors);
+        assert_eq!(distances[&end], by_dijkstra.cost);
+        assert_eq!(distances[&(0, 0)], 0);
+        assert_eq!(distances.len(), 28);
+    }
+
+    #[test]
+    fn test_on_index_graph() {
+        let (graph, ids) = Graph::from_edges(Direction::Undirected, vec![
+            ('a', 'b', 7), ('a', 'c', 9), ('a', 'f', 14), ('b', 'c', 10), ('b', 'd', 15),
+            ('c', 'd', 11), ('c', 'f', 2), ('d', 'e', 6), ('e', 'f', 9)
+        ]);
+        let successors = |&id: &usize| graph.edges_from(id).iter().map(|edge| (edge.to, edge.weight));
+        let path = dijkstra(ids[&'a'], successors, |&id| id == ids[&'e']).unwrap();
+        assert_eq!(path.cost, 20);
+        assert_eq!(path.states.iter().map(|&id| *graph.node(id)).collect::<String>(), "acfe");
+        let hops = bfs(ids[&'a'], |&id| graph.neighbors(id).collect::<Vec<_>>(), |&id| id == ids[&'e']).unwrap();
+        assert_eq!(hops.cost, 2);
+    }
+
+    // random graphs against Floyd-Warshall, which counts the shortest paths too
+    #[test]
+    fn test_random_graphs() {
+        let mut rng = Rng::new(43);
+        for _ in 0..200 {
+            let n = rng.gen_range(1..9);
+            let mut weights = vec![vec![None; n]; n];
+            for _ in 0..rng.gen_range(0..n * n) {
+                let (from, to) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                if from != to {
+                    weights[from][to] = Some(rng.gen_range(0..5) as u64);
+                }
+            }
+            // zero weight edges make paths of equal cost but different length,
+            // counts only work out with positive weights
+            let positive: Vec<Vec<Option<u64>>> = weights.iter().map(|row| row.iter().map(|w| w.map(|w| w + 1)).collect()).collect();
+            let mut dist = vec![vec![None; n]; n];
+            for (i, row) in positive.iter().enumerate() {
+                dist[i][i] = Some(0);
+                for (j, w) in row.iter().enumerate() {
+                    if w.is_some() {
+                        dist[i][j] = *w;
+                    }
+                }
+            }
+            for k in 0..n {
+                for i in 0..n {
+                    for j in 0..n {
+                        if let (Some(a), Some(b)) = (dist[i][k], dist[k][j]) {
+                            if dist[i][j].is_none_or(|d| a + b < d) {
+                                dist[i][j] = Some(a + b);
+                            }
+                        }
+                    }
+                }
+            }
+            let successors = |&i: &usize| positive[i].iter().enumerate().filter_map(|(j, w)| w.map(|w| (j, w))).collect::<Vec<_>>();
+            let goal = n - 1;
+            let found = dijkstra(0, successors, |&i| i == goal);
+            assert_eq!(found.as_ref().map(|path| path.cost), dist[0][goal]);
+            let all = dijkstra_all(0, successors, |&i| i == goal);
+            assert_eq!(all.as_ref().map(|all| all.cost()), dist[0][goal]);
+            if let Some(all) = all {
+                // count the paths by brute force over the states in order of distance
+                let mut order: Vec<usize> = (0..n).filter(|&i| dist[0][i].is_some()).collect();
+                order.sort_by_key(|&i| dist[0][i]);
+                let mut counts = vec![0u128; n];
+                counts[0] = 1;
+                for &j in &order[1..] {
+                    counts[j] = (0..n)
+                        .filter(|&i| matches!((dist[0][i], positive[i][j]), (Some(d), Some(w)) if d + w == dist[0][j].unwrap()))
+                        .map(|i| counts[i])
+                        .sum();
+                }
+                assert_eq!(all.count(), counts[goal]);
+                assert_eq!(all.paths().len() as u128, counts[goal]);
+            }
+            // zero weights still give the right costs
+            let with_zeros = dijkstra_distances(0, |&i: &usize| weights[i].iter().enumerate().filter_map(|(j, w)| w.map(|w| (j, w))).collect::<Vec<_>>());
+            let hops = bfs_distances(0, |&i: &usize| (0..n).filter(|&j| weights[i][j].is_some()).collect::<Vec<_>>());
+            assert_eq!(with_zeros.len(), hops.len());
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::{any::Any, fmt::{self, Debug, Display, Formatter}, panic::{self, AssertUnwindSafe}, sync::{atomic::{AtomicBool, Ordering}, mpsc, Mutex}, thread};
+
+// Running a bunch of independent jobs, one after another or on several threads.
+//
+// A Task is anything that computes a value, closures included. A TaskQueue collects boxed
+// tasks of different types as long as they produce the same kind of output, and an Executor
+// runs the whole queue and hands the results back in the order the tasks were pushed,
+// no matter which one finished first.
+//
+// With PanicPolicy::Isolate a panicking task just gets an Err in its slot and the others carry
+// on. With PanicPolicy::Propagate the panic reaches the caller of run, with its original
+// payload. The sequential executor stops right there. The pool stops handing out tasks once
+// one panicked, lets the ones already running finish and then re-raises the panic of the
+// earliest task.
+
+pub trait Task: Send {
+    type Output;
+
+    fn execute(&self) -> Self::Output;
+}
+
+impl<O, F: Fn() -> O + Send> Task for F {
+    type Output = O;
+
+    fn execute(&self) -> O {
+        self()
+    }
+}
+
+// tasks can borrow whatever outlives the queue, the pool runs them on scoped threads
+pub struct TaskQueue<'a, O> {
+    tasks: Vec<Box<dyn Task<Output = O> + 'a>>
+}
+
+impl<'a, O> TaskQueue<'a, O> {
+    pub fn new() -> Self {
+        TaskQueue { tasks: Vec::new() }
+    }
+
+    // the index of the task, which is also where its result ends up
+    pub fn push(&mut self, task: impl Task<Output = O> + 'a) -> usize {
+        self.push_boxed(Box::new(task))
+    }
+
+    pub fn push_boxed(&mut self, task: Box<dyn Task<Output = O> + 'a>) -> usize {
+        self.tasks.push(task);
+        self.tasks.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+impl<O> Default for TaskQueue<'_, O> {
+    fn default() -> Self {
+        TaskQueue::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicPolicy {
+    Propagate,
+    Isolate
+}
+
+pub struct TaskPanic {
+    pub index: usize,
+    pub message: String,
+    payload: Box<dyn Any + Send>
+}
+
+impl TaskPanic {
+    fn new(index: usize, payload: Box<dyn Any + Send>) -> Self {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic with a non string payload".to_owned());
+        TaskPanic { index, message, payload }
+    }
+
+    // panics again with the payload the task panicked with
+    pub fn resume(self) -> ! {
+        panic::resume_unwind(self.payload)
+    }
+}
+
+impl Debug for TaskPanic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskPanic").field("index", &self.index).field("message", &self.message).finish()
+    }
+}
+
+impl Display for TaskPanic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "task {} panicked: {}", self.index, self.message)
+    }
+}
+
+pub type TaskResult<O> = Result<O, TaskPanic>;
+
+pub trait Executor {
+    fn run<O: Send>(&self, queue: TaskQueue<'_, O>) -> Vec<TaskResult<O>>;
+}
+
+fn execute_caught<O>(index: usize, task: &dyn Task<Output = O>) -> TaskResult<O> {
+    panic::catch_unwind(AssertUnwindSafe(|| task.execute())).map_err(|payload| TaskPanic::new(index, payload))
+}
+
+// runs the tasks one by one on the calling thread
+#[derive(Clone, Copy, Debug)]
+pub struct Sequential {
+    policy: PanicPolicy
+}
+
+impl Sequential {
+    pub fn new(policy: PanicPolicy) -> Self {
+        Sequential { policy }
+    }
+}
+
+impl Executor for Sequential {
+    fn run<O: Send>(&self, queue: TaskQueue<'_, O>) -> Vec<TaskResult<O>> {
+        queue.tasks.into_iter().enumerate().map(|(index, task)| {
+            match self.policy {
+                PanicPolicy::Propagate => Ok(task.execute()),
+                PanicPolicy::Isolate => execute_caught(index, task.as_ref())
+            }
+        }).collect()
+    }
+}
+
+// A fixed number of worker threads taking the next task off the queue whenever they are free.
+// The workers only live for one run, so the tasks don't have to be 'static
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadPool {
+    threads: usize,
+    policy: PanicPolicy
+}
+
+impl ThreadPool {
+    pub fn new(threads: usize, policy: PanicPolicy) -> Self {
+        assert!(threads > 0, "a pool needs at least one thread");
+        ThreadPool { threads, policy }
+    }
+
+    // as many threads as the machine can run at once
+    pub fn with_available_parallelism(policy: PanicPolicy) -> Self {
+        ThreadPool::new(thread::available_parallelism().map_or(1, |threads| threads.get()), policy)
+    }
+}
+
+impl Executor for ThreadPool {
+    fn run<O: Send>(&self, queue: TaskQueue<'_, O>) -> Vec<TaskResult<O>> {
+        let count = queue.len();
+        let tasks = Mutex::new(queue.tasks.into_iter().enumerate());
+        // only ever set under Propagate, the first panic means nobody starts another task
+        let stop = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(count) {
+                let (tasks, stop, sender) = (&tasks, &stop, sender.clone());
+                scope.spawn(move || loop {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    // the lock is only held while taking a task, so a panicking task can't poison it
+                    let next = tasks.lock().unwrap().next();
+                    let Some((index, task)) = next else { break };
+                    let result = execute_caught(index, task.as_ref());
+                    if result.is_err() && self.policy == PanicPolicy::Propagate {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    sender.send((index, result)).unwrap();
+                });
+            }
+        });
+        drop(sender);
+        let mut results: Vec<Option<TaskResult<O>>> = (0..count).map(|_| None).collect();
+        for (index, result) in receiver {
+            results[index] = Some(result);
+        }
+        // tasks are taken in order, so the ones that never started all come after every one that did
+        if self.policy == PanicPolicy::Propagate {
+            if let Some(first) = results.iter().position(|result| matches!(result, Some(Err(_)))) {
+                if let Some(Err(panic)) = results.swap_remove(first) {
+                    panic.resume();
+                }
+            }
+        }
+        let results: Vec<TaskResult<O>> = results.into_iter().map(|result| result.expect("every task sends its result")).collect();
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{panic, sync::{atomic::{AtomicUsize, Ordering}, Barrier}, thread, time::Duration};
+
+    use super::*;
+
+    struct Square(u64);
+
+    impl Task for Square {
+        type Output = u64;
+
+        fn execute(&self) -> u64 {
+            self.0 * self.0
+        }
+    }
+
+    type Run = Box<dyn Fn(TaskQueue<'_, u64>) -> Vec<TaskResult<u64>>>;
+
+    // the same queue through every executor
+    fn executors(policy: PanicPolicy) -> Vec<Run> {
+        vec![
+            Box::new(move |queue| Sequential::new(policy).run(queue)),
+            Box::new(move |queue| ThreadPool::new(1, policy).run(queue)),
+            Box::new(move |queue| ThreadPool::new(4, policy).run(queue))
+        ]
+    }
+
+    fn values(results: Vec<TaskResult<u64>>) -> Vec<u64> {
+        results.into_iter().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_results_in_submission_order() {
+        for run in executors(PanicPolicy::Propagate) {
+            let mut queue = TaskQueue::new();
+            for i in 0..20 {
+                // the early tasks take the longest, so they finish last on the pool
+                queue.push(move || {
+                    thread::sleep(Duration::from_millis(20 - i));
+                    i
+                });
+                queue.push(Square(i));
+            }
+            let expected: Vec<u64> = (0..20).flat_map(|i| [i, i * i]).collect();
+            assert_eq!(values(run(queue)), expected);
+        }
+        assert!(Sequential::new(PanicPolicy::Isolate).run(TaskQueue::<u64>::new()).is_empty());
+        assert!(ThreadPool::new(3, PanicPolicy::Isolate).run(TaskQueue::<u64>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_tasks_borrow_their_data() {
+        let data: Vec<u64> = (1..=100).collect();
+        let done = AtomicUsize::new(0);
+        let mut queue = TaskQueue::new();
+        for chunk in data.chunks(10) {
+            let done = &done;
+            queue.push(move || {
+                done.fetch_add(1, Ordering::Relaxed);
+                chunk.iter().sum::<u64>()
+            });
+        }
+        let sums = values(ThreadPool::new(3, PanicPolicy::Propagate).run(queue));
+        assert_eq!(sums.iter().sum::<u64>(), 5050);
+        assert_eq!(sums[0], 55);
+        assert_eq!(done.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn test_pool_runs_tasks_at_the_same_time() {
+        // every task waits for all the others, one thread alone would wait forever
+        let barrier = Barrier::new(4);
+        let mut queue = TaskQueue::new();
+        for i in 0..4 {
+            let barrier = &barrier;
+            queue.push(move || {
+                barrier.wait();
+                i
+            });
+        }
+        assert_eq!(values(ThreadPool::new(4, PanicPolicy::Propagate).run(queue)), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_isolated_panics() {
+        for run in executors(PanicPolicy::Isolate) {
+            let mut queue = TaskQueue::new();
+            queue.push(Square(3));
+            queue.push(|| -> u64 { panic!("task {} failed", 1) });
+            queue.push_boxed(Box::new(Square(4)));
+            queue.push(|| -> u64 { panic!("static message") });
+            let results = run(queue);
+            assert_eq!(results.len(), 4);
+            assert_eq!(results[0].as_ref().ok(), Some(&9));
+            assert_eq!(results[2].as_ref().ok(), Some(&16));
+            let panic = results[1].as_ref().unwrap_err();
+            assert_eq!((panic.index, panic.message.as_str()), (1, "task 1 failed"));
+            assert_eq!(results[3].as_ref().unwrap_err().to_string(), "task 3 panicked: static message");
+        }
+    }
+
+    #[test]
+    fn test_propagated_panics() {
+        for run in executors(PanicPolicy::Propagate) {
+            let started = AtomicUsize::new(0);
+            let mut queue = TaskQueue::new();
+            for i in 0..100 {
+                let started = &started;
+                queue.push(move || {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    if i == 1 || i == 3 {
+                        panic!("odd task {}", i);
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                    i
+                });
+            }
+            let payload = panic::catch_unwind(AssertUnwindSafe(|| run(queue))).unwrap_err();
+            // the earliest panic wins, with the payload it was raised with
+            assert_eq!(payload.downcast_ref::<String>().map(String::as_str), Some("odd task 1"));
+            // nothing new starts after a panic, four threads may have a few more running by then
+            let started = started.load(Ordering::SeqCst);
+            assert!((2..100).contains(&started), "{} tasks started", started);
+        }
+        // with a single thread it is exactly the tasks up to the first panic
+        for run in &executors(PanicPolicy::Propagate)[..2] {
+            let started = AtomicUsize::new(0);
+            let mut queue = TaskQueue::new();
+            for i in 0..6 {
+                let started = &started;
+                queue.push(move || {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    if i == 2 {
+                        panic!("third task");
+                    }
+                    i
+                });
+            }
+            assert!(panic::catch_unwind(AssertUnwindSafe(|| run(queue))).is_err());
+            assert_eq!(started.load(Ordering::SeqCst), 3);
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use crate::common::{file::read_lines_from_file, strings::matches_substr_on_index};
 
 pub fn day1() {
-    let res = get_calibration(".\\src\\day1\\input\\part1-whole.txt");
+    let res = solve();
     println!("Res: {}", res);
 }
 
+pub fn solve() -> u32 {
+    get_calibration(".\\src\\day1\\input\\part1-whole.txt")
+}
+
 fn get_calibration(file_name: &str) -> u32 {
     match read_lines_from_file(file_name) {
         Ok(lines) => {
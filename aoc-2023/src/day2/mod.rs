@@ -1,10 +1,13 @@
-use crate::common::file::read_lines_from_file;
+use crate::common::{file::read_lines_from_file, num::CheckedIterator};
 
 pub fn day2() {
-    let res = part1(".\\src\\day2\\inputs\\part1-whole.txt");
-    println!("part1 res: {res}");
-    let res = part2(".\\src\\day2\\inputs\\part1-whole.txt");
-    println!("part2 res: {res}");
+    let (res1, res2) = solve();
+    println!("part1 res: {res1}");
+    println!("part2 res: {res2}");
+}
+
+pub fn solve() -> (u32, u32) {
+    (part1(".\\src\\day2\\inputs\\part1-whole.txt"), part2(".\\src\\day2\\inputs\\part1-whole.txt"))
 }
 
 const AVAILABLE_CUBES: CubeSet = CubeSet{red:12, green:13, blue:14};
@@ -28,16 +31,14 @@ fn part1(file_name: &str) -> u32 {
 fn part2(file_name: &str) -> u32 {
     match read_lines_from_file(file_name) {
         Ok(lines) => {
-            let mut sum: u32 = 0;
-            for line in &lines {
-                let game = parse_game(line);
-                let power = game.get_required_cube_set().get_power();
-                sum += power;
-            }
-            return sum;
+            // a plain += would silently wrap around in release builds
+            lines.iter()
+                .map(|line| parse_game(line).get_required_cube_set().get_power())
+                .checked_sum()
+                .expect("the sum of the powers doesn't fit into a u32")
         },
         Err(err) => panic!("{}, {}", err.to_string(), file_name)
-    };
+    }
 }
 
 fn parse_game(line: &str) -> Game {
@@ -98,7 +99,9 @@ impl CubeSet {
     }
 
     fn get_power(&self) -> u32 {
-        self.red * self.blue * self.green
+        [self.red, self.blue, self.green].into_iter()
+            .checked_product()
+            .expect("the power of a cube set doesn't fit into a u32")
     }
 }
 
@@ -145,6 +148,18 @@ mod tests {
         assert_eq!(parse_game("Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green"), g1);
     }
 
+    #[test]
+    fn test_power() {
+        assert_eq!(parse_cube_set("4 red, 2 green, 6 blue").get_power(), 48);
+        assert_eq!(CubeSet::empty().get_power(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit into a u32")]
+    fn test_power_overflow() {
+        parse_cube_set("70000 red, 70000 green, 1 blue").get_power();
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1(".\\src\\day2\\inputs\\part1-example.txt"), 8);
@@ -4,10 +4,14 @@ use std::{iter::Map, collections::HashSet, clone};
 use crate::common::file::read_lines_from_file;
 
 pub fn day3() {
-    let res = part1(".\\src\\day3\\inputs\\part1-whole.txt");
+    let res = solve();
     println!("Res: {}", res);
 }
 
+pub fn solve() -> u32 {
+    part1(".\\src\\day3\\inputs\\part1-whole.txt")
+}
+
 fn part1(file_name: &str) -> u32 {
     let engine_schema = parse_engine(file_name);
     engine_schema.get_number_part_sum()
@@ -149,6 +153,7 @@ fn find_numbers(engine_schema: &Vec<String>) -> EngineSchema {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::linked_lists::sorted_doubly_linked_list::{List, EqualPlacement};
 
     #[test]
     pub fn test_parsing(){
@@ -183,4 +188,35 @@ mod tests {
         // println!("{:?}", poss);
         // assert_eq!(num.is_part_number(&engine.symbols), true);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_sorted_number_records(){
+        let records = vec![
+            NumberRecord::new(467, &Coord{x:0, y:0}, 3),
+            NumberRecord::new(114, &Coord{x:5, y:0}, 3),
+            NumberRecord::new(35, &Coord{x:2, y:2}, 2),
+            NumberRecord::new(633, &Coord{x:6, y:2}, 3),
+            NumberRecord::new(35, &Coord{x:0, y:4}, 2),
+        ];
+
+        let mut by_value = List::with_key(|record: &NumberRecord| record.value).with_placement(EqualPlacement::Stable);
+        let mut by_position = List::with_key(|record: &NumberRecord| (record.y, record.x));
+        for record in records {
+            by_position.insert(NumberRecord::new(record.value, &Coord{x:record.x, y:record.y}, record.num_digits));
+            by_value.insert(record);
+        }
+
+        let mut values = Vec::new();
+        while let Some(record) = by_value.pop_front() {
+            values.push((record.value, record.y));
+        }
+        // the two 35s stay in the order they were inserted in
+        assert_eq!(values, vec![(35, 2), (35, 4), (114, 0), (467, 0), (633, 2)]);
+
+        let mut positions = Vec::new();
+        while let Some(record) = by_position.pop_front() {
+            positions.push((record.x, record.y));
+        }
+        assert_eq!(positions, vec![(0, 0), (5, 0), (2, 2), (6, 2), (0, 4)]);
+    }
+}
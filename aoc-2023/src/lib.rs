@@ -1,4 +1,4 @@
-mod common;
+pub mod common;
 
 pub mod day1;
 pub mod day2;
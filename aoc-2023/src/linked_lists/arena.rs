@@ -0,0 +1,743 @@
+use std::{fmt::{self, Debug, Display, Formatter}, mem, str::FromStr};
+
+use super::{collection::Deque, text::{self, ParseListError}};
+use crate::common::dot::Dot;
+
+// Doubly linked lists without Rc<RefCell<Node>>: all nodes live in one Vec (the arena) and
+// link to each other by index. A node costs no allocation of its own and no refcount, and
+// there are no reference cycles to worry about when the whole thing is dropped.
+//
+// Outside code gets a Handle for every node. A handle is the slot index plus the generation
+// of the slot - freed slots are reused, and every reuse bumps the generation, so a handle to a
+// removed node never silently points to whatever got stored in its slot later.
+// Handles stay valid through any other mutation, nothing ever moves to a different slot.
+//
+// One arena can hold several lists (each identified by a ListId) and a node can be moved
+// between them, or unlinked and kept around for later, all in O(1).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListId(usize);
+
+#[derive(Clone)]
+struct Node<T> {
+    elem: T,
+    prev: Option<usize>,
+    next: Option<usize>,
+    // None while the node is unlinked
+    list: Option<ListId>
+}
+
+#[derive(Clone)]
+enum Slot<T> {
+    Occupied(Node<T>),
+    // free slots form a singly linked list of their own
+    Free { next_free: Option<usize> }
+}
+
+#[derive(Clone)]
+struct Entry<T> {
+    generation: u64,
+    slot: Slot<T>
+}
+
+#[derive(Clone, Copy, Default)]
+struct Ends {
+    head: Option<usize>,
+    tail: Option<usize>,
+    len: usize
+}
+
+#[derive(Clone)]
+pub struct Arena<T> {
+    entries: Vec<Entry<T>>,
+    free_head: Option<usize>,
+    lists: Vec<Ends>,
+    // occupied slots, linked or not
+    len: usize
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena { entries: Vec::new(), free_head: None, lists: Vec::new(), len: 0 }
+    }
+
+    pub fn new_list(&mut self) -> ListId {
+        self.lists.push(Ends::default());
+        ListId(self.lists.len() - 1)
+    }
+
+    // number of nodes in the arena, including the unlinked ones
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn list_len(&self, list: ListId) -> usize {
+        self.lists[list.0].len
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.node(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.node(handle).map(|node| &node.elem)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.node_mut(handle).map(|node| &mut node.elem)
+    }
+
+    // which list the node is linked into right now
+    pub fn list_of(&self, handle: Handle) -> Option<ListId> {
+        self.node(handle).and_then(|node| node.list)
+    }
+
+    pub fn front(&self, list: ListId) -> Option<Handle> {
+        self.lists[list.0].head.map(|index| self.handle(index))
+    }
+
+    pub fn back(&self, list: ListId) -> Option<Handle> {
+        self.lists[list.0].tail.map(|index| self.handle(index))
+    }
+
+    pub fn next(&self, handle: Handle) -> Option<Handle> {
+        self.node(handle).and_then(|node| node.next).map(|index| self.handle(index))
+    }
+
+    pub fn prev(&self, handle: Handle) -> Option<Handle> {
+        self.node(handle).and_then(|node| node.prev).map(|index| self.handle(index))
+    }
+
+    // a node that isn't in any list yet, it can be linked anywhere later
+    pub fn insert_unlinked(&mut self, elem: T) -> Handle {
+        let node = Node { elem, prev: None, next: None, list: None };
+        self.len += 1;
+        match self.free_head {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                self.free_head = match entry.slot {
+                    Slot::Free { next_free } => next_free,
+                    Slot::Occupied(_) => unreachable!("free list points to an occupied slot")
+                };
+                entry.slot = Slot::Occupied(node);
+                Handle { index, generation: entry.generation }
+            },
+            None => {
+                self.entries.push(Entry { generation: 0, slot: Slot::Occupied(node) });
+                Handle { index: self.entries.len() - 1, generation: 0 }
+            }
+        }
+    }
+
+    pub fn push_front(&mut self, list: ListId, elem: T) -> Handle {
+        let handle = self.insert_unlinked(elem);
+        self.link_front(list, handle);
+        handle
+    }
+
+    pub fn push_back(&mut self, list: ListId, elem: T) -> Handle {
+        let handle = self.insert_unlinked(elem);
+        self.link_back(list, handle);
+        handle
+    }
+
+    pub fn pop_front(&mut self, list: ListId) -> Option<T> {
+        self.front(list).and_then(|handle| self.remove(handle))
+    }
+
+    pub fn pop_back(&mut self, list: ListId) -> Option<T> {
+        self.back(list).and_then(|handle| self.remove(handle))
+    }
+
+    // unlinks the node and frees its slot, the handle (and every copy of it) is stale afterwards
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        if !self.unlink(handle) {
+            return None;
+        }
+        let entry = &mut self.entries[handle.index];
+        entry.generation += 1;
+        let slot = mem::replace(&mut entry.slot, Slot::Free { next_free: self.free_head });
+        self.free_head = Some(handle.index);
+        self.len -= 1;
+        match slot {
+            Slot::Occupied(node) => Some(node.elem),
+            Slot::Free { .. } => unreachable!("unlink checked the slot is occupied")
+        }
+    }
+
+    // takes the node out of its list but keeps it (and its handle) alive.
+    // Returns false only for a stale handle, unlinking an unlinked node is fine
+    pub fn unlink(&mut self, handle: Handle) -> bool {
+        let (prev, next, list) = match self.node(handle) {
+            Some(node) => (node.prev, node.next, node.list),
+            None => return false
+        };
+        let Some(list) = list else {
+            return true;
+        };
+        match prev {
+            Some(prev) => self.node_at(prev).next = next,
+            None => self.lists[list.0].head = next
+        }
+        match next {
+            Some(next) => self.node_at(next).prev = prev,
+            None => self.lists[list.0].tail = prev
+        }
+        self.lists[list.0].len -= 1;
+        let node = self.node_at(handle.index);
+        node.prev = None;
+        node.next = None;
+        node.list = None;
+        true
+    }
+
+    // All the link_* functions move the node: if it is linked somewhere already it gets unlinked first.
+    // They return false (and change nothing) for a stale handle.
+    pub fn link_front(&mut self, list: ListId, handle: Handle) -> bool {
+        if !self.unlink(handle) {
+            return false;
+        }
+        let head = self.lists[list.0].head;
+        self.link_between(list, None, head, handle.index);
+        true
+    }
+
+    pub fn link_back(&mut self, list: ListId, handle: Handle) -> bool {
+        if !self.unlink(handle) {
+            return false;
+        }
+        let tail = self.lists[list.0].tail;
+        self.link_between(list, tail, None, handle.index);
+        true
+    }
+
+    // the anchor has to be linked into some list, the node ends up in the same one
+    pub fn link_after(&mut self, anchor: Handle, handle: Handle) -> bool {
+        if anchor == handle || !self.contains(handle) || self.list_of(anchor).is_none() {
+            return false;
+        }
+        self.unlink(handle);
+        let anchor_node = self.node_at(anchor.index);
+        let (list, next) = (anchor_node.list.unwrap(), anchor_node.next);
+        self.link_between(list, Some(anchor.index), next, handle.index);
+        true
+    }
+
+    pub fn link_before(&mut self, anchor: Handle, handle: Handle) -> bool {
+        if anchor == handle || !self.contains(handle) || self.list_of(anchor).is_none() {
+            return false;
+        }
+        self.unlink(handle);
+        let anchor_node = self.node_at(anchor.index);
+        let (list, prev) = (anchor_node.list.unwrap(), anchor_node.prev);
+        self.link_between(list, prev, Some(anchor.index), handle.index);
+        true
+    }
+
+    // removes every node of the list, handles to them go stale
+    pub fn clear(&mut self, list: ListId) {
+        while self.pop_front(list).is_some() {}
+    }
+
+    pub fn iter(&self, list: ListId) -> Iter<'_, T> {
+        let ends = self.lists[list.0];
+        Iter { arena: self, front: ends.head, back: ends.tail, remaining: ends.len }
+    }
+
+    // the node has to be unlinked already, prev and next have to be neighbours in the list
+    fn link_between(&mut self, list: ListId, prev: Option<usize>, next: Option<usize>, index: usize) {
+        let node = self.node_at(index);
+        node.prev = prev;
+        node.next = next;
+        node.list = Some(list);
+        match prev {
+            Some(prev) => self.node_at(prev).next = Some(index),
+            None => self.lists[list.0].head = Some(index)
+        }
+        match next {
+            Some(next) => self.node_at(next).prev = Some(index),
+            None => self.lists[list.0].tail = Some(index)
+        }
+        self.lists[list.0].len += 1;
+    }
+
+    fn handle(&self, index: usize) -> Handle {
+        Handle { index, generation: self.entries[index].generation }
+    }
+
+    fn node(&self, handle: Handle) -> Option<&Node<T>> {
+        match self.entries.get(handle.index) {
+            Some(Entry { generation, slot: Slot::Occupied(node) }) if *generation == handle.generation => Some(node),
+            _ => None
+        }
+    }
+
+    fn node_mut(&mut self, handle: Handle) -> Option<&mut Node<T>> {
+        match self.entries.get_mut(handle.index) {
+            Some(Entry { generation, slot: Slot::Occupied(node) }) if *generation == handle.generation => Some(node),
+            _ => None
+        }
+    }
+
+    // for indices taken from links, those always point to occupied slots
+    fn node_at(&mut self, index: usize) -> &mut Node<T> {
+        match &mut self.entries[index].slot {
+            Slot::Occupied(node) => node,
+            Slot::Free { .. } => unreachable!("link points to a free slot")
+        }
+    }
+
+    fn elem_at(&self, index: usize) -> (&T, Option<usize>, Option<usize>) {
+        match &self.entries[index].slot {
+            Slot::Occupied(node) => (&node.elem, node.prev, node.next),
+            Slot::Free { .. } => unreachable!("link points to a free slot")
+        }
+    }
+}
+
+impl<T: Debug> Arena<T> {
+    // every occupied slot, named after the slot. Unlinked ones are dotted and every list gets
+    // a marker pointing at its ends
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("arena");
+        for (index, entry) in self.entries.iter().enumerate() {
+            let Slot::Occupied(node) = &entry.slot else { continue };
+            let id = format!("n{}", index);
+            let label = format!("{:?}\nslot {} gen {}", node.elem, index, entry.generation);
+            if node.list.is_some() {
+                dot.node(&id, &label);
+            } else {
+                dot.node_with(&id, &label, "style=dotted");
+            }
+            if let Some(next) = node.next {
+                dot.edge(&id, &format!("n{}", next), "next");
+            }
+            if let Some(prev) = node.prev {
+                dot.edge_with(&id, &format!("n{}", prev), "prev", "style=dashed");
+            }
+        }
+        for (list, ends) in self.lists.iter().enumerate() {
+            let id = format!("list{}", list);
+            dot.marker(&id, &format!("list {} (len {})", list, ends.len));
+            if let Some(head) = ends.head {
+                dot.edge(&id, &format!("n{}", head), "head");
+            }
+            if let Some(tail) = ends.tail {
+                dot.edge(&id, &format!("n{}", tail), "tail");
+            }
+        }
+        dot.finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    arena: &'a Arena<T>,
+    front: Option<usize>,
+    back: Option<usize>,
+    // the ends meet in the middle, counting is simpler than comparing them
+    remaining: usize
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|index| {
+            let (elem, _, next) = self.arena.elem_at(index);
+            self.front = next;
+            self.remaining -= 1;
+            elem
+        })
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|index| {
+            let (elem, prev, _) = self.arena.elem_at(index);
+            self.back = prev;
+            self.remaining -= 1;
+            elem
+        })
+    }
+}
+
+// The common case of one list owning its arena, so it can be used like any other deque.
+// Handles work the same as with a shared arena.
+#[derive(Clone)]
+pub struct ArenaList<T> {
+    arena: Arena<T>,
+    list: ListId
+}
+
+impl<T> ArenaList<T> {
+    pub fn new() -> Self {
+        let mut arena = Arena::new();
+        let list = arena.new_list();
+        ArenaList { arena, list }
+    }
+
+    pub fn len(&self) -> usize {
+        self.arena.list_len(self.list)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) -> Handle {
+        self.arena.push_front(self.list, elem)
+    }
+
+    pub fn push_back(&mut self, elem: T) -> Handle {
+        self.arena.push_back(self.list, elem)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.arena.pop_front(self.list)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.arena.pop_back(self.list)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.arena.front(self.list).and_then(|handle| self.arena.get(handle))
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.arena.back(self.list).and_then(|handle| self.arena.get(handle))
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.arena.get(handle)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.arena.get_mut(handle)
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.arena.remove(handle)
+    }
+
+    pub fn move_to_front(&mut self, handle: Handle) -> bool {
+        self.arena.link_front(self.list, handle)
+    }
+
+    pub fn move_to_back(&mut self, handle: Handle) -> bool {
+        self.arena.link_back(self.list, handle)
+    }
+
+    pub fn move_after(&mut self, anchor: Handle, handle: Handle) -> bool {
+        self.arena.link_after(anchor, handle)
+    }
+
+    pub fn move_before(&mut self, anchor: Handle, handle: Handle) -> bool {
+        self.arena.link_before(anchor, handle)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.arena.iter(self.list)
+    }
+}
+
+impl<T: Debug> ArenaList<T> {
+    pub fn to_dot(&self) -> String {
+        self.arena.to_dot()
+    }
+}
+
+impl<T> Default for ArenaList<T> {
+    fn default() -> Self {
+        ArenaList::new()
+    }
+}
+
+impl<T> Deque<T> for ArenaList<T> {
+    type ElemRef<'a> = &'a T where T: 'a;
+
+    fn push_front(&mut self, elem: T) {
+        ArenaList::push_front(self, elem);
+    }
+
+    fn push_back(&mut self, elem: T) {
+        ArenaList::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        ArenaList::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        ArenaList::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn peek_back(&self) -> Option<&T> {
+        self.back()
+    }
+
+    fn is_empty(&self) -> bool {
+        ArenaList::is_empty(self)
+    }
+}
+
+impl<T> FromIterator<T> for ArenaList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = ArenaList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for ArenaList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            ArenaList::push_back(self, elem);
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArenaList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+pub struct IntoIter<T>(ArenaList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> IntoIterator for ArenaList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArenaList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Display> Display for ArenaList<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.iter())
+    }
+}
+
+impl<T: Debug> Debug for ArenaList<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: FromStr> FromStr for ArenaList<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Arena, ArenaList};
+    use crate::common::drop_counter::DropCounter;
+
+    fn elems<T: Clone>(arena: &Arena<T>, list: super::ListId) -> Vec<T> {
+        arena.iter(list).cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = ArenaList::new();
+        assert_eq!(list.pop_front(), None);
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front(), Some(&1));
+        assert_eq!(list.back(), Some(&3));
+        assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![&3, &2, &1]);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn handles_survive_mutations() {
+        let mut list = ArenaList::new();
+        let handles: Vec<_> = (0..10).map(|elem| list.push_back(elem)).collect();
+        list.pop_front();
+        list.push_front(100);
+        assert!(list.remove(handles[5]).is_some());
+        *list.get_mut(handles[7]).unwrap() *= 10;
+        assert_eq!(list.get(handles[9]), Some(&9));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![100, 1, 2, 3, 4, 6, 70, 8, 9]);
+    }
+
+    #[test]
+    fn stale_handles() {
+        let mut list = ArenaList::new();
+        let first = list.push_back("first");
+        assert_eq!(list.remove(first), Some("first"));
+        assert_eq!(list.remove(first), None);
+        // the slot gets reused, but the old handle must not see the new element
+        let second = list.push_back("second");
+        assert_eq!(list.get(first), None);
+        assert_eq!(list.get(second), Some(&"second"));
+        assert!(!list.move_to_front(first));
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn move_nodes_around() {
+        let mut list = ArenaList::new();
+        let handles: Vec<_> = (0..5).map(|elem| list.push_back(elem)).collect();
+        assert!(list.move_to_front(handles[4]));
+        assert!(list.move_to_back(handles[0]));
+        assert!(list.move_after(handles[1], handles[3]));
+        assert!(list.move_before(handles[1], handles[2]));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![4, 2, 1, 3, 0]);
+        // moving next to itself makes no sense
+        assert!(!list.move_after(handles[1], handles[1]));
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), vec![0, 3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn lists_sharing_an_arena() {
+        let mut arena = Arena::new();
+        let todo = arena.new_list();
+        let done = arena.new_list();
+        let tasks: Vec<_> = ["a", "b", "c", "d"].into_iter().map(|task| arena.push_back(todo, task)).collect();
+
+        assert!(arena.link_back(done, tasks[1]));
+        assert!(arena.link_front(done, tasks[3]));
+        assert_eq!(elems(&arena, todo), vec!["a", "c"]);
+        assert_eq!(elems(&arena, done), vec!["d", "b"]);
+        assert_eq!(arena.list_of(tasks[1]), Some(done));
+
+        // unlinked nodes stay in the arena until they are linked again or removed
+        assert!(arena.unlink(tasks[0]));
+        assert_eq!(arena.list_of(tasks[0]), None);
+        assert_eq!((arena.list_len(todo), arena.len()), (1, 4));
+        assert!(arena.link_after(tasks[3], tasks[0]));
+        assert_eq!(elems(&arena, done), vec!["d", "a", "b"]);
+        assert_eq!(arena.next(tasks[3]), Some(tasks[0]));
+        assert_eq!(arena.prev(tasks[3]), None);
+
+        arena.clear(done);
+        assert_eq!(elems(&arena, todo), vec!["c"]);
+        assert_eq!(arena.len(), 1);
+        assert!(!arena.contains(tasks[0]));
+    }
+
+    #[test]
+    fn into_iter_both_ends() {
+        let list: ArenaList<i32> = (1..=4).collect();
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn slots_get_reused() {
+        let mut list = ArenaList::new();
+        for round in 0..100 {
+            for elem in 0..10 {
+                list.push_back(round * 10 + elem);
+            }
+            while list.pop_front().is_some() {}
+        }
+        assert_eq!(list.arena.entries.len(), 10);
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let counter = DropCounter::new();
+        let mut arena = Arena::new();
+        let first = arena.new_list();
+        let second = arena.new_list();
+        for elem in 0..100 {
+            let handle = arena.push_back(if elem % 2 == 0 { first } else { second }, counter.track(elem));
+            if elem % 3 == 0 {
+                arena.unlink(handle);
+            }
+        }
+        arena.pop_front(first);
+        assert_eq!(counter.dropped(), 1);
+        drop(arena);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut arena = Arena::new();
+        let list = arena.new_list();
+        let a = arena.push_back(list, 'a');
+        arena.push_back(list, 'b');
+        let removed = arena.push_back(list, 'x');
+        arena.remove(removed);
+        arena.unlink(a);
+        let dot = arena.to_dot();
+        assert!(dot.contains("n0 [label=\"'a'\\nslot 0 gen 0\", style=dotted];"), "{}", dot);
+        assert!(dot.contains("n1 [label=\"'b'\\nslot 1 gen 0\"];"), "{}", dot);
+        // freed slots don't show up at all
+        assert!(!dot.contains("n2"), "{}", dot);
+        assert!(dot.contains("list0 [label=\"list 0 (len 1)\", shape=plaintext];"), "{}", dot);
+        assert!(dot.contains("list0 -> n1 [label=\"head\"];"), "{}", dot);
+
+        let list: ArenaList<i32> = (1..=2).collect();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 -> n1 [label=\"next\"];") && dot.contains("n1 -> n0 [label=\"prev\", style=dashed];"), "{}", dot);
+    }
+}
@@ -1,4 +1,7 @@
-use std::{rc::Rc, cell::{RefCell, Ref, RefMut}};
+use std::{rc::Rc, cell::{RefCell, Ref, RefMut}, fmt::{self, Debug, Display, Formatter}, str::FromStr};
+
+use super::{collection::Deque, text::{self, ParseListError}};
+use crate::common::dot;
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
@@ -43,6 +46,26 @@ impl<T> List<T> {
         }
     }
 
+    // mirror image of push_front
+    pub fn push_back(&mut self, elem: T) {
+        let new_tail = Node::new(elem);
+        match self.tail.take() {
+            Some(old_tail) => {
+                old_tail.borrow_mut().next = Some(new_tail.clone());
+                new_tail.borrow_mut().prev = Some(old_tail);
+                self.tail = Some(new_tail)
+            },
+            None => {
+                self.head = Some(new_tail.clone());
+                self.tail = Some(new_tail)
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
             match old_head.borrow_mut().next.take() {
@@ -115,8 +138,11 @@ impl<T> Drop for List<T> {
 // INTO ITER
 pub struct IntoIter<T>(List<T>);
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -135,6 +161,93 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 
+impl<T> Deque<T> for List<T> {
+    type ElemRef<'a> = Ref<'a, T> where T: 'a;
+
+    fn push_front(&mut self, elem: T) {
+        List::push_front(self, elem)
+    }
+
+    fn push_back(&mut self, elem: T) {
+        List::push_back(self, elem)
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        List::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        List::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<Ref<'_, T>> {
+        List::peek_front(self)
+    }
+
+    fn peek_back(&self) -> Option<Ref<'_, T>> {
+        List::peek_back(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        List::is_empty(self)
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List::new();
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            list.push_back(node.borrow().elem.clone());
+            cur = node.borrow().next.clone();
+        }
+        list
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut first = self.head.clone();
+        let mut second = other.head.clone();
+        loop {
+            match (first, second) {
+                (None, None) => return true,
+                (Some(first_node), Some(second_node)) => {
+                    if first_node.borrow().elem != second_node.borrow().elem {
+                        return false;
+                    }
+                    first = first_node.borrow().next.clone();
+                    second = second_node.borrow().next.clone();
+                },
+                _ => return false
+            }
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+// elements are appended at the back, so the list keeps the iteration order
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
 // ITER
 pub struct Iter<'a, T>(Option<Ref<'a, Node<T>>>);
 
@@ -161,9 +274,37 @@ impl<T> List<T> {
 //     // }
 // }
 
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_list(f, text::rc_nodes(&self.head, |node| &node.next), |f, node| write!(f, "{}", node.borrow().elem))
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_list(f, text::rc_nodes(&self.head, |node| &node.next), |f, node| write!(f, "{:?}", node.borrow().elem))
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T: Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        dot::doubly_linked("deque", &self.head, &self.tail, |node| &node.elem, |node| &node.next, |node| &node.prev)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
+    use crate::common::drop_counter::DropCounter;
+    use std::rc::Rc;
 
     #[test]
     fn basics() {
@@ -216,6 +357,50 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
-}
+    #[test]
+    fn drops_every_element_once() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+        for elem in 0..6 {
+            list.push_back(counter.track(elem));
+        }
+        drop(list.pop_front());
+        drop(list.pop_back());
+        assert_eq!(counter.dropped_ids(), vec![0, 5]);
+
+        // prev links make a cycle between every pair of neighbours, Drop has to break all of them
+        let head = Rc::downgrade(list.head.as_ref().unwrap());
+        let tail = Rc::downgrade(list.tail.as_ref().unwrap());
+        drop(list);
+        assert!(head.upgrade().is_none());
+        assert!(tail.upgrade().is_none());
+        // dropped from the front
+        assert_eq!(counter.dropped_ids(), vec![0, 5, 1, 2, 3, 4]);
+        counter.assert_all_dropped_once();
+    }
 
+    #[test]
+    fn drops_rest_of_partially_consumed_iterator() {
+        let counter = DropCounter::new();
+        let list: List<_> = (0..8).map(|elem| counter.track(elem)).collect();
+        let mut iter = list.into_iter();
+        let front = iter.next().unwrap();
+        let back = iter.next_back().unwrap();
+        drop(iter);
+        assert_eq!(counter.alive(), 2);
+        assert_eq!((*front, *back), (0, 7));
+        drop((front, back));
+        counter.assert_all_dropped_once();
+    }
 
+    #[test]
+    fn to_dot() {
+        let list: List<i32> = vec![1, 2, 3].into_iter().collect();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"1\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n1 -> n2 [label=\"next\"];"), "{}", dot);
+        assert!(dot.contains("n2 -> n1 [label=\"prev\", style=dashed];"), "{}", dot);
+        assert!(dot.contains("head -> n0;") && dot.contains("tail -> n2;"), "{}", dot);
+        assert!(!List::<i32>::new().to_dot().contains("head"));
+    }
+}
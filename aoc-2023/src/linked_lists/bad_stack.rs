@@ -1,4 +1,7 @@
-use std::mem;
+use std::{fmt::{self, Debug, Display, Formatter}, mem, str::FromStr};
+
+use super::{collection::Stack, text::{self, ParseListError}};
+use crate::common::dot;
 
 // problem of this implemenatation of the linked list is that the first element of the 
 // list is allocated on the stack while the rest is on the heap - it is not allocate uniformly
@@ -12,6 +15,21 @@ pub enum ListHeadOnStack<T> {
     Conns(T, Box<ListHeadOnStack<T>>)
 }
 
+// the derived drop would recurse through every box, so long lists are unlinked in a loop.
+// Nodes can't be moved out of the enum (it implements Drop now), so the rest of the list is
+// swapped for Nil instead and every node gets dropped with nothing behind it
+impl<T> Drop for ListHeadOnStack<T> {
+    fn drop(&mut self) {
+        let mut cur = match self {
+            ListHeadOnStack::Nil => return,
+            ListHeadOnStack::Conns(_, next) => mem::replace(&mut **next, ListHeadOnStack::Nil)
+        };
+        while let ListHeadOnStack::Conns(_, next) = &mut cur {
+            cur = mem::replace(&mut **next, ListHeadOnStack::Nil);
+        }
+    }
+}
+
 pub fn example() {
     let list = ListHeadOnStack::Conns(1, Box::new(ListHeadOnStack::Conns(2, Box::new(ListHeadOnStack::Nil))));
     println!("{:?}", list);
@@ -30,6 +48,19 @@ pub enum PubNodeList<T> {
     More(Box<PubNode<T>>)
 }
 
+// same story as with ListHeadOnStack
+impl<T> Drop for PubNodeList<T> {
+    fn drop(&mut self) {
+        let mut cur = match self {
+            PubNodeList::Empty => return,
+            PubNodeList::More(boxed_node) => mem::replace(&mut boxed_node.next, PubNodeList::Empty)
+        };
+        while let PubNodeList::More(boxed_node) = &mut cur {
+            cur = mem::replace(&mut boxed_node.next, PubNodeList::Empty);
+        }
+    }
+}
+
 // this takes care of the visibility problem
 // we have
 // * enum that "represent a ponter" - it is eiher empty (null) or it contains a poinet to the next element
@@ -97,6 +128,126 @@ impl<T> List<T> {
     }
 }
 
+impl<T> List<T> {
+    pub fn peek(&self) -> Option<&T> {
+        match &self.head {
+            Link::Empty => None,
+            Link::More(boxed_node) => Some(&boxed_node.elem)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.head, Link::Empty)
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        List::push(self, elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        List::peek(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        List::is_empty(self)
+    }
+}
+
+impl<T> List<T> {
+    // references to the elements from the top, the list has no iterator of its own
+    fn elem_refs(&self) -> Vec<&T> {
+        let mut elems = Vec::new();
+        let mut cur_link = &self.head;
+        while let Link::More(boxed_node) = cur_link {
+            elems.push(&boxed_node.elem);
+            cur_link = &boxed_node.next;
+        }
+        elems
+    }
+}
+
+// both written as loops, derived impls would recurse once per node
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List::new();
+        for elem in self.elem_refs().into_iter().rev() {
+            list.push(elem.clone());
+        }
+        list
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut first = &self.head;
+        let mut second = &other.head;
+        loop {
+            match (first, second) {
+                (Link::Empty, Link::Empty) => return true,
+                (Link::More(first_node), Link::More(second_node)) => {
+                    if first_node.elem != second_node.elem {
+                        return false;
+                    }
+                    first = &first_node.next;
+                    second = &second_node.next;
+                },
+                _ => return false
+            }
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+// first element ends up on top, so collecting the list's own into_iter gives back the same list
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        let mut list = List::new();
+        list.extend(elems.into_iter().rev());
+        list
+    }
+}
+
+// extending a stack pushes the elements one by one, so the last one ends up on top
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+// just pops until the list is empty
+pub struct IntoIter<T>(List<T>);
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
         let mut cur_link = mem::replace(&mut self.head, Link::Empty);
@@ -110,6 +261,41 @@ impl<T> Drop for List<T> {
     }
 }
 
+// top of the stack first, parsing keeps the order like collect does
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.elem_refs())
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.elem_refs()).finish()
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T: Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        // Link is what Option would be, so it takes a match to get at the boxes
+        let head = match &self.head {
+            Link::Empty => None,
+            Link::More(node) => Some(node)
+        };
+        dot::singly_linked("bad_stack", head, |node| &node.elem, |node| match &node.next {
+            Link::Empty => None,
+            Link::More(next) => Some(next)
+        }, |_, elem| elem)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::List;
@@ -141,4 +327,20 @@ mod test {
         assert_eq!(list.pop(), Some(1));
         assert_eq!(list.pop(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn to_dot() {
+        let list: List<i32> = vec![1, 2].into_iter().collect();
+        assert_eq!(list.to_dot(), concat!(
+            "digraph bad_stack {\n",
+            "    rankdir=LR;\n",
+            "    node [shape=box];\n",
+            "    head [label=\"head\", shape=plaintext];\n",
+            "    head -> n0;\n",
+            "    n0 [label=\"1\"];\n",
+            "    n0 -> n1 [label=\"next\"];\n",
+            "    n1 [label=\"2\"];\n",
+            "}\n"
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+use std::ops::Deref;
+
+// Common interface of the lists in this module, so code (and tests) can be written once
+// against a trait instead of against every list with its own method names.
+
+// LIFO, the most recently pushed element is the one that gets popped
+pub trait Stack<T> {
+    fn push(&mut self, elem: T);
+    fn pop(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn is_empty(&self) -> bool;
+}
+
+// Lists built from Rc<RefCell<Node>> can only lend their elements out through a Ref guard,
+// so the type of the peeked reference is up to the implementation.
+pub trait Deque<T> {
+    type ElemRef<'a>: Deref<Target = T> where Self: 'a;
+
+    fn push_front(&mut self, elem: T);
+    fn push_back(&mut self, elem: T);
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+    fn peek_front(&self) -> Option<Self::ElemRef<'_>>;
+    fn peek_back(&self) -> Option<Self::ElemRef<'_>>;
+    fn is_empty(&self) -> bool;
+}
+
+// elements come out smallest first, whatever "smallest" means for the collection
+pub trait SortedCollection<T> {
+    type ElemRef<'a>: Deref<Target = T> where Self: 'a;
+
+    fn insert(&mut self, elem: T);
+    fn pop_first(&mut self) -> Option<T>;
+    fn first(&self) -> Option<Self::ElemRef<'_>>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// One conformance suite for every implementation. Each collection additionally has to support
+// the std conversion traits, so it can be built with collect() and consumed with a for loop.
+#[cfg(test)]
+mod test {
+    use super::{Stack, Deque, SortedCollection};
+    use crate::linked_lists::{
+        bad_stack, ok_singly_linked_stack, persistent_singly_linked_stack,
+        bad_but_safe_doubly_linked_deq, sorted_doubly_linked_list, arena, unrolled
+    };
+
+    fn stack_conformance<S>()
+    where S: Stack<i32> + Default + FromIterator<i32> + Extend<i32> + IntoIterator<Item = i32> {
+        let mut stack = S::default();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.peek(), None);
+
+        stack.push(1);
+        stack.push(2);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.peek(), Some(&2));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(3);
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+
+        // extending pushes in iteration order, so the last element ends up on top
+        stack.extend(vec![4, 5, 6]);
+        assert_eq!(stack.peek(), Some(&6));
+        assert_eq!(stack.into_iter().collect::<Vec<_>>(), vec![6, 5, 4]);
+
+        // collecting keeps the order, so the first element ends up on top
+        let collected: S = (1..=3).collect();
+        assert_eq!(collected.peek(), Some(&1));
+        let round_trip: S = collected.into_iter().collect();
+        assert_eq!(round_trip.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let mut long: S = (0..10_000).collect();
+        for expected in 0..10_000 {
+            assert_eq!(long.pop(), Some(expected));
+        }
+        assert!(long.is_empty());
+    }
+
+    fn deque_conformance<D>()
+    where D: Deque<i32> + Default + FromIterator<i32> + Extend<i32> + IntoIterator<Item = i32> {
+        let mut deque = D::default();
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.peek_front().is_none());
+        assert!(deque.peek_back().is_none());
+
+        deque.push_front(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        assert_eq!(deque.peek_front().as_deref(), Some(&1));
+        assert_eq!(deque.peek_back().as_deref(), Some(&3));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert!(deque.is_empty());
+
+        // single element has to be reachable from both ends
+        deque.push_back(7);
+        assert_eq!(deque.peek_front().as_deref(), Some(&7));
+        assert_eq!(deque.peek_back().as_deref(), Some(&7));
+        assert_eq!(deque.pop_front(), Some(7));
+        assert_eq!(deque.pop_back(), None);
+
+        // extending and collecting append at the back, iteration goes front to back
+        deque.extend(vec![4, 5]);
+        deque.push_front(3);
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        let collected: D = (1..=3).collect();
+        assert_eq!(collected.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    fn sorted_conformance<C>()
+    where C: SortedCollection<i32> + Default + FromIterator<i32> + Extend<i32> + IntoIterator<Item = i32> {
+        let mut sorted = C::default();
+        assert!(sorted.is_empty());
+        assert_eq!(sorted.len(), 0);
+        assert_eq!(sorted.pop_first(), None);
+        assert!(sorted.first().is_none());
+
+        for elem in [5, 1, 4, 1, 3] {
+            sorted.insert(elem);
+        }
+        assert_eq!(sorted.len(), 5);
+        assert_eq!(sorted.first().as_deref(), Some(&1));
+        assert_eq!(sorted.pop_first(), Some(1));
+        assert_eq!(sorted.pop_first(), Some(1));
+        assert_eq!(sorted.len(), 3);
+
+        sorted.extend(vec![2, 9, 0]);
+        assert_eq!(sorted.into_iter().collect::<Vec<_>>(), vec![0, 2, 3, 4, 5, 9]);
+
+        let collected: C = vec![3, -1, 2].into_iter().collect();
+        assert_eq!(collected.into_iter().collect::<Vec<_>>(), vec![-1, 2, 3]);
+    }
+
+    #[test]
+    fn bad_stack() {
+        stack_conformance::<bad_stack::List<i32>>();
+    }
+
+    #[test]
+    fn ok_singly_linked_stack() {
+        stack_conformance::<ok_singly_linked_stack::List<i32>>();
+    }
+
+    #[test]
+    fn persistent_singly_linked_stack() {
+        stack_conformance::<persistent_singly_linked_stack::List<i32>>();
+        stack_conformance::<persistent_singly_linked_stack::ArcList<i32>>();
+    }
+
+    #[test]
+    fn bad_but_safe_doubly_linked_deq() {
+        deque_conformance::<bad_but_safe_doubly_linked_deq::List<i32>>();
+    }
+
+    #[test]
+    fn arena_list() {
+        deque_conformance::<arena::ArenaList<i32>>();
+    }
+
+    #[test]
+    fn unrolled_list() {
+        deque_conformance::<unrolled::UnrolledList<i32>>();
+        deque_conformance::<unrolled::UnrolledList<i32, 2>>();
+    }
+
+    #[test]
+    fn sorted_doubly_linked_list() {
+        sorted_conformance::<sorted_doubly_linked_list::List<i32>>();
+    }
+}
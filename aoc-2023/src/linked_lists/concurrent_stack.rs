@@ -0,0 +1,406 @@
+use std::{
+    cell::UnsafeCell,
+    mem::ManuallyDrop,
+    ptr,
+    fmt::Debug,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering}
+};
+
+use crate::common::dot::Dot;
+
+// Treiber stack: a singly linked stack shared between threads without a lock.
+// push and pop both prepare the new head and then swing the head pointer over with
+// compare_exchange, if some other thread got there first they just try again.
+//
+// The hard part is freeing popped nodes. A thread that lost the race may still be reading
+// the old head node (it needs its next pointer), so a popped node can't be freed right away.
+// This uses hazard pointers: before touching a node, a thread publishes its address in a
+// hazard record, and popped nodes are only retired - they get freed later, by whoever pops,
+// once no record points to them. A retired node can't be freed and its address reused while
+// someone is looking at it, which also rules out the ABA problem on the head pointer.
+//
+// Nothing else in the crate needs unsafe code, everything unsafe lives in this file.
+
+struct Node<T> {
+    // moved out by the pop that unlinks the node, freeing the node must not drop it again
+    elem: ManuallyDrop<T>,
+    next: *mut Node<T>
+}
+
+// One per popping thread at a time. Records are never freed while the stack lives, a thread
+// that is done with one just marks it inactive and the next pop (on any thread) can claim it.
+struct HazardRecord<T> {
+    hazard: AtomicPtr<Node<T>>,
+    active: AtomicBool,
+    // set before the record is published, never changes afterwards
+    next: *mut HazardRecord<T>,
+    // only touched by the thread that holds the record active
+    retired: UnsafeCell<Vec<*mut Node<T>>>
+}
+
+// Unlike the other lists this one has no Display, Debug or FromStr. Formatting would have to
+// walk the nodes through &self while other threads pop and free them, which needs a hazard
+// per node on the way, and a FromStr that can't be printed back wouldn't round-trip.
+// to_dot takes &mut self for the same reason
+pub struct TreiberStack<T> {
+    head: AtomicPtr<Node<T>>,
+    records: AtomicPtr<HazardRecord<T>>,
+    record_count: AtomicUsize
+}
+
+// the elements move between threads, the raw pointers are managed by the stack itself
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+// a popping thread that retired this many nodes per record scans the hazards and frees
+// what it can, so each record holds on to at most a handful of nodes
+const SCAN_FACTOR: usize = 2;
+const MIN_SCAN: usize = 16;
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        TreiberStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            records: AtomicPtr::new(ptr::null_mut()),
+            record_count: AtomicUsize::new(0)
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node { elem: ManuallyDrop::new(elem), next: ptr::null_mut() }));
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // the node isn't shared until the exchange succeeds, writing to it is fine
+            unsafe { (*node).next = head };
+            match self.head.compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = self.acquire_record();
+        let record = guard.record;
+        let node = loop {
+            let head = self.head.load(Ordering::Acquire);
+            if head.is_null() {
+                return None;
+            }
+            // publish the hazard, then check head didn't change in between - if it is still
+            // the head now, nobody could have retired it before seeing our hazard
+            record.hazard.store(head, Ordering::SeqCst);
+            if self.head.load(Ordering::SeqCst) != head {
+                continue;
+            }
+            let next = unsafe { (*head).next };
+            // SeqCst and not just AcqRel: the handshake with scan is "we store the hazard, then
+            // read head" against "it swings head, then reads the hazards". Only if all four are
+            // SeqCst are they in one total order, so at least one side sees the other - either
+            // we see head moved and retry, or scan sees our hazard. An AcqRel exchange is left
+            // out of that order and scan could miss the hazard and free a node we still read
+            if self.head.compare_exchange(head, next, Ordering::SeqCst, Ordering::Acquire).is_ok() {
+                break head;
+            }
+        };
+        record.hazard.store(ptr::null_mut(), Ordering::Release);
+        // only the thread whose exchange succeeded gets here with this node
+        let elem = unsafe { ManuallyDrop::take(&mut (*node).elem) };
+        self.retire(record, node);
+        Some(elem)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    // claims an inactive record or adds a new one to the list
+    fn acquire_record(&self) -> RecordGuard<'_, T> {
+        let mut cur = self.records.load(Ordering::Acquire);
+        while !cur.is_null() {
+            let record = unsafe { &*cur };
+            if record.active.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                return RecordGuard { record };
+            }
+            cur = record.next;
+        }
+        let record = Box::into_raw(Box::new(HazardRecord {
+            hazard: AtomicPtr::new(ptr::null_mut()),
+            active: AtomicBool::new(true),
+            next: ptr::null_mut(),
+            retired: UnsafeCell::new(Vec::new())
+        }));
+        let mut head = self.records.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*record).next = head };
+            match self.records.compare_exchange_weak(head, record, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => head = current
+            }
+        }
+        self.record_count.fetch_add(1, Ordering::Relaxed);
+        RecordGuard { record: unsafe { &*record } }
+    }
+
+    fn retire(&self, record: &HazardRecord<T>, node: *mut Node<T>) {
+        // the record is active and ours, nobody else touches its retired list
+        let retired = unsafe { &mut *record.retired.get() };
+        retired.push(node);
+        let threshold = (self.record_count.load(Ordering::Relaxed) * SCAN_FACTOR).max(MIN_SCAN);
+        if retired.len() >= threshold {
+            self.scan(retired);
+        }
+    }
+
+    // frees every retired node no record has a hazard on. The nodes were unlinked by this
+    // thread's SeqCst exchange in pop, which is what makes the SeqCst hazard loads below safe
+    fn scan(&self, retired: &mut Vec<*mut Node<T>>) {
+        let mut hazards = Vec::new();
+        let mut cur = self.records.load(Ordering::Acquire);
+        while !cur.is_null() {
+            let record = unsafe { &*cur };
+            let hazard = record.hazard.load(Ordering::SeqCst);
+            if !hazard.is_null() {
+                hazards.push(hazard);
+            }
+            cur = record.next;
+        }
+        retired.retain(|node| {
+            if hazards.contains(node) {
+                return true;
+            }
+            // the elem was moved out already and is ManuallyDrop, this only frees the node
+            drop(unsafe { Box::from_raw(*node) });
+            false
+        });
+    }
+
+    #[cfg(test)]
+    fn retired_count(&self) -> usize {
+        let mut count = 0;
+        let mut cur = self.records.load(Ordering::Acquire);
+        while !cur.is_null() {
+            let record = unsafe { &*cur };
+            count += unsafe { (*record.retired.get()).len() };
+            cur = record.next;
+        }
+        count
+    }
+}
+
+impl<T: Debug> TreiberStack<T> {
+    // takes &mut self so no other thread can pop and free a node while it is being looked at
+    pub fn to_dot(&mut self) -> String {
+        let mut dot = Dot::new("treiber_stack");
+        dot.marker("head", "head");
+        let mut cur = *self.head.get_mut();
+        if !cur.is_null() {
+            let first_id = dot.id_of(cur);
+            dot.edge("head", &first_id, "");
+        }
+        while !cur.is_null() {
+            let node = unsafe { &*cur };
+            let id = dot.id_of(cur);
+            dot.node(&id, &format!("{:?}", *node.elem));
+            if !node.next.is_null() {
+                let next_id = dot.id_of(node.next);
+                dot.edge(&id, &next_id, "next");
+            }
+            cur = node.next;
+        }
+        dot.finish()
+    }
+}
+
+struct RecordGuard<'a, T> {
+    record: &'a HazardRecord<T>
+}
+
+// releases the record even if the pop panics halfway
+impl<T> Drop for RecordGuard<'_, T> {
+    fn drop(&mut self) {
+        self.record.hazard.store(ptr::null_mut(), Ordering::Release);
+        self.record.active.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        TreiberStack::new()
+    }
+}
+
+// with &mut self no other thread can be in the middle of a push or pop, everything can go
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+            cur = node.next;
+        }
+        let mut cur = *self.records.get_mut();
+        while !cur.is_null() {
+            let record = unsafe { Box::from_raw(cur) };
+            for node in record.retired.into_inner() {
+                drop(unsafe { Box::from_raw(node) });
+            }
+            cur = record.next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TreiberStack;
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    use std::thread;
+
+    #[test]
+    fn basics() {
+        let stack = TreiberStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        stack.push(1);
+        stack.push(2);
+        assert!(!stack.is_empty());
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(3);
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn retired_nodes_get_freed() {
+        let stack = TreiberStack::new();
+        for round in 0..100 {
+            for elem in 0..100 {
+                stack.push(round * 100 + elem);
+            }
+            while stack.pop().is_some() {}
+            // a single thread never holds a hazard while scanning, so nothing piles up
+            assert!(stack.retired_count() < 16, "{} nodes still retired", stack.retired_count());
+        }
+    }
+
+    // Counts drops through an Arc, the DropCounter of the other lists is single threaded
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn drops_everything_once() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let stack = TreiberStack::new();
+        for _ in 0..100 {
+            stack.push(Counted(dropped.clone()));
+        }
+        for _ in 0..40 {
+            drop(stack.pop());
+        }
+        assert_eq!(dropped.load(Ordering::Relaxed), 40);
+        drop(stack);
+        assert_eq!(dropped.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn deep_stack_drop() {
+        let stack = TreiberStack::new();
+        for elem in 0..1_000_000 {
+            stack.push(elem);
+        }
+        drop(stack);
+    }
+
+    // every thread pushes its own range of values and pops whatever it finds,
+    // in the end every value has to come out exactly once
+    #[test]
+    fn stress_every_element_popped_once() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 20_000;
+
+        let stack = Arc::new(TreiberStack::new());
+        let handles: Vec<_> = (0..THREADS).map(|thread| {
+            let stack = stack.clone();
+            thread::spawn(move || {
+                let mut popped = Vec::new();
+                for i in 0..PER_THREAD {
+                    stack.push(thread * PER_THREAD + i);
+                    // pop a bit less than is pushed, so the stack keeps changing size
+                    if i % 3 != 0 {
+                        popped.extend(stack.pop());
+                    }
+                }
+                popped
+            })
+        }).collect();
+
+        let mut popped: Vec<usize> = handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+        while let Some(elem) = stack.pop() {
+            popped.push(elem);
+        }
+        popped.sort_unstable();
+        assert_eq!(popped, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+
+    // separate pushers and poppers, the poppers keep going until all pushers are done
+    #[test]
+    fn stress_pushers_and_poppers() {
+        const PUSHERS: usize = 4;
+        const POPPERS: usize = 4;
+        const PER_PUSHER: usize = 25_000;
+
+        let stack = Arc::new(TreiberStack::new());
+        let pushed = Arc::new(AtomicUsize::new(0));
+        let poppers: Vec<_> = (0..POPPERS).map(|_| {
+            let (stack, pushed) = (stack.clone(), pushed.clone());
+            thread::spawn(move || {
+                let mut popped = Vec::new();
+                loop {
+                    match stack.pop() {
+                        Some(elem) => popped.push(elem),
+                        None if pushed.load(Ordering::Acquire) == PUSHERS => break,
+                        None => thread::yield_now()
+                    }
+                }
+                popped
+            })
+        }).collect();
+        let pushers: Vec<_> = (0..PUSHERS).map(|pusher| {
+            let (stack, pushed) = (stack.clone(), pushed.clone());
+            thread::spawn(move || {
+                for i in 0..PER_PUSHER {
+                    stack.push(pusher * PER_PUSHER + i);
+                }
+                pushed.fetch_add(1, Ordering::Release);
+            })
+        }).collect();
+
+        for pusher in pushers {
+            pusher.join().unwrap();
+        }
+        let mut popped: Vec<usize> = poppers.into_iter().flat_map(|popper| popper.join().unwrap()).collect();
+        popped.extend(std::iter::from_fn(|| stack.pop()));
+        popped.sort_unstable();
+        assert_eq!(popped, (0..PUSHERS * PER_PUSHER).collect::<Vec<_>>());
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut stack = TreiberStack::new();
+        for elem in 1..=3 {
+            stack.push(elem);
+        }
+        stack.pop();
+        let dot = stack.to_dot();
+        assert!(dot.contains("head -> n0;"), "{}", dot);
+        assert!(dot.contains("n0 [label=\"2\"];") && dot.contains("n1 [label=\"1\"];"), "{}", dot);
+        assert!(dot.contains("n0 -> n1 [label=\"next\"];") && !dot.contains("n2"), "{}", dot);
+    }
+}
@@ -0,0 +1,114 @@
+// Every list here is a chain of nodes, and anything that walks the chain recursively
+// (a derived Drop, Clone or PartialEq) needs one stack frame per node. These tests build lists
+// long enough that a recursive walk would overflow the stack of a test thread and then drop,
+// clone and compare them.
+
+use super::{
+    bad_stack, ok_singly_linked_stack, persistent_singly_linked_stack, bad_but_safe_doubly_linked_deq,
+    sorted_doubly_linked_list, arena, unrolled
+};
+
+const N: usize = 2_000_000;
+
+#[test]
+fn list_head_on_stack_drop() {
+    let mut list = bad_stack::ListHeadOnStack::Nil;
+    for elem in 0..N {
+        list = bad_stack::ListHeadOnStack::Conns(elem, Box::new(list));
+    }
+    drop(list);
+}
+
+#[test]
+fn bad_stack_drop_clone_eq() {
+    let list: bad_stack::List<usize> = (0..N).collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    assert_eq!(clone.pop(), Some(0));
+    assert!(list != clone);
+    clone.push(1);
+    assert!(list != clone);
+    drop(list);
+    drop(clone);
+}
+
+#[test]
+fn ok_singly_linked_stack_drop_clone_eq() {
+    let list: ok_singly_linked_stack::List<usize> = (0..N).collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    *clone.peek_mut().unwrap() = N;
+    assert!(list != clone);
+    drop(list);
+    drop(clone);
+}
+
+#[test]
+fn persistent_singly_linked_stack_drop_clone_eq() {
+    let list: persistent_singly_linked_stack::List<usize> = (0..N).collect();
+    // the clone shares every node, a rebuilt list shares none of them
+    let shared = list.clone();
+    let rebuilt: persistent_singly_linked_stack::List<usize> = list.iter().copied().collect();
+    assert_eq!(list, shared);
+    assert_eq!(list, rebuilt);
+    assert_ne!(list, rebuilt.tail());
+    assert_ne!(list, rebuilt.tail().prepend(1));
+    drop(list);
+    drop(shared);
+    drop(rebuilt);
+
+    let arc_list: persistent_singly_linked_stack::ArcList<usize> = (0..N).collect();
+    let arc_rebuilt: persistent_singly_linked_stack::ArcList<usize> = arc_list.iter().copied().collect();
+    assert_eq!(arc_list, arc_rebuilt);
+}
+
+#[test]
+fn bad_but_safe_doubly_linked_deq_drop_clone_eq() {
+    let list: bad_but_safe_doubly_linked_deq::List<usize> = (0..N).collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    assert_eq!(clone.pop_back(), Some(N - 1));
+    assert!(list != clone);
+    clone.push_back(0);
+    assert!(list != clone);
+    drop(list);
+    drop(clone);
+}
+
+#[test]
+fn sorted_doubly_linked_list_drop_clone_eq() {
+    let list: sorted_doubly_linked_list::List<usize> = (0..N).rev().collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    // the clone keeps sorting the same way as the original
+    clone.insert(N / 2);
+    assert_eq!(clone.len(), N + 1);
+    assert!(list != clone);
+    assert_eq!(clone.pop_front(), Some(0));
+    drop(list);
+    drop(clone);
+}
+
+#[test]
+fn arena_list_drop_clone_eq() {
+    let list: arena::ArenaList<usize> = (0..N).collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    let back = clone.push_back(N);
+    assert!(list != clone);
+    clone.remove(back);
+    assert!(list == clone);
+    drop(list);
+    drop(clone);
+}
+
+#[test]
+fn unrolled_list_drop_clone_eq() {
+    let list: unrolled::UnrolledList<usize> = (0..N).collect();
+    let mut clone = list.clone();
+    assert!(list == clone);
+    clone[N / 2] = 0;
+    assert!(list != clone);
+    drop(list);
+    drop(clone);
+}
@@ -2,4 +2,16 @@ pub mod bad_stack;
 pub mod ok_singly_linked_stack;
 pub mod persistent_singly_linked_stack;
 pub mod bad_but_safe_doubly_linked_deq;
-pub mod sorted_doubly_linked_list;
\ No newline at end of file
+pub mod sorted_doubly_linked_list;
+pub mod shared_pointer;
+pub mod persistent_trie;
+pub mod arena;
+pub mod skip_list;
+pub mod unrolled;
+pub mod concurrent_stack;
+pub mod collection;
+pub mod text;
+#[cfg(test)]
+mod model_tests;
+#[cfg(test)]
+mod deep_tests;
\ No newline at end of file
@@ -0,0 +1,279 @@
+// Model based randomized tests: random sequences of operations are applied both to a list
+// and to a std collection that models it (Vec for stacks, VecDeque for deques),
+// and everything either of them reports back has to be identical.
+// Failing sequences are shrunk by common::property, so the report shows a minimal one.
+
+use std::collections::VecDeque;
+
+use super::{
+    collection::{Stack, Deque},
+    bad_stack, ok_singly_linked_stack, persistent_singly_linked_stack, bad_but_safe_doubly_linked_deq, arena, unrolled
+};
+use crate::common::{property::{check, gen_vec, shrink_vec, shrink_i64}, random::Rng};
+
+const CASES: u64 = 300;
+const MAX_OPS: usize = 60;
+
+#[derive(Clone, Debug)]
+enum StackOp {
+    Push(i64),
+    Pop,
+    Peek,
+    // borrow the elements, top first
+    Iterate,
+    // consume the list by value, take a few elements and drop the iterator with the rest
+    IntoIterPartial(usize),
+    // drop the whole list while it still holds elements and start over with an empty one
+    DropList
+}
+
+fn gen_stack_op(rng: &mut Rng) -> StackOp {
+    match rng.gen_range(0..20) {
+        0..=7 => StackOp::Push(rng.gen_range(0..1_000) as i64 - 500),
+        8..=12 => StackOp::Pop,
+        13 | 14 => StackOp::Peek,
+        15 | 16 => StackOp::Iterate,
+        17 | 18 => StackOp::IntoIterPartial(rng.gen_range(0..5)),
+        _ => StackOp::DropList
+    }
+}
+
+fn shrink_stack_op(op: &StackOp) -> Vec<StackOp> {
+    match op {
+        StackOp::Push(elem) => shrink_i64(*elem).into_iter().map(StackOp::Push).collect(),
+        StackOp::IntoIterPartial(taken) if *taken > 0 => vec![StackOp::IntoIterPartial(taken - 1)],
+        _ => Vec::new()
+    }
+}
+
+// not every stack can lend out its elements, those just skip the Iterate operation
+fn run_stack_ops<S>(ops: &[StackOp], iterate: Option<fn(&S) -> Vec<i64>>) -> Result<(), String>
+where S: Stack<i64> + Default + IntoIterator<Item = i64> {
+    let mut list = S::default();
+    let mut model: Vec<i64> = Vec::new();
+    for (step, op) in ops.iter().enumerate() {
+        let (observed, expected) = match op {
+            StackOp::Push(elem) => {
+                list.push(*elem);
+                model.push(*elem);
+                (vec![], vec![])
+            },
+            StackOp::Pop => (list.pop().into_iter().collect(), model.pop().into_iter().collect()),
+            StackOp::Peek => (list.peek().copied().into_iter().collect(), model.last().copied().into_iter().collect()),
+            StackOp::Iterate => match iterate {
+                Some(iterate) => (iterate(&list), model.iter().rev().copied().collect()),
+                None => (vec![], vec![])
+            },
+            StackOp::IntoIterPartial(taken) => {
+                let observed = std::mem::take(&mut list).into_iter().take(*taken).collect();
+                let expected = model.iter().rev().take(*taken).copied().collect();
+                model.clear();
+                (observed, expected)
+            },
+            StackOp::DropList => {
+                list = S::default();
+                model.clear();
+                (vec![], vec![])
+            }
+        };
+        if observed != expected {
+            return Err(format!("step {} ({:?}): list gave {:?}, model gave {:?}", step, op, observed, expected));
+        }
+        if list.is_empty() != model.is_empty() {
+            return Err(format!("step {} ({:?}): list and model disagree on being empty", step, op));
+        }
+    }
+    // whatever is left has to come out in the same order as from the model
+    let rest: Vec<i64> = list.into_iter().collect();
+    let expected: Vec<i64> = model.into_iter().rev().collect();
+    if rest != expected {
+        return Err(format!("at the end: list held {:?}, model held {:?}", rest, expected));
+    }
+    Ok(())
+}
+
+fn check_stack<S>(seed: u64, iterate: Option<fn(&S) -> Vec<i64>>)
+where S: Stack<i64> + Default + IntoIterator<Item = i64> {
+    check(seed, CASES,
+        |rng| gen_vec(rng, MAX_OPS, gen_stack_op),
+        |ops| shrink_vec(ops, shrink_stack_op),
+        |ops| run_stack_ops::<S>(ops, iterate));
+}
+
+#[test]
+fn bad_stack_matches_vec() {
+    check_stack::<bad_stack::List<i64>>(100, None);
+}
+
+#[test]
+fn ok_singly_linked_stack_matches_vec() {
+    check_stack::<ok_singly_linked_stack::List<i64>>(200, Some(|list| list.iter().copied().collect()));
+}
+
+#[test]
+fn persistent_singly_linked_stack_matches_vec() {
+    check_stack::<persistent_singly_linked_stack::List<i64>>(300, Some(|list| list.iter().copied().collect()));
+}
+
+#[derive(Clone, Debug)]
+enum DequeOp {
+    PushFront(i64),
+    PushBack(i64),
+    PopFront,
+    PopBack,
+    PeekFront,
+    PeekBack,
+    // consume the list by value, alternately taking from the front and the back, then drop the rest
+    IntoIterPartial(usize),
+    DropList
+}
+
+fn gen_deque_op(rng: &mut Rng) -> DequeOp {
+    let elem = rng.gen_range(0..1_000) as i64 - 500;
+    match rng.gen_range(0..20) {
+        0..=4 => DequeOp::PushFront(elem),
+        5..=9 => DequeOp::PushBack(elem),
+        10..=12 => DequeOp::PopFront,
+        13..=15 => DequeOp::PopBack,
+        16 => DequeOp::PeekFront,
+        17 => DequeOp::PeekBack,
+        18 => DequeOp::IntoIterPartial(rng.gen_range(0..5)),
+        _ => DequeOp::DropList
+    }
+}
+
+fn shrink_deque_op(op: &DequeOp) -> Vec<DequeOp> {
+    match op {
+        DequeOp::PushFront(elem) => shrink_i64(*elem).into_iter().map(DequeOp::PushFront).collect(),
+        DequeOp::PushBack(elem) => shrink_i64(*elem).into_iter().map(DequeOp::PushBack).collect(),
+        DequeOp::IntoIterPartial(taken) if *taken > 0 => vec![DequeOp::IntoIterPartial(taken - 1)],
+        _ => Vec::new()
+    }
+}
+
+fn run_deque_ops<D>(ops: &[DequeOp]) -> Result<(), String>
+where D: Deque<i64> + Default + IntoIterator<Item = i64>, D::IntoIter: DoubleEndedIterator {
+    let mut list = D::default();
+    let mut model: VecDeque<i64> = VecDeque::new();
+    for (step, op) in ops.iter().enumerate() {
+        let (observed, expected): (Vec<i64>, Vec<i64>) = match op {
+            DequeOp::PushFront(elem) => {
+                list.push_front(*elem);
+                model.push_front(*elem);
+                (vec![], vec![])
+            },
+            DequeOp::PushBack(elem) => {
+                list.push_back(*elem);
+                model.push_back(*elem);
+                (vec![], vec![])
+            },
+            DequeOp::PopFront => (list.pop_front().into_iter().collect(), model.pop_front().into_iter().collect()),
+            DequeOp::PopBack => (list.pop_back().into_iter().collect(), model.pop_back().into_iter().collect()),
+            DequeOp::PeekFront => (list.peek_front().map(|elem| *elem).into_iter().collect(), model.front().copied().into_iter().collect()),
+            DequeOp::PeekBack => (list.peek_back().map(|elem| *elem).into_iter().collect(), model.back().copied().into_iter().collect()),
+            DequeOp::IntoIterPartial(taken) => {
+                let mut iter = std::mem::take(&mut list).into_iter();
+                let mut model_iter = std::mem::take(&mut model).into_iter();
+                let mut observed = Vec::new();
+                let mut expected = Vec::new();
+                for i in 0..*taken {
+                    if i % 2 == 0 {
+                        observed.extend(iter.next());
+                        expected.extend(model_iter.next());
+                    } else {
+                        observed.extend(iter.next_back());
+                        expected.extend(model_iter.next_back());
+                    }
+                }
+                (observed, expected)
+            },
+            DequeOp::DropList => {
+                list = D::default();
+                model.clear();
+                (vec![], vec![])
+            }
+        };
+        if observed != expected {
+            return Err(format!("step {} ({:?}): list gave {:?}, model gave {:?}", step, op, observed, expected));
+        }
+        if list.is_empty() != model.is_empty() {
+            return Err(format!("step {} ({:?}): list and model disagree on being empty", step, op));
+        }
+    }
+    let rest: Vec<i64> = list.into_iter().collect();
+    let expected: Vec<i64> = model.into_iter().collect();
+    if rest != expected {
+        return Err(format!("at the end: list held {:?}, model held {:?}", rest, expected));
+    }
+    Ok(())
+}
+
+fn check_deque<D>(seed: u64)
+where D: Deque<i64> + Default + IntoIterator<Item = i64>, D::IntoIter: DoubleEndedIterator {
+    check(seed, CASES,
+        |rng| gen_vec(rng, MAX_OPS, gen_deque_op),
+        |ops| shrink_vec(ops, shrink_deque_op),
+        |ops| run_deque_ops::<D>(ops));
+}
+
+#[test]
+fn bad_but_safe_doubly_linked_deq_matches_vec_deque() {
+    check_deque::<bad_but_safe_doubly_linked_deq::List<i64>>(400);
+}
+
+#[test]
+fn arena_list_matches_vec_deque() {
+    check_deque::<arena::ArenaList<i64>>(500);
+}
+
+#[test]
+fn unrolled_list_matches_vec_deque() {
+    check_deque::<unrolled::UnrolledList<i64, 4>>(600);
+}
+
+// The harness itself has to catch a broken list and shrink the failure to something readable.
+// This "stack" forgets every element pushed after its third one.
+#[derive(Default)]
+struct ForgetfulStack(Vec<i64>);
+
+impl Stack<i64> for ForgetfulStack {
+    fn push(&mut self, elem: i64) {
+        if self.0.len() < 3 {
+            self.0.push(elem);
+        }
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        self.0.pop()
+    }
+
+    fn peek(&self) -> Option<&i64> {
+        self.0.last()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl IntoIterator for ForgetfulStack {
+    type Item = i64;
+    type IntoIter = std::iter::Rev<std::vec::IntoIter<i64>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter().rev()
+    }
+}
+
+#[test]
+fn harness_shrinks_broken_stack() {
+    use crate::common::property::find_failure;
+
+    let failure = find_failure(0, CASES,
+        |rng| gen_vec(rng, MAX_OPS, gen_stack_op),
+        |ops| shrink_vec(ops, shrink_stack_op),
+        |ops| run_stack_ops::<ForgetfulStack>(ops, None)).expect("the broken stack has to be caught");
+    // four pushes are needed to lose an element, nothing else is needed to notice it
+    assert_eq!(failure.shrunk.len(), 4, "{:?}", failure.shrunk);
+    assert!(failure.shrunk.iter().all(|op| matches!(op, StackOp::Push(0))), "{:?}", failure.shrunk);
+}
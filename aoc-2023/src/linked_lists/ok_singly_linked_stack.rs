@@ -6,7 +6,10 @@
 // in nothing other than just Option<Box<Node>>
 // lets try to rewrite the list using Option
 
-use std::mem;
+use std::{fmt::{self, Debug, Display, Formatter}, mem, str::FromStr};
+
+use super::{collection::Stack, text::{self, ParseListError}};
+use crate::common::dot;
 
 pub struct List<T> {
     head: Link<T>
@@ -96,8 +99,11 @@ impl<T> Drop for List<T> {
 // IntoIter is jusy like calling pop over and over
 pub struct IntoIter<T>(List<T>); // tuple struct
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
 }
@@ -184,6 +190,112 @@ impl<'a, T> Iterator for IterMut<'a, T> {
     }
 }
 
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> List<T> {
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+}
+
+impl<T> Stack<T> for List<T> {
+    fn push(&mut self, elem: T) {
+        List::push(self, elem)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        List::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        List::peek(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        List::is_empty(self)
+    }
+}
+
+// both go through the iterators instead of deriving, derived impls would recurse once per node
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+// first element ends up on top, so collecting the list's own into_iter gives back the same list
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        let mut list = List::new();
+        list.extend(elems.into_iter().rev());
+        list
+    }
+}
+
+// extending a stack pushes the elements one by one, so the last one ends up on top
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+// top of the stack first, parsing keeps the order like collect does
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.iter())
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T: Debug> List<T> {
+    pub fn to_dot(&self) -> String {
+        dot::singly_linked("ok_stack", self.head.as_ref(), |node| &node.elem, |node| node.next.as_ref(), |_, elem| elem)
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -277,4 +389,13 @@ mod test {
         assert_eq!(iter.next(), Some(&mut 2));
         assert_eq!(iter.next(), Some(&mut 1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn to_dot() {
+        let list: List<&str> = vec!["a", "b", "c"].into_iter().collect();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"\\\"a\\\"\"];"), "{}", dot);
+        assert!(dot.contains("n1 -> n2 [label=\"next\"];"), "{}", dot);
+        assert!(!dot.contains("n3"), "{}", dot);
+    }
+}
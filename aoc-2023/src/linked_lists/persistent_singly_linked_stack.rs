@@ -1,32 +1,41 @@
-use std::rc::Rc;
+use std::{fmt::{self, Debug, Display, Formatter}, hash::{Hash, Hasher}, str::FromStr};
 
-pub struct List<T> {
-    head: Link<T>,
+use super::{shared_pointer::{SharedPointerKind, RcPointer, ArcPointer}, collection::Stack, text::{self, ParseListError}};
+use crate::common::dot;
+
+// The list is generic over the kind of shared pointer its nodes are linked with,
+// List uses Rc and ArcList uses Arc, so it can be shared between threads.
+pub struct PersistentList<T, P: SharedPointerKind> {
+    head: Link<T, P>,
 }
 
-type Link<T> = Option<Rc<Node<T>>>; // use Rc instead of box
+pub type List<T> = PersistentList<T, RcPointer>;
+
+pub type ArcList<T> = PersistentList<T, ArcPointer>;
+
+type Link<T, P> = Option<<P as SharedPointerKind>::Pointer<Node<T, P>>>; // use Rc (or Arc) instead of box
 
-struct Node<T> {
+struct Node<T, P: SharedPointerKind> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, P>,
 }
 
-impl<T> List<T> {
+impl<T, P: SharedPointerKind> PersistentList<T, P> {
     pub fn new() -> Self {
-        List { head: None }
+        PersistentList { head: None }
     }
 
-    pub fn prepend(&self, elem:T) -> List<T> {
-        List{
-            head: Some(Rc::new(Node{
+    pub fn prepend(&self, elem:T) -> PersistentList<T, P> {
+        PersistentList{
+            head: Some(P::new(Node{
                 elem: elem,
                 next: self.head.clone()
             }))
         }
     }
 
-    pub fn tail(&self) -> List<T> {
-        List { 
+    pub fn tail(&self) -> PersistentList<T, P> {
+        PersistentList { 
             head: self.head.as_ref().and_then(|node| node.next.clone()) }
     }
 
@@ -35,19 +44,19 @@ impl<T> List<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>
+pub struct Iter<'a, T, P: SharedPointerKind> {
+    next: Option<&'a Node<T, P>>
 }
 
-impl<T> List<T> {
-    pub fn iter<'a>(&'a self) -> Iter<'a, T> {
+impl<T, P: SharedPointerKind> PersistentList<T, P> {
+    pub fn iter<'a>(&'a self) -> Iter<'a, T, P> {
        Iter {
             next: self.head.as_deref()
        } 
     } 
 }
 
-impl<'a, T>  Iterator for Iter<'a, T> {
+impl<'a, T, P: SharedPointerKind> Iterator for Iter<'a, T, P> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -58,14 +67,245 @@ impl<'a, T>  Iterator for Iter<'a, T> {
     }
 }
 
-impl<T> Drop for List<T> {
+// Everything below shares as much of the original list as it can, nodes are only
+// copied when the elements in front of the shared part have to change.
+impl<T, P: SharedPointerKind> PersistentList<T, P> {
+    // O(n), the list does not store its length, otherwise prepend would have to touch every node
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.iter().nth(n)
+    }
+
+    // shares everything but the first n nodes, which are just skipped
+    pub fn drop_n(&self, n: usize) -> PersistentList<T, P> {
+        let mut link = &self.head;
+        for _ in 0..n {
+            match link {
+                Some(node) => link = &node.next,
+                None => break
+            }
+        }
+        PersistentList { head: link.clone() }
+    }
+
+    pub fn fold<A, F: FnMut(A, &T) -> A>(&self, init: A, f: F) -> A {
+        self.iter().fold(init, f)
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> PersistentList<U, P> {
+        PersistentList::from_prefix(self.iter().map(f).collect(), None)
+    }
+
+    // builds the list [prefix..., rest...] by prepending the prefix from its back
+    fn from_prefix(prefix: Vec<T>, rest: Link<T, P>) -> PersistentList<T, P> {
+        let mut list = PersistentList { head: rest };
+        for elem in prefix.into_iter().rev() {
+            list = list.prepend(elem);
+        }
+        list
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> PersistentList<T, P> {
+    // no sharing possible here, every node ends up in a different place
+    pub fn reverse(&self) -> PersistentList<T, P> {
+        self.iter().fold(PersistentList::new(), |reversed, elem| reversed.prepend(elem.clone()))
+    }
+
+    // self gets copied, other is shared as the tail of the result
+    pub fn append(&self, other: &PersistentList<T, P>) -> PersistentList<T, P> {
+        PersistentList::from_prefix(self.iter().cloned().collect(), other.head.clone())
+    }
+
+    // the part behind the last removed element is kept as it is, so it can be shared
+    pub fn filter<F: FnMut(&T) -> bool>(&self, mut predicate: F) -> PersistentList<T, P> {
+        let mut kept = Vec::new();
+        let mut kept_before_shared = 0;
+        let mut shared: &Link<T, P> = &self.head;
+        let mut link = &self.head;
+        while let Some(node) = link {
+            link = &node.next;
+            if predicate(&node.elem) {
+                kept.push(&node.elem);
+            } else {
+                kept_before_shared = kept.len();
+                shared = link;
+            }
+        }
+        kept.truncate(kept_before_shared);
+        PersistentList::from_prefix(kept.into_iter().cloned().collect(), shared.clone())
+    }
+}
+
+impl<T, P: SharedPointerKind> Clone for PersistentList<T, P> {
+    // cloning is cheap, the clone shares all the nodes
+    fn clone(&self) -> Self {
+        PersistentList { head: self.head.clone() }
+    }
+}
+
+impl<T, P: SharedPointerKind> Default for PersistentList<T, P> {
+    fn default() -> Self {
+        PersistentList::new()
+    }
+}
+
+// Stack on top of a persistent list just swaps the head, nodes that are shared
+// with other lists stay untouched. Popping has to clone the element unless the node
+// isn't shared with anyone.
+impl<T: Clone, P: SharedPointerKind> Stack<T> for PersistentList<T, P> {
+    fn push(&mut self, elem: T) {
+        *self = self.prepend(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_owned()
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.head()
+    }
+
+    fn is_empty(&self) -> bool {
+        PersistentList::is_empty(self)
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> PersistentList<T, P> {
+    fn pop_owned(&mut self) -> Option<T> {
+        let node = self.head.take()?;
+        match P::try_unwrap(node) {
+            Ok(mut node) => {
+                self.head = node.next.take();
+                Some(node.elem)
+            },
+            Err(node) => {
+                self.head = node.next.clone();
+                Some(node.elem.clone())
+            }
+        }
+    }
+}
+
+// pushes in iteration order like a stack, unlike collect(), which keeps the order
+impl<T: Clone, P: SharedPointerKind> Extend<T> for PersistentList<T, P> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+pub struct IntoIter<T, P: SharedPointerKind>(PersistentList<T, P>);
+
+impl<T: Clone, P: SharedPointerKind> IntoIterator for PersistentList<T, P> {
+    type Item = T;
+    type IntoIter = IntoIter<T, P>;
+
+    fn into_iter(self) -> IntoIter<T, P> {
+        IntoIter(self)
+    }
+}
+
+impl<T: Clone, P: SharedPointerKind> Iterator for IntoIter<T, P> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_owned()
+    }
+}
+
+impl<'a, T, P: SharedPointerKind> IntoIterator for &'a PersistentList<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Iter<'a, T, P> {
+        self.iter()
+    }
+}
+
+impl<T, P: SharedPointerKind> FromIterator<T> for PersistentList<T, P> {
+    // first element of the iterator ends up as the head
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        PersistentList::from_prefix(iter.into_iter().collect(), None)
+    }
+}
+
+impl<T: PartialEq, P: SharedPointerKind> PartialEq for PersistentList<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        // a loop and not a recursive comparison of the nodes, so long lists are fine
+        let mut first = &self.head;
+        let mut second = &other.head;
+        loop {
+            match (first, second) {
+                (None, None) => return true,
+                (Some(first_node), Some(second_node)) => {
+                    // shared tails are equal without looking at them
+                    if P::ptr_eq(first_node, second_node) {
+                        return true;
+                    }
+                    if first_node.elem != second_node.elem {
+                        return false;
+                    }
+                    first = &first_node.next;
+                    second = &second_node.next;
+                },
+                _ => return false
+            }
+        }
+    }
+}
+
+impl<T: Eq, P: SharedPointerKind> Eq for PersistentList<T, P> {}
+
+impl<T: Hash, P: SharedPointerKind> Hash for PersistentList<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // length first, the same way Vec does it, so [[1], []] and [[], [1]] don't collide
+        state.write_usize(self.len());
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T: Display, P: SharedPointerKind> Display for PersistentList<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.iter())
+    }
+}
+
+impl<T: Debug, P: SharedPointerKind> Debug for PersistentList<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: FromStr, P: SharedPointerKind> FromStr for PersistentList<T, P> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T, P: SharedPointerKind> Drop for PersistentList<T, P> {
     fn drop(&mut self) {
         let mut head = self.head.take();
         while let Some(node) = head {
-            // Returns the inner value, if the Rc has exactly one strong reference.
-            // Otherwise, an Err is returned with the same Rc that was passed in.
-            // This will succeed even if there are outstanding weak references.
-            if let Ok(mut node) = Rc::try_unwrap(node) { 
+            // into_inner hands the node back only if this was the last strong reference,
+            // otherwise some other list still shares it and we can stop here.
+            // With Arc the plain try_unwrap is racy, two threads dropping lists that share
+            // a tail could both see a count of 2, both give up and the last decrement would
+            // then drop the rest of the tail recursively. into_inner is the same check, but
+            // exactly one of the racing threads gets the node and continues the loop.
+            if let Some(mut node) = P::into_inner(node) {
                 head = node.next.take();
             } else {
                 break;
@@ -75,9 +315,18 @@ impl<T> Drop for List<T> {
 }
 
 
+impl<T: Debug, P: SharedPointerKind> PersistentList<T, P> {
+    // the labels carry the strong counts, so the nodes this list shares with other lists
+    // stand out (their count is above 1)
+    pub fn to_dot(&self) -> String {
+        dot::singly_linked("persistent_list", self.head.as_ref(), |node| &node.elem, |node| node.next.as_ref(), |node, elem| format!("{}\nstrong: {}", elem, P::strong_count(node)))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{List, ArcList};
+    use crate::linked_lists::shared_pointer::{SharedPointerKind, ArcPointer};
 
     #[test]
     fn basics() {
@@ -111,4 +360,170 @@ mod test {
         assert_eq!(iter.next(), Some(&2));
         assert_eq!(iter.next(), Some(&1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn len_nth_drop_n() {
+        let list: List<i32> = (1..=5).collect();
+        assert_eq!(list.len(), 5);
+        assert!(!list.is_empty());
+        assert_eq!(list.head(), Some(&1));
+        assert_eq!(list.nth(0), Some(&1));
+        assert_eq!(list.nth(4), Some(&5));
+        assert_eq!(list.nth(5), None);
+
+        let dropped = list.drop_n(3);
+        assert_eq!(dropped, vec![4, 5].into_iter().collect());
+        assert!(list.drop_n(10).is_empty());
+        assert_eq!(list.drop_n(0), list);
+    }
+
+    #[test]
+    fn reverse_append() {
+        let list: List<i32> = (1..=3).collect();
+        assert_eq!(list.reverse(), vec![3, 2, 1].into_iter().collect());
+
+        let other: List<i32> = (4..=5).collect();
+        let appended = list.append(&other);
+        assert_eq!(appended, (1..=5).collect());
+        // other was not copied, its nodes are the tail of the result
+        assert!(std::ptr::eq(appended.drop_n(3).head().unwrap(), other.head().unwrap()));
+
+        assert_eq!(List::new().append(&other), other);
+        assert_eq!(list.append(&List::new()), list);
+    }
+
+    #[test]
+    fn map_filter_fold() {
+        let list: List<i32> = (1..=6).collect();
+        assert_eq!(list.map(|elem| elem * 10), vec![10, 20, 30, 40, 50, 60].into_iter().collect());
+        assert_eq!(list.map(|elem| elem.to_string()).head(), Some(&"1".to_owned()));
+        assert_eq!(list.fold(0, |sum, elem| sum + elem), 21);
+
+        let odd = list.filter(|elem| elem % 2 == 1);
+        assert_eq!(odd, vec![1, 3, 5].into_iter().collect());
+
+        // nothing is removed behind 2, so [3, 4, 5, 6] is shared with the original list
+        let no_two = list.filter(|elem| *elem != 2);
+        assert_eq!(no_two, vec![1, 3, 4, 5, 6].into_iter().collect());
+        assert!(std::ptr::eq(no_two.nth(1).unwrap(), list.nth(2).unwrap()));
+        assert!(list.filter(|_| false).is_empty());
+    }
+
+    #[test]
+    fn eq_hash_debug() {
+        use std::collections::HashSet;
+
+        let list: List<i32> = (1..=3).collect();
+        let same = List::new().prepend(3).prepend(2).prepend(1);
+        assert_eq!(list, same);
+        assert_ne!(list, list.tail());
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", List::<i32>::new()), "[]");
+
+        let mut set = HashSet::new();
+        set.insert(list.clone());
+        assert!(set.contains(&same));
+        assert!(!set.contains(&list.tail()));
+    }
+
+    #[test]
+    fn backtracking_shares_paths() {
+        // all paths of length 3 over {0, 1}, every branch extends the path of its parent
+        fn extend(path: &List<u8>, depth: usize, paths: &mut Vec<List<u8>>) {
+            if depth == 3 {
+                paths.push(path.clone());
+                return;
+            }
+            for step in 0..2 {
+                extend(&path.prepend(step), depth + 1, paths);
+            }
+        }
+
+        let mut paths = Vec::new();
+        extend(&List::new(), 0, &mut paths);
+        assert_eq!(paths.len(), 8);
+        assert_eq!(paths[5].reverse(), vec![1, 0, 1].into_iter().collect());
+        // siblings share their parent's path
+        assert!(std::ptr::eq(paths[0].nth(1).unwrap(), paths[1].nth(1).unwrap()));
+    }
+
+    #[test]
+    fn arc_list_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ArcList<i32>>();
+        assert_send_sync::<ArcList<String>>();
+    }
+
+    #[test]
+    fn arc_list_basics() {
+        // same implementation as the Rc list, so a quick smoke test is enough
+        let list: ArcList<i32> = (1..=4).collect();
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.tail().head(), Some(&2));
+        assert_eq!(list.filter(|elem| elem % 2 == 0), vec![2, 4].into_iter().collect());
+        assert_eq!(format!("{:?}", list.reverse()), "[4, 3, 2, 1]");
+    }
+
+    #[test]
+    fn arc_list_shared_between_threads() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 10_000;
+
+        let base: ArcList<usize> = (0..PER_THREAD).collect();
+        let handles: Vec<_> = (0..THREADS).map(|thread| {
+            let base = base.clone();
+            std::thread::spawn(move || {
+                // every thread grows its own branch on top of the shared base
+                // and keeps dropping tails of it, which all race in the Drop of the shared nodes
+                let mut branch = base.clone();
+                for i in 0..PER_THREAD {
+                    branch = branch.prepend(thread * PER_THREAD + i);
+                    if i % 3 == 0 {
+                        drop(base.drop_n(i));
+                    }
+                }
+                let sum = branch.fold(0, |sum, elem| sum + elem);
+                let len = branch.len();
+                drop(branch);
+                (len, sum)
+            })
+        }).collect();
+
+        let base_sum: usize = (0..PER_THREAD).sum();
+        for (thread, handle) in handles.into_iter().enumerate() {
+            let (len, sum) = handle.join().unwrap();
+            let branch_sum: usize = (0..PER_THREAD).map(|i| thread * PER_THREAD + i).sum();
+            assert_eq!(len, 2 * PER_THREAD);
+            assert_eq!(sum, base_sum + branch_sum);
+        }
+        // all branches are gone, the base is not shared with anyone anymore
+        assert_eq!(ArcPointer::strong_count(base.head.as_ref().unwrap()), 1);
+        assert_eq!(base.len(), PER_THREAD);
+        assert_eq!(base.head(), Some(&0));
+    }
+
+    #[test]
+    fn arc_list_dropped_concurrently() {
+        // a long shared tail dropped by several threads at once must not be dropped recursively
+        let base: ArcList<usize> = (0..200_000).collect();
+        let branches: Vec<ArcList<usize>> = (0..4).map(|i| base.prepend(i)).collect();
+        drop(base);
+        let handles: Vec<_> = branches.into_iter().map(|branch| std::thread::spawn(move || drop(branch))).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn to_dot_shows_sharing() {
+        let tail: List<i32> = vec![2, 3].into_iter().collect();
+        let list = tail.prepend(1);
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"1\\nstrong: 1\"];"), "{}", dot);
+        // the first node of the tail is pointed to by both lists
+        assert!(dot.contains("n1 [label=\"2\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n2 [label=\"3\\nstrong: 1\"];"), "{}", dot);
+        let arc_list: ArcList<i32> = vec![1].into_iter().collect();
+        assert!(arc_list.to_dot().contains("strong: 1"));
+    }
+}
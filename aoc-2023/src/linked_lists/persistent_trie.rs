@@ -0,0 +1,890 @@
+// Persistent random access structures to go with the persistent cons list.
+// Both are tries with 32 children per node, so they are at most log32(n) levels deep and
+// every update copies just the path from the root to the changed leaf, everything else
+// is shared with the previous version through Rc.
+// The updates are written as copy-on-write with Rc::make_mut on a cheap clone of the whole
+// structure, so nodes nobody else points to are not even copied.
+
+use std::{rc::Rc, fmt::Debug, hash::{Hash, Hasher}, collections::hash_map::DefaultHasher, mem};
+
+use crate::common::dot::Dot;
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+// PERSISTENT VECTOR
+// Works like the Clojure vector: the elements live in leaves of the trie, except for the last
+// (up to) 32 of them, which are kept in a separate tail buffer, so pushing and popping at the
+// end only touches the trie once every 32 operations.
+
+#[derive(Clone)]
+enum VecNode<T> {
+    Branch(Vec<Rc<VecNode<T>>>),
+    Leaf(Vec<T>)
+}
+
+impl<T> VecNode<T> {
+    fn children_mut(&mut self) -> &mut Vec<Rc<VecNode<T>>> {
+        match self {
+            VecNode::Branch(children) => children,
+            VecNode::Leaf(_) => unreachable!("leaves live only at the bottom level of the trie")
+        }
+    }
+
+    fn child(&self, index: usize) -> &Rc<VecNode<T>> {
+        match self {
+            VecNode::Branch(children) => &children[index],
+            VecNode::Leaf(_) => unreachable!("leaves live only at the bottom level of the trie")
+        }
+    }
+
+    fn elems(&self) -> &[T] {
+        match self {
+            VecNode::Leaf(elems) => elems,
+            VecNode::Branch(_) => unreachable!("branches never hold elements")
+        }
+    }
+}
+
+pub struct PersistentVec<T> {
+    len: usize,
+    // how far the index has to be shifted to get the child index in the root
+    shift: usize,
+    root: Rc<VecNode<T>>,
+    tail: Rc<Vec<T>>
+}
+
+impl<T> PersistentVec<T> {
+    pub fn new() -> Self {
+        PersistentVec {
+            len: 0,
+            shift: BITS,
+            root: Rc::new(VecNode::Branch(Vec::new())),
+            tail: Rc::new(Vec::new())
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // index of the first element stored in the tail buffer
+    fn tail_offset(&self) -> usize {
+        if self.len < WIDTH {
+            0
+        } else {
+            ((self.len - 1) >> BITS) << BITS
+        }
+    }
+
+    // the leaf (or the tail) the element at index lives in
+    fn leaf_for(&self, index: usize) -> &[T] {
+        if index >= self.tail_offset() {
+            return &self.tail;
+        }
+        let mut node = &self.root;
+        let mut level = self.shift;
+        while level > 0 {
+            node = node.child((index >> level) & MASK);
+            level -= BITS;
+        }
+        node.elems()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(&self.leaf_for(index)[index & MASK])
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.tail.last()
+    }
+
+    pub fn iter(&self) -> VecIter<'_, T> {
+        VecIter { vec: self, index: 0, leaf: &[] }
+    }
+}
+
+impl<T: Clone> PersistentVec<T> {
+    pub fn push_back(&self, elem: T) -> PersistentVec<T> {
+        let mut pushed = self.clone();
+        pushed.push_back_in_place(elem);
+        pushed
+    }
+
+    // None if index is out of bounds
+    pub fn update(&self, index: usize, elem: T) -> Option<PersistentVec<T>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut updated = self.clone();
+        updated.update_in_place(index, elem);
+        Some(updated)
+    }
+
+    // the vector without its last element, None if it is empty
+    pub fn pop_back(&self) -> Option<PersistentVec<T>> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut popped = self.clone();
+        popped.pop_back_in_place();
+        Some(popped)
+    }
+
+    fn push_back_in_place(&mut self, elem: T) {
+        if self.len - self.tail_offset() < WIDTH {
+            Rc::make_mut(&mut self.tail).push(elem);
+            self.len += 1;
+            return;
+        }
+
+        // tail is full, it moves into the trie as a new leaf and a new tail is started
+        let full_tail = mem::replace(&mut self.tail, Rc::new(vec![elem]));
+        let leaf = Rc::new(VecNode::Leaf(Rc::try_unwrap(full_tail).unwrap_or_else(|shared| (*shared).clone())));
+        if (self.len >> BITS) > (1 << self.shift) {
+            // root is full as well, the trie grows by one level
+            let old_root = mem::replace(&mut self.root, Rc::new(VecNode::Branch(Vec::new())));
+            let new_path = Self::new_path(self.shift, leaf);
+            *Rc::make_mut(&mut self.root).children_mut() = vec![old_root, new_path];
+            self.shift += BITS;
+        } else {
+            Self::push_leaf(self.len, self.shift, &mut self.root, leaf);
+        }
+        self.len += 1;
+    }
+
+    // a chain of single child branches from the given level down to the leaf
+    fn new_path(level: usize, leaf: Rc<VecNode<T>>) -> Rc<VecNode<T>> {
+        let mut node = leaf;
+        let mut cur_level = 0;
+        while cur_level < level {
+            node = Rc::new(VecNode::Branch(vec![node]));
+            cur_level += BITS;
+        }
+        node
+    }
+
+    // len is the number of elements including the full tail that is being pushed as the leaf
+    fn push_leaf(len: usize, level: usize, node: &mut Rc<VecNode<T>>, leaf: Rc<VecNode<T>>) {
+        let children = Rc::make_mut(node).children_mut();
+        let child_index = ((len - 1) >> level) & MASK;
+        if level == BITS {
+            children.push(leaf);
+        } else if child_index < children.len() {
+            Self::push_leaf(len, level - BITS, &mut children[child_index], leaf);
+        } else {
+            children.push(Self::new_path(level - BITS, leaf));
+        }
+    }
+
+    fn update_in_place(&mut self, index: usize, elem: T) {
+        if index >= self.tail_offset() {
+            Rc::make_mut(&mut self.tail)[index & MASK] = elem;
+            return;
+        }
+        let mut node = &mut self.root;
+        let mut level = self.shift;
+        while level > 0 {
+            node = &mut Rc::make_mut(node).children_mut()[(index >> level) & MASK];
+            level -= BITS;
+        }
+        match Rc::make_mut(node) {
+            VecNode::Leaf(elems) => elems[index & MASK] = elem,
+            VecNode::Branch(_) => unreachable!("branches never hold elements")
+        }
+    }
+
+    fn pop_back_in_place(&mut self) {
+        if self.len - self.tail_offset() > 1 || self.len == 1 {
+            Rc::make_mut(&mut self.tail).pop();
+            self.len -= 1;
+            return;
+        }
+
+        // tail is about to become empty, the last leaf of the trie becomes the new tail
+        let new_tail = self.leaf_for(self.len - 2).to_vec();
+        Self::pop_leaf(self.len, self.shift, &mut self.root);
+        self.tail = Rc::new(new_tail);
+        self.len -= 1;
+
+        // root with a single child is a useless level
+        if self.shift > BITS {
+            if let VecNode::Branch(children) = &*self.root {
+                if children.len() == 1 {
+                    self.root = children[0].clone();
+                    self.shift -= BITS;
+                }
+            }
+        }
+    }
+
+    // removes the last leaf, returns true if the node ended up without children
+    fn pop_leaf(len: usize, level: usize, node: &mut Rc<VecNode<T>>) -> bool {
+        let children = Rc::make_mut(node).children_mut();
+        let child_index = ((len - 2) >> level) & MASK;
+        if level > BITS {
+            if Self::pop_leaf(len, level - BITS, &mut children[child_index]) {
+                children.pop();
+            }
+        } else {
+            children.pop();
+        }
+        children.is_empty()
+    }
+}
+
+impl<T> Clone for PersistentVec<T> {
+    // cheap, the clone shares everything
+    fn clone(&self) -> Self {
+        PersistentVec {
+            len: self.len,
+            shift: self.shift,
+            root: self.root.clone(),
+            tail: self.tail.clone()
+        }
+    }
+}
+
+impl<T> Default for PersistentVec<T> {
+    fn default() -> Self {
+        PersistentVec::new()
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = PersistentVec::new();
+        for elem in iter {
+            vec.push_back_in_place(elem);
+        }
+        vec
+    }
+}
+
+impl<T: Clone> Extend<T> for PersistentVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back_in_place(elem);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PersistentVec<T> {
+    type Item = &'a T;
+    type IntoIter = VecIter<'a, T>;
+
+    fn into_iter(self) -> VecIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for PersistentVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Debug> Debug for PersistentVec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Debug> VecNode<T> {
+    // adds the node and everything below it, returns the node's id
+    fn add_to_dot(node: &Rc<Self>, dot: &mut Dot) -> String {
+        let id = dot.id_of(Rc::as_ptr(node));
+        match &**node {
+            VecNode::Branch(children) => {
+                dot.node(&id, &format!("branch\nstrong: {}", Rc::strong_count(node)));
+                for (index, child) in children.iter().enumerate() {
+                    let child_id = VecNode::add_to_dot(child, dot);
+                    dot.edge(&id, &child_id, &index.to_string());
+                }
+            },
+            VecNode::Leaf(elems) => dot.node(&id, &format!("{:?}\nstrong: {}", elems, Rc::strong_count(node)))
+        }
+        id
+    }
+}
+
+impl<T: Debug> PersistentVec<T> {
+    // the trie and the tail buffer, nodes shared with other versions of the vector have a
+    // strong count above 1
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("persistent_vec");
+        let root_id = VecNode::add_to_dot(&self.root, &mut dot);
+        let tail_id = dot.id_of(Rc::as_ptr(&self.tail));
+        dot.node(&tail_id, &format!("tail {:?}\nstrong: {}", self.tail, Rc::strong_count(&self.tail)));
+        dot.marker("root", &format!("root (len {})", self.len));
+        dot.edge("root", &root_id, "");
+        dot.marker("tail", "tail");
+        dot.edge("tail", &tail_id, "");
+        dot.finish()
+    }
+}
+
+pub struct VecIter<'a, T> {
+    vec: &'a PersistentVec<T>,
+    index: usize,
+    // rest of the current leaf, so the trie is only walked once per 32 elements
+    leaf: &'a [T]
+}
+
+impl<'a, T> Iterator for VecIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.vec.len {
+            return None;
+        }
+        if self.leaf.is_empty() {
+            self.leaf = &self.vec.leaf_for(self.index)[self.index & MASK..];
+        }
+        let (elem, rest) = self.leaf.split_first()?;
+        self.leaf = rest;
+        self.index += 1;
+        Some(elem)
+    }
+}
+
+// PERSISTENT HASH MAP
+// Hash array mapped trie: every level consumes 5 bits of the key's hash to pick one of 32 slots.
+// Nodes only store the slots that are in use, the bitmap says which ones they are, and the
+// position of a slot in the entries vector is the number of used slots before it.
+// Keys whose whole 64 bit hash is equal end up in a collision entry.
+
+#[derive(Clone)]
+enum Entry<K, V> {
+    Leaf(u64, K, V),
+    Collision(u64, Rc<Vec<(K, V)>>),
+    Branch(Rc<MapNode<K, V>>)
+}
+
+#[derive(Clone)]
+struct MapNode<K, V> {
+    bitmap: u32,
+    entries: Vec<Entry<K, V>>
+}
+
+fn hash_of<K: Hash>(key: &K) -> u64 {
+    // DefaultHasher::new is deterministic, so equal keys hash the same in every map
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+// slot bit and position in the entries vector of the given hash on the given level
+fn slot(bitmap: u32, hash: u64, shift: usize) -> (u32, usize) {
+    let bit = 1u32 << ((hash >> shift) as usize & MASK);
+    (bit, (bitmap & (bit - 1)).count_ones() as usize)
+}
+
+impl<K, V> MapNode<K, V> {
+    fn empty() -> Self {
+        MapNode { bitmap: 0, entries: Vec::new() }
+    }
+}
+
+impl<K: Eq, V> MapNode<K, V> {
+    fn get(&self, shift: usize, hash: u64, key: &K) -> Option<&V> {
+        let (bit, position) = slot(self.bitmap, hash, shift);
+        if self.bitmap & bit == 0 {
+            return None;
+        }
+        match &self.entries[position] {
+            Entry::Leaf(leaf_hash, leaf_key, value) => {
+                (*leaf_hash == hash && leaf_key == key).then_some(value)
+            },
+            Entry::Collision(_, pairs) => {
+                pairs.iter().find(|(pair_key, _)| pair_key == key).map(|(_, value)| value)
+            },
+            Entry::Branch(child) => child.get(shift + BITS, hash, key)
+        }
+    }
+}
+
+impl<K: Eq + Clone, V: Clone> MapNode<K, V> {
+    // returns true if the key was not in the map yet
+    fn insert(node: &mut Rc<MapNode<K, V>>, shift: usize, hash: u64, key: K, value: V) -> bool {
+        let node = Rc::make_mut(node);
+        let (bit, position) = slot(node.bitmap, hash, shift);
+        if node.bitmap & bit == 0 {
+            node.bitmap |= bit;
+            node.entries.insert(position, Entry::Leaf(hash, key, value));
+            return true;
+        }
+
+        match &mut node.entries[position] {
+            Entry::Branch(child) => return Self::insert(child, shift + BITS, hash, key, value),
+            Entry::Leaf(leaf_hash, leaf_key, leaf_value) if *leaf_hash == hash && *leaf_key == key => {
+                *leaf_value = value;
+                return false;
+            },
+            Entry::Collision(collision_hash, pairs) if *collision_hash == hash => {
+                let pairs = Rc::make_mut(pairs);
+                return match pairs.iter_mut().find(|(pair_key, _)| *pair_key == key) {
+                    Some((_, pair_value)) => {
+                        *pair_value = value;
+                        false
+                    },
+                    None => {
+                        pairs.push((key, value));
+                        true
+                    }
+                };
+            },
+            _ => {}
+        }
+
+        // slot is taken by a different key, it gets pushed one level down next to the new one
+        let existing = mem::replace(&mut node.entries[position], Entry::Branch(Rc::new(MapNode::empty())));
+        match existing {
+            Entry::Leaf(leaf_hash, leaf_key, leaf_value) if leaf_hash == hash => {
+                node.entries[position] = Entry::Collision(hash, Rc::new(vec![(leaf_key, leaf_value), (key, value)]));
+            },
+            existing => {
+                let existing_hash = match &existing {
+                    Entry::Leaf(leaf_hash, _, _) => *leaf_hash,
+                    Entry::Collision(collision_hash, _) => *collision_hash,
+                    Entry::Branch(_) => unreachable!("branches are handled above")
+                };
+                let (existing_bit, _) = slot(0, existing_hash, shift + BITS);
+                let mut child = Rc::new(MapNode { bitmap: existing_bit, entries: vec![existing] });
+                Self::insert(&mut child, shift + BITS, hash, key, value);
+                node.entries[position] = Entry::Branch(child);
+            }
+        }
+        true
+    }
+
+    // the key has to be in the map, callers check that first so nothing is copied needlessly
+    fn remove(node: &mut Rc<MapNode<K, V>>, shift: usize, hash: u64, key: &K) {
+        let node = Rc::make_mut(node);
+        let (bit, position) = slot(node.bitmap, hash, shift);
+        let remove_entry = match &mut node.entries[position] {
+            Entry::Leaf(_, _, _) => true,
+            Entry::Collision(collision_hash, pairs) => {
+                let pairs = Rc::make_mut(pairs);
+                pairs.retain(|(pair_key, _)| pair_key != key);
+                if pairs.len() == 1 {
+                    let (last_key, last_value) = pairs.pop().unwrap();
+                    node.entries[position] = Entry::Leaf(*collision_hash, last_key, last_value);
+                }
+                false
+            },
+            Entry::Branch(child) => {
+                Self::remove(child, shift + BITS, hash, key);
+                // a branch left with a single leaf is collapsed, so the trie stays as shallow as possible
+                match child.entries.as_slice() {
+                    [] => true,
+                    [Entry::Leaf(..)] | [Entry::Collision(..)] => {
+                        let only_entry = child.entries[0].clone();
+                        node.entries[position] = only_entry;
+                        false
+                    },
+                    _ => false
+                }
+            }
+        };
+        if remove_entry {
+            node.entries.remove(position);
+            node.bitmap &= !bit;
+        }
+    }
+}
+
+pub struct PersistentHashMap<K, V> {
+    len: usize,
+    root: Rc<MapNode<K, V>>
+}
+
+impl<K, V> PersistentHashMap<K, V> {
+    pub fn new() -> Self {
+        PersistentHashMap { len: 0, root: Rc::new(MapNode::empty()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> MapIter<'_, K, V> {
+        MapIter { stack: vec![self.root.entries.iter()], collision: [].iter() }
+    }
+}
+
+impl<K: Hash + Eq, V> PersistentHashMap<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.root.get(0, hash_of(key), key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> PersistentHashMap<K, V> {
+    pub fn insert(&self, key: K, value: V) -> PersistentHashMap<K, V> {
+        let mut inserted = self.clone();
+        inserted.insert_in_place(key, value);
+        inserted
+    }
+
+    pub fn remove(&self, key: &K) -> PersistentHashMap<K, V> {
+        let mut removed = self.clone();
+        if self.contains_key(key) {
+            MapNode::remove(&mut removed.root, 0, hash_of(key), key);
+            removed.len -= 1;
+        }
+        removed
+    }
+
+    fn insert_in_place(&mut self, key: K, value: V) {
+        let hash = hash_of(&key);
+        if MapNode::insert(&mut self.root, 0, hash, key, value) {
+            self.len += 1;
+        }
+    }
+}
+
+impl<K, V> Clone for PersistentHashMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentHashMap { len: self.len, root: self.root.clone() }
+    }
+}
+
+impl<K, V> Default for PersistentHashMap<K, V> {
+    fn default() -> Self {
+        PersistentHashMap::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> FromIterator<(K, V)> for PersistentHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = PersistentHashMap::new();
+        for (key, value) in iter {
+            map.insert_in_place(key, value);
+        }
+        map
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Extend<(K, V)> for PersistentHashMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_in_place(key, value);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a PersistentHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = MapIter<'a, K, V>;
+
+    fn into_iter(self) -> MapIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for PersistentHashMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Debug, V: Debug> MapNode<K, V> {
+    // adds the node and its branches, leaves and collisions are listed in the node's label
+    fn add_to_dot(node: &Rc<Self>, dot: &mut Dot) -> String {
+        let id = dot.id_of(Rc::as_ptr(node));
+        let mut label = format!("bitmap {:032b}\nstrong: {}", node.bitmap, Rc::strong_count(node));
+        let slots = (0..WIDTH).filter(|slot| node.bitmap & (1 << slot) != 0);
+        let mut branches = Vec::new();
+        for (slot, entry) in slots.zip(&node.entries) {
+            match entry {
+                Entry::Leaf(_, key, value) => label.push_str(&format!("\n{}: {:?} => {:?}", slot, key, value)),
+                Entry::Collision(_, pairs) => label.push_str(&format!("\n{}: collision {:?}", slot, pairs)),
+                Entry::Branch(child) => branches.push((slot, child))
+            }
+        }
+        dot.node(&id, &label);
+        for (slot, child) in branches {
+            let child_id = MapNode::add_to_dot(child, dot);
+            dot.edge(&id, &child_id, &slot.to_string());
+        }
+        id
+    }
+}
+
+impl<K: Debug, V: Debug> PersistentHashMap<K, V> {
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("persistent_hash_map");
+        let root_id = MapNode::add_to_dot(&self.root, &mut dot);
+        dot.marker("root", &format!("root (len {})", self.len));
+        dot.edge("root", &root_id, "");
+        dot.finish()
+    }
+}
+
+// walks the trie depth first, the stack holds the not yet visited entries of every level
+pub struct MapIter<'a, K, V> {
+    stack: Vec<std::slice::Iter<'a, Entry<K, V>>>,
+    collision: std::slice::Iter<'a, (K, V)>
+}
+
+impl<'a, K, V> Iterator for MapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.collision.next() {
+                return Some((key, value));
+            }
+            let entries = self.stack.last_mut()?;
+            match entries.next() {
+                Some(Entry::Leaf(_, key, value)) => return Some((key, value)),
+                Some(Entry::Collision(_, pairs)) => self.collision = pairs.iter(),
+                Some(Entry::Branch(child)) => self.stack.push(child.entries.iter()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+// keys hash the same no matter in which order they were inserted, so two maps
+// are equal when they have the same size and every key maps to the same value
+impl<K: Hash + Eq, V: PartialEq> PartialEq for PersistentHashMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PersistentVec, PersistentHashMap};
+    use crate::common::random::Rng;
+    use std::{collections::HashMap, hash::{Hash, Hasher}};
+
+    #[test]
+    fn vec_basics() {
+        let empty = PersistentVec::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.get(0), None);
+        assert!(empty.pop_back().is_none());
+
+        let one = empty.push_back(1);
+        let two = one.push_back(2);
+        assert_eq!(two.len(), 2);
+        assert_eq!(two.get(0), Some(&1));
+        assert_eq!(two.last(), Some(&2));
+        // older versions are untouched
+        assert_eq!(one.len(), 1);
+        assert!(empty.is_empty());
+
+        let changed = two.update(0, 10).unwrap();
+        assert_eq!(changed.first(), Some(&10));
+        assert_eq!(two.first(), Some(&1));
+        assert!(two.update(2, 0).is_none());
+        assert_eq!(format!("{:?}", changed), "[10, 2]");
+    }
+
+    #[test]
+    fn vec_grows_and_shrinks_through_levels() {
+        // 32 * 32 * 32 + a bit, enough for the root to split twice
+        const N: usize = 33 * 32 * 32 + 7;
+        let vec: PersistentVec<usize> = (0..N).collect();
+        assert_eq!(vec.len(), N);
+        assert!(vec.iter().copied().eq(0..N));
+        assert!((0..N).all(|i| vec.get(i) == Some(&i)));
+
+        let mut popped = vec.clone();
+        for len in (0..N).rev() {
+            popped = popped.pop_back().unwrap();
+            assert_eq!(popped.len(), len);
+            if len % 997 == 0 || len < 70 {
+                assert!(popped.iter().copied().eq(0..len));
+            }
+        }
+        assert!(popped.is_empty());
+        assert_eq!(vec.get(N - 1), Some(&(N - 1)));
+    }
+
+    #[test]
+    fn vec_randomized_against_std() {
+        let mut rng = Rng::new(30);
+        let mut vec = PersistentVec::new();
+        let mut model: Vec<u64> = Vec::new();
+        // snapshots of older versions, they must never change
+        let mut history: Vec<(PersistentVec<u64>, Vec<u64>)> = Vec::new();
+
+        for step in 0..20_000 {
+            match rng.gen_range(0..10) {
+                0..=4 => {
+                    let elem = rng.next_u64();
+                    vec = vec.push_back(elem);
+                    model.push(elem);
+                },
+                5 | 6 => {
+                    if let Some(popped) = vec.pop_back() {
+                        vec = popped;
+                    }
+                    model.pop();
+                },
+                7 | 8 => {
+                    if !model.is_empty() {
+                        let index = rng.gen_range(0..model.len());
+                        let elem = rng.next_u64();
+                        vec = vec.update(index, elem).unwrap();
+                        model[index] = elem;
+                    }
+                },
+                _ => {
+                    if !model.is_empty() {
+                        let index = rng.gen_range(0..model.len());
+                        assert_eq!(vec.get(index), model.get(index));
+                    }
+                }
+            }
+            assert_eq!(vec.len(), model.len());
+            assert_eq!(vec.last(), model.last());
+            if step % 500 == 0 {
+                history.push((vec.clone(), model.clone()));
+            }
+        }
+        for (old_vec, old_model) in &history {
+            assert!(old_vec.iter().eq(old_model.iter()));
+        }
+    }
+
+    #[test]
+    fn map_basics() {
+        let empty = PersistentHashMap::new();
+        let one = empty.insert("one", 1);
+        let two = one.insert("two", 2);
+        assert_eq!(two.len(), 2);
+        assert_eq!(two.get(&"one"), Some(&1));
+        assert_eq!(two.get(&"three"), None);
+        assert_eq!(one.get(&"two"), None);
+
+        let replaced = two.insert("one", 11);
+        assert_eq!(replaced.len(), 2);
+        assert_eq!(replaced.get(&"one"), Some(&11));
+        assert_eq!(two.get(&"one"), Some(&1));
+
+        let removed = replaced.remove(&"one");
+        assert_eq!(removed.len(), 1);
+        assert!(!removed.contains_key(&"one"));
+        assert!(replaced.contains_key(&"one"));
+        assert_eq!(removed.remove(&"missing").len(), 1);
+        assert_eq!(format!("{:?}", removed), "{\"two\": 2}");
+        assert!(empty.is_empty());
+    }
+
+    // only a few distinct hashes, so plenty of full hash collisions
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct BadHash(u32);
+
+    impl Hash for BadHash {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            (self.0 % 7).hash(state);
+        }
+    }
+
+    #[test]
+    fn map_collisions() {
+        let map: PersistentHashMap<BadHash, u32> = (0..100).map(|i| (BadHash(i), i)).collect();
+        assert_eq!(map.len(), 100);
+        assert!((0..100).all(|i| map.get(&BadHash(i)) == Some(&i)));
+
+        let mut shrunk = map.clone();
+        for i in (0..100).filter(|i| i % 3 != 0) {
+            shrunk = shrunk.remove(&BadHash(i));
+        }
+        assert_eq!(shrunk.len(), 34);
+        assert!((0..100).all(|i| shrunk.contains_key(&BadHash(i)) == (i % 3 == 0)));
+        assert_eq!(map.len(), 100);
+    }
+
+    fn check_map_randomized<K: Hash + Eq + Clone + std::fmt::Debug>(seed: u64, make_key: impl Fn(u64) -> K) {
+        let mut rng = Rng::new(seed);
+        let mut map = PersistentHashMap::new();
+        let mut model: HashMap<K, u64> = HashMap::new();
+        let mut history = Vec::new();
+
+        for step in 0..20_000 {
+            let key = make_key(rng.next_u64() % 2_000);
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let value = rng.next_u64();
+                    map = map.insert(key.clone(), value);
+                    model.insert(key, value);
+                },
+                2 => {
+                    map = map.remove(&key);
+                    model.remove(&key);
+                },
+                _ => assert_eq!(map.get(&key), model.get(&key))
+            }
+            assert_eq!(map.len(), model.len());
+            if step % 1_000 == 0 {
+                history.push((map.clone(), model.clone()));
+            }
+        }
+        history.push((map, model));
+
+        for (old_map, old_model) in &history {
+            assert_eq!(old_map.len(), old_model.len());
+            assert_eq!(old_map.iter().count(), old_model.len());
+            for (key, value) in old_model {
+                assert_eq!(old_map.get(key), Some(value));
+            }
+            let rebuilt: PersistentHashMap<K, u64> = old_model.iter().map(|(key, value)| (key.clone(), *value)).collect();
+            assert!(rebuilt == *old_map);
+        }
+    }
+
+    #[test]
+    fn map_randomized_against_std() {
+        check_map_randomized(31, |key| key);
+        check_map_randomized(32, |key| BadHash(key as u32));
+    }
+
+    #[test]
+    fn vec_to_dot() {
+        let first: PersistentVec<usize> = (0..40).collect();
+        let second = first.update(0, 100).unwrap();
+        let dot = first.to_dot();
+        assert!(dot.contains("branch\\nstrong: 1"), "{}", dot);
+        // the tail buffer wasn't touched by the update, both versions share it
+        assert!(dot.contains("tail [32, 33, 34, 35, 36, 37, 38, 39]\\nstrong: 2"), "{}", dot);
+        assert!(dot.contains("root (len 40)"), "{}", dot);
+        assert!(second.to_dot().contains("[100, 1, 2"));
+    }
+
+    #[test]
+    fn map_to_dot() {
+        let map: PersistentHashMap<u32, char> = (0..100).map(|key| (key, 'x')).collect();
+        let dot = map.to_dot();
+        assert_eq!(dot.matches("=> 'x'").count(), 100, "{}", dot);
+        assert!(dot.contains("root (len 100)"), "{}", dot);
+        let shared = map.clone();
+        assert!(shared.to_dot().contains("strong: 2"));
+    }
+}
@@ -0,0 +1,74 @@
+use std::{ops::Deref, rc::Rc, sync::Arc};
+
+// Lets a data structure be written once and then used either with Rc (cheaper, single threaded)
+// or with Arc (atomic refcount, so the structure is Send + Sync when its elements are).
+// The kind is just a marker type, the actual pointer is the generic associated type.
+pub trait SharedPointerKind {
+    type Pointer<T>: Deref<Target = T> + Clone;
+
+    fn new<T>(value: T) -> Self::Pointer<T>;
+
+    // same contract as Rc::try_unwrap - gives the value back if this is the only strong reference
+    fn try_unwrap<T>(pointer: Self::Pointer<T>) -> Result<T, Self::Pointer<T>>;
+
+    // like try_unwrap, but drops the pointer when it is not the last one. If several threads
+    // race to drop their clones, exactly one of them gets the value back, whereas with
+    // try_unwrap all of them could fail and the value would be dropped in the last decrement
+    fn into_inner<T>(pointer: Self::Pointer<T>) -> Option<T>;
+
+    fn ptr_eq<T>(first: &Self::Pointer<T>, second: &Self::Pointer<T>) -> bool;
+
+    fn strong_count<T>(pointer: &Self::Pointer<T>) -> usize;
+}
+
+pub struct RcPointer;
+
+pub struct ArcPointer;
+
+impl SharedPointerKind for RcPointer {
+    type Pointer<T> = Rc<T>;
+
+    fn new<T>(value: T) -> Rc<T> {
+        Rc::new(value)
+    }
+
+    fn try_unwrap<T>(pointer: Rc<T>) -> Result<T, Rc<T>> {
+        Rc::try_unwrap(pointer)
+    }
+
+    fn into_inner<T>(pointer: Rc<T>) -> Option<T> {
+        Rc::into_inner(pointer)
+    }
+
+    fn ptr_eq<T>(first: &Rc<T>, second: &Rc<T>) -> bool {
+        Rc::ptr_eq(first, second)
+    }
+
+    fn strong_count<T>(pointer: &Rc<T>) -> usize {
+        Rc::strong_count(pointer)
+    }
+}
+
+impl SharedPointerKind for ArcPointer {
+    type Pointer<T> = Arc<T>;
+
+    fn new<T>(value: T) -> Arc<T> {
+        Arc::new(value)
+    }
+
+    fn try_unwrap<T>(pointer: Arc<T>) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(pointer)
+    }
+
+    fn into_inner<T>(pointer: Arc<T>) -> Option<T> {
+        Arc::into_inner(pointer)
+    }
+
+    fn ptr_eq<T>(first: &Arc<T>, second: &Arc<T>) -> bool {
+        Arc::ptr_eq(first, second)
+    }
+
+    fn strong_count<T>(pointer: &Arc<T>) -> usize {
+        Arc::strong_count(pointer)
+    }
+}
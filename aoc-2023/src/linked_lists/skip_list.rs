@@ -0,0 +1,483 @@
+use std::{fmt::{self, Debug, Display, Formatter}, ops::{Bound, RangeBounds}, str::FromStr};
+
+use super::text::{self, ParseListError};
+use crate::common::{dot::Dot, random::Rng};
+
+// Sorted set built from a linked list with express lanes. Every node is in the bottom list,
+// and each node is also in the next list up with probability 1/4, so a search can skip
+// ahead on the sparse upper levels and only walk a few nodes on every level on its way down.
+// That gives expected O(log n) search, insert and remove, where the sorted doubly linked
+// list has to walk through everything.
+//
+// Every link also remembers its width - how many bottom level nodes it jumps over - which
+// makes finding the rank of an element or the nth element O(log n) too.
+//
+// Nodes are stored in a Vec and link to each other by index (like the arena lists), slot 0
+// is the head, a sentinel without an element that is present on every level.
+// A link to nowhere gets the width it would have if there was one more node behind the last one,
+// so the widths stay consistent without special cases for the ends.
+
+const MAX_LEVEL: usize = 32;
+const HEAD: usize = 0;
+
+#[derive(Clone)]
+struct SkipNode<T> {
+    // None for the head and for free slots
+    elem: Option<T>,
+    next: Vec<Option<usize>>,
+    width: Vec<usize>
+}
+
+#[derive(Clone)]
+pub struct SkipList<T> {
+    nodes: Vec<SkipNode<T>>,
+    free: Vec<usize>,
+    len: usize,
+    // number of levels in use, the head has MAX_LEVEL of them all the time
+    level: usize,
+    rng: Rng
+}
+
+// for every level, the last node before the searched position and its rank (head has rank 0)
+struct Predecessors {
+    nodes: [usize; MAX_LEVEL],
+    ranks: [usize; MAX_LEVEL]
+}
+
+impl<T> SkipList<T> {
+    pub fn new() -> Self {
+        SkipList::with_seed(0)
+    }
+
+    // the shape of the list depends on the coin flips, a seed makes it reproducible
+    pub fn with_seed(seed: u64) -> Self {
+        let head = SkipNode { elem: None, next: vec![None; MAX_LEVEL], width: vec![1; MAX_LEVEL] };
+        SkipList { nodes: vec![head], free: Vec::new(), len: 0, level: 1, rng: Rng::new(seed) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.nodes[HEAD].next[0].map(|index| self.elem(index))
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.nth(self.len.checked_sub(1)?)
+    }
+
+    // 0 based, in sorted order
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        if n >= self.len {
+            return None;
+        }
+        let target = n + 1;
+        let mut cur = HEAD;
+        let mut rank = 0;
+        for level in (0..self.level).rev() {
+            // links to nowhere are wide enough to never be taken here
+            while rank + self.nodes[cur].width[level] <= target {
+                rank += self.nodes[cur].width[level];
+                cur = self.nodes[cur].next[level].unwrap();
+            }
+        }
+        Some(self.elem(cur))
+    }
+
+    // the first node sits right behind the head on every level it is on, no search needed
+    pub fn pop_first(&mut self) -> Option<T> {
+        let first = self.nodes[HEAD].next[0]?;
+        let found = Predecessors { nodes: [HEAD; MAX_LEVEL], ranks: [0; MAX_LEVEL] };
+        Some(self.unlink(&found, first))
+    }
+
+    pub fn clear(&mut self) {
+        *self = SkipList { rng: self.rng.clone(), ..SkipList::new() };
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { list: self, cur: self.nodes[HEAD].next[0], remaining: self.len }
+    }
+
+    fn elem(&self, index: usize) -> &T {
+        self.nodes[index].elem.as_ref().expect("only the head and free slots have no element")
+    }
+
+    // walks down from the top level, moving right while the next element still goes before the position
+    fn predecessors(&self, mut goes_before: impl FnMut(&T) -> bool) -> Predecessors {
+        let mut found = Predecessors { nodes: [HEAD; MAX_LEVEL], ranks: [0; MAX_LEVEL] };
+        let mut cur = HEAD;
+        let mut rank = 0;
+        for level in (0..self.level).rev() {
+            while let Some(next) = self.nodes[cur].next[level] {
+                if !goes_before(self.elem(next)) {
+                    break;
+                }
+                rank += self.nodes[cur].width[level];
+                cur = next;
+            }
+            found.nodes[level] = cur;
+            found.ranks[level] = rank;
+        }
+        found
+    }
+
+    // 1 + number of successful coin flips, a quarter chance each
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.gen_range(0..4) == 0 {
+            level += 1;
+        }
+        level
+    }
+
+    fn unlink(&mut self, found: &Predecessors, index: usize) -> T {
+        for lvl in 0..self.level {
+            let before = found.nodes[lvl];
+            if self.nodes[before].next[lvl] == Some(index) {
+                self.nodes[before].next[lvl] = self.nodes[index].next[lvl];
+                self.nodes[before].width[lvl] += self.nodes[index].width[lvl] - 1;
+            } else {
+                self.nodes[before].width[lvl] -= 1;
+            }
+        }
+        while self.level > 1 && self.nodes[HEAD].next[self.level - 1].is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+        self.free.push(index);
+        let node = &mut self.nodes[index];
+        node.next = Vec::new();
+        node.width = Vec::new();
+        node.elem.take().expect("only the head and free slots have no element")
+    }
+
+    fn allocate(&mut self, elem: T, level: usize) -> usize {
+        let node = SkipNode { elem: Some(elem), next: vec![None; level], width: vec![0; level] };
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+}
+
+impl<T: Ord> SkipList<T> {
+    // returns false (and drops the element) if an equal one is in the list already
+    pub fn insert(&mut self, elem: T) -> bool {
+        let mut found = self.predecessors(|other| other < &elem);
+        if self.nodes[found.nodes[0]].next[0].is_some_and(|next| *self.elem(next) == elem) {
+            return false;
+        }
+        let level = self.random_level();
+        if level > self.level {
+            for new_level in self.level..level {
+                found.nodes[new_level] = HEAD;
+                found.ranks[new_level] = 0;
+                self.nodes[HEAD].width[new_level] = self.len + 1;
+            }
+            self.level = level;
+        }
+
+        let rank = found.ranks[0];
+        let index = self.allocate(elem, level);
+        for lvl in 0..level {
+            let before = found.nodes[lvl];
+            // the old link gets split in two, the new node sits rank - ranks[lvl] + 1 steps after the predecessor
+            let steps_before = rank - found.ranks[lvl] + 1;
+            self.nodes[index].next[lvl] = self.nodes[before].next[lvl];
+            self.nodes[index].width[lvl] = self.nodes[before].width[lvl] + 1 - steps_before;
+            self.nodes[before].next[lvl] = Some(index);
+            self.nodes[before].width[lvl] = steps_before;
+        }
+        // links above the new node now jump over one more node
+        for lvl in level..self.level {
+            self.nodes[found.nodes[lvl]].width[lvl] += 1;
+        }
+        self.len += 1;
+        true
+    }
+
+    pub fn remove(&mut self, elem: &T) -> Option<T> {
+        let found = self.predecessors(|other| other < elem);
+        let index = self.nodes[found.nodes[0]].next[0].filter(|next| self.elem(*next) == elem)?;
+        Some(self.unlink(&found, index))
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.lower_bound(elem) == Some(elem)
+    }
+
+    // number of elements smaller than elem, also the index elem has (or would have) in the list
+    pub fn rank(&self, elem: &T) -> usize {
+        self.predecessors(|other| other < elem).ranks[0]
+    }
+
+    // smallest element >= elem
+    pub fn lower_bound(&self, elem: &T) -> Option<&T> {
+        let found = self.predecessors(|other| other < elem);
+        self.nodes[found.nodes[0]].next[0].map(|index| self.elem(index))
+    }
+
+    // smallest element > elem
+    pub fn upper_bound(&self, elem: &T) -> Option<&T> {
+        let found = self.predecessors(|other| other <= elem);
+        self.nodes[found.nodes[0]].next[0].map(|index| self.elem(index))
+    }
+
+    // same bounds as BTreeSet::range, e.g. list.range(3..8) or list.range(..=5)
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Iter<'_, T> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.predecessors(|other| other < start),
+            Bound::Excluded(start) => self.predecessors(|other| other <= start),
+            Bound::Unbounded => self.predecessors(|_| false)
+        };
+        let end_rank = match range.end_bound() {
+            Bound::Included(end) => self.predecessors(|other| other <= end).ranks[0],
+            Bound::Excluded(end) => self.predecessors(|other| other < end).ranks[0],
+            Bound::Unbounded => self.len
+        };
+        Iter {
+            list: self,
+            cur: self.nodes[start.nodes[0]].next[0],
+            remaining: end_rank.saturating_sub(start.ranks[0])
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a SkipList<T>,
+    cur: Option<usize>,
+    remaining: usize
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.cur.map(|index| {
+            self.remaining -= 1;
+            self.cur = self.list.nodes[index].next[0];
+            self.list.elem(index)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<T> Default for SkipList<T> {
+    fn default() -> Self {
+        SkipList::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SkipList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = SkipList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Ord> Extend<T> for SkipList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.insert(elem);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SkipList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SkipList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Display> Display for SkipList<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.iter())
+    }
+}
+
+impl<T: Debug> Debug for SkipList<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// duplicates in the text collapse into one element, like with collect
+impl<T: Ord + FromStr> FromStr for SkipList<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T: Debug> SkipList<T> {
+    // the nodes in order with one edge per level they are linked on, labelled with the level
+    // and the width of the link
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("skip_list");
+        let mut cur = Some(HEAD);
+        while let Some(index) = cur {
+            let node = &self.nodes[index];
+            let id = format!("n{}", index);
+            match &node.elem {
+                Some(elem) => dot.node(&id, &format!("{:?}", elem)),
+                None => dot.node(&id, "head")
+            }
+            // the head has every level, only the ones in use are interesting
+            let levels = if index == HEAD { self.level } else { node.next.len() };
+            for level in 0..levels {
+                if let Some(next) = node.next[level] {
+                    dot.edge(&id, &format!("n{}", next), &format!("L{} width {}", level, node.width[level]));
+                }
+            }
+            cur = node.next[0];
+        }
+        dot.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::ops::Bound;
+
+    use super::SkipList;
+    use crate::common::random::Rng;
+
+    #[test]
+    fn basics() {
+        let mut list = SkipList::new();
+        assert!(list.is_empty());
+        assert_eq!(list.first(), None);
+        for elem in [5, 1, 9, 3, 7] {
+            assert!(list.insert(elem));
+        }
+        assert!(!list.insert(3));
+        assert_eq!(list.len(), 5);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+        assert_eq!((list.first(), list.last()), (Some(&1), Some(&9)));
+        assert!(list.contains(&7));
+        assert!(!list.contains(&4));
+        assert_eq!(list.remove(&5), Some(5));
+        assert_eq!(list.remove(&5), None);
+        assert_eq!(list.pop_first(), Some(1));
+        assert_eq!(format!("{:?}", list), "[3, 7, 9]");
+    }
+
+    #[test]
+    fn queries() {
+        let list: SkipList<i32> = (0..50).map(|elem| elem * 2).collect();
+        assert_eq!(list.nth(0), Some(&0));
+        assert_eq!(list.nth(10), Some(&20));
+        assert_eq!(list.nth(50), None);
+        assert_eq!(list.rank(&20), 10);
+        assert_eq!(list.rank(&21), 11);
+        assert_eq!(list.rank(&-5), 0);
+        assert_eq!(list.rank(&1000), 50);
+        assert_eq!(list.lower_bound(&20), Some(&20));
+        assert_eq!(list.lower_bound(&21), Some(&22));
+        assert_eq!(list.upper_bound(&20), Some(&22));
+        assert_eq!(list.upper_bound(&98), None);
+        assert_eq!(list.range(10..16).copied().collect::<Vec<_>>(), vec![10, 12, 14]);
+        assert_eq!(list.range(11..=16).copied().collect::<Vec<_>>(), vec![12, 14, 16]);
+        assert_eq!(list.range(95..).copied().collect::<Vec<_>>(), vec![96, 98]);
+        assert_eq!(list.range(..3).len(), 2);
+        assert_eq!(list.range((Bound::Excluded(96), Bound::Unbounded)).collect::<Vec<_>>(), vec![&98]);
+        assert_eq!(list.range((Bound::Included(30), Bound::Excluded(20))).count(), 0);
+    }
+
+    #[test]
+    fn levels_shrink_back() {
+        let mut list = SkipList::with_seed(3);
+        list.extend(0..10_000);
+        assert!(list.level > 1);
+        for elem in 0..10_000 {
+            assert_eq!(list.remove(&elem), Some(elem));
+        }
+        assert_eq!(list.level, 1);
+        assert!(list.is_empty());
+        // freed slots get reused
+        list.extend(0..10_000);
+        assert_eq!(list.nodes.len(), 10_001);
+    }
+
+    // random inserts, removes and queries, everything has to agree with BTreeSet
+    #[test]
+    fn matches_btree_set() {
+        for seed in 0..20 {
+            let mut rng = Rng::new(seed);
+            let mut list = SkipList::with_seed(seed);
+            let mut model = BTreeSet::new();
+            for _ in 0..2_000 {
+                let elem = rng.gen_range(0..500) as i32;
+                match rng.gen_range(0..10) {
+                    0..=3 => assert_eq!(list.insert(elem), model.insert(elem)),
+                    4..=5 => assert_eq!(list.remove(&elem).is_some(), model.remove(&elem)),
+                    6 => assert_eq!(list.pop_first(), model.pop_first()),
+                    7 => {
+                        assert_eq!(list.contains(&elem), model.contains(&elem));
+                        assert_eq!(list.rank(&elem), model.range(..elem).count());
+                        assert_eq!(list.lower_bound(&elem), model.range(elem..).next());
+                        assert_eq!(list.upper_bound(&elem), model.range(elem + 1..).next());
+                    },
+                    8 => {
+                        let index = rng.gen_range(0..model.len() + 1);
+                        assert_eq!(list.nth(index), model.iter().nth(index));
+                    },
+                    _ => {
+                        let end = elem + rng.gen_range(0..100) as i32;
+                        assert!(list.range(elem..end).eq(model.range(elem..end)));
+                        assert!(list.range(elem..=end).eq(model.range(elem..=end)));
+                    }
+                }
+                assert_eq!(list.len(), model.len());
+            }
+            assert!(list.iter().eq(model.iter()));
+            assert_eq!(list.last(), model.last());
+        }
+    }
+
+    #[test]
+    fn to_dot() {
+        let list: SkipList<i32> = (1..=20).collect();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"head\"];"), "{}", dot);
+        assert_eq!(dot.matches("L0 width 1").count(), 20, "{}", dot);
+        // every level in use starts at the head
+        for level in 0..list.level {
+            let label = format!("L{} width", level);
+            assert!(dot.lines().any(|line| line.trim_start().starts_with("n0 -> ") && line.contains(&label)), "{}", dot);
+        }
+        assert!(!dot.contains(&format!("L{} ", list.level)), "{}", dot);
+    }
+}
@@ -1,9 +1,29 @@
-use std::{rc::Rc, cell::{RefCell, Ref, RefMut}, fmt::Debug, io::empty};
+use std::{rc::Rc, cell::{RefCell, Ref}, fmt::{self, Debug, Display, Formatter}, cmp::Ordering, str::FromStr};
+
+use super::{collection::SortedCollection, text::{self, ParseListError}};
+use crate::common::dot;
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
+// Rc and not Box, so clones of the list can share it
+type Comparator<T> = Rc<dyn Fn(&T, &T) -> Ordering>;
+
+// where a new element ends up relative to the elements that compare equal to it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EqualPlacement {
+    // behind them, so equal elements stay in the order they were inserted in
+    Stable,
+    // in front of them, that is where the first equal element is found, so the walk is shorter
+    Unstable
+}
+
 pub struct List<T> {
-    head: Link<T>
+    head: Link<T>,
+    // keeping the tail around makes appending already sorted input O(1)
+    tail: Link<T>,
+    len: usize,
+    compare: Comparator<T>,
+    placement: EqualPlacement
 }
 
 struct Node<T> {
@@ -15,118 +35,333 @@ struct Node<T> {
 impl<T> Node<T> {
     fn new(elem: T) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Node {
-            elem: elem,
+            elem,
             prev: None,
             next: None
         }))
     }
 }
 
-impl<T : PartialOrd + Debug> List<T>  { 
+impl<T: PartialOrd> List<T> {
     pub fn new() -> Self {
-        List {head: None}
+        List::with_comparator(|a: &T, b: &T| total_cmp(a, b))
     }
 
-    pub fn print(&self) {
-        let mut cur = self.head.clone();
-        while let Some(node) = cur  {
-            println!("[{:?}]", node.borrow().elem);
-            cur = node.borrow().next.clone();
+    // sorting once and then linking the nodes one after another is O(n log n),
+    // calling insert for every element would be O(n^2)
+    pub fn from_unsorted<I: IntoIterator<Item = T>>(elems: I) -> Self {
+        let mut list = List::new();
+        list.extend_unsorted(elems);
+        list
+    }
+}
+
+// partial_cmp made total, the way sort_by needs it. Things like NaN don't even compare to
+// themselves, they are equal to each other and go behind everything else, which is also where
+// insert used to put them when it compared with <= directly. Elements that do compare to
+// themselves but not to each other are called equal, for an actual partial order that isn't
+// total either, use with_comparator
+fn total_cmp<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).unwrap_or_else(|| {
+        let a_incomparable = a.partial_cmp(a).is_none();
+        let b_incomparable = b.partial_cmp(b).is_none();
+        a_incomparable.cmp(&b_incomparable)
+    })
+}
+
+impl<T> List<T> {
+    // the comparator has to be a total order. Otherwise the order of the list is unspecified and
+    // extend_unsorted (and with it from_unsorted, collect and parsing) can panic inside sort_by
+    pub fn with_comparator<F: Fn(&T, &T) -> Ordering + 'static>(compare: F) -> Self {
+        List {
+            head: None,
+            tail: None,
+            len: 0,
+            compare: Rc::new(compare),
+            placement: EqualPlacement::Unstable
         }
     }
 
-    pub fn insert(&mut self, elem:T) {
-        //let new_node = Node::new(elem);
+    // keeps elements sorted by whatever the key function extracts from them,
+    // e.g. List::with_key(|record: &NumberRecord| record.value)
+    pub fn with_key<K: Ord, F: Fn(&T) -> K + 'static>(key: F) -> Self {
+        List::with_comparator(move |a: &T, b: &T| key(a).cmp(&key(b)))
+    }
 
-        let taken_head = self.head.take();
-        self.head = self.insert_recursive(taken_head, elem);
+    pub fn with_placement(mut self, placement: EqualPlacement) -> Self {
+        self.placement = placement;
+        self
     }
 
-    fn insert_recursive(&self, current: Link<T>, elem:T) -> Link<T> {
-        match current {
-            Some(ref cur_node) => {
-                let should_insert = elem <= cur_node.borrow().elem;
-                if should_insert {
-                    let new_node = Node::new(elem);
-                    let prev_node = cur_node.borrow_mut().prev.take();
-                    new_node.borrow_mut().prev = prev_node.clone();
-                    new_node.borrow_mut().next = Some(cur_node.clone());
-                    cur_node.borrow_mut().prev = Some(new_node.clone());
-                    if let Some(ref prev_node) = new_node.borrow().prev {
-                        prev_node.borrow_mut().next = Some(Rc::clone(&new_node));
-                    } 
-                    return Some(Rc::clone(&new_node));
-                }
-                else{
-                    let new_next = self.insert_recursive(cur_node.borrow().next.clone(), elem);
-                    cur_node.borrow_mut().next = new_next;
-                    Some(cur_node.clone())
+    pub fn placement(&self) -> EqualPlacement {
+        self.placement
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // smallest element is always at the front
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
+        self.head.as_ref().map(|node| {
+            Ref::map(node.borrow(), |node| &node.elem)
+        })
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|old_head| {
+            match old_head.borrow_mut().next.take() {
+                Some(new_head) => {
+                    new_head.borrow_mut().prev.take();
+                    self.head = Some(new_head);
+                },
+                None => {
+                    self.tail.take();
                 }
             }
+            self.len -= 1;
+            // nobody else points to the old head anymore, so try_unwrap cannot fail
+            Rc::try_unwrap(old_head).ok().unwrap().into_inner().elem
+        })
+    }
+
+    // only for callers that already know elem belongs behind the current tail
+    fn push_back(&mut self, elem: T) {
+        let new_node = Node::new(elem);
+        match self.tail.take() {
+            Some(old_tail) => {
+                old_tail.borrow_mut().next = Some(new_node.clone());
+                new_node.borrow_mut().prev = Some(old_tail);
+            },
             None => {
-                let new_node = Node::new(elem);
-                return  Some(new_node);
+                self.head = Some(new_node.clone());
             }
         }
+        self.tail = Some(new_node);
+        self.len += 1;
+    }
+
+    fn insert_before(&mut self, next_node: &Rc<RefCell<Node<T>>>, elem: T) {
+        let new_node = Node::new(elem);
+        let prev_node = next_node.borrow_mut().prev.take();
+        match prev_node {
+            Some(prev_node) => {
+                prev_node.borrow_mut().next = Some(new_node.clone());
+                new_node.borrow_mut().prev = Some(prev_node);
+            },
+            None => {
+                self.head = Some(new_node.clone());
+            }
+        }
+        new_node.borrow_mut().next = Some(next_node.clone());
+        next_node.borrow_mut().prev = Some(new_node);
+        self.len += 1;
+    }
+}
+
+impl<T> List<T> {
+    // true if elem has to be placed somewhere behind existing_elem
+    fn goes_behind(&self, elem: &T, existing_elem: &T) -> bool {
+        match (self.compare)(elem, existing_elem) {
+            Ordering::Less => false,
+            Ordering::Equal => self.placement == EqualPlacement::Stable,
+            Ordering::Greater => true
+        }
+    }
+
+    // anything that belongs behind the tail is appended right away, otherwise the list is
+    // walked from the front until the first element elem doesn't go behind
+    pub fn insert(&mut self, elem: T) {
+        let goes_behind_tail = match self.tail {
+            Some(ref tail) => self.goes_behind(&elem, &tail.borrow().elem),
+            None => true
+        };
+        if goes_behind_tail {
+            self.push_back(elem);
+            return;
+        }
+
+        let mut cur = self.head.clone();
+        while let Some(cur_node) = cur {
+            if !self.goes_behind(&elem, &cur_node.borrow().elem) {
+                self.insert_before(&cur_node, elem);
+                return;
+            }
+            cur = cur_node.borrow().next.clone();
+        }
+        // unreachable in practice, elem does not go behind the tail so we have stopped somewhere
+        self.push_back(elem);
+    }
+
+    // sorts the new elements once and merges them into the list in a single pass,
+    // so this is O(m log m + n) instead of the O(m * n) of inserting them one by one
+    pub fn extend_unsorted<I: IntoIterator<Item = T>>(&mut self, elems: I) {
+        let mut elems: Vec<T> = elems.into_iter().collect();
+        // sort_by is stable, which keeps equal elements in their original order for EqualPlacement::Stable
+        elems.sort_by(|a, b| (self.compare)(a, b));
+
+        let mut cur = self.head.clone();
+        for elem in elems {
+            // the new elements are sorted, so the position of the next one can only be further down the list
+            while let Some(cur_node) = cur.clone() {
+                if !self.goes_behind(&elem, &cur_node.borrow().elem) {
+                    break;
+                }
+                cur = cur_node.borrow().next.clone();
+            }
+            match cur {
+                Some(ref cur_node) => self.insert_before(cur_node, elem),
+                None => self.push_back(elem)
+            }
+        }
+    }
+}
+
+// Without this the list would leak, every node is kept alive by the prev link of its successor.
+// Popping from the front unlinks the nodes one by one, so nothing recurses either.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+// the clone keeps the ordering of the original, nodes are just linked in the same order
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List {
+            head: None,
+            tail: None,
+            len: 0,
+            compare: self.compare.clone(),
+            placement: self.placement
+        };
+        let mut cur = self.head.clone();
+        while let Some(node) = cur {
+            list.push_back(node.borrow().elem.clone());
+            cur = node.borrow().next.clone();
+        }
+        list
+    }
+}
+
+// compares just the elements, two lists sorted differently can still be equal
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        let mut first = self.head.clone();
+        let mut second = other.head.clone();
+        while let (Some(first_node), Some(second_node)) = (first, second) {
+            if first_node.borrow().elem != second_node.borrow().elem {
+                return false;
+            }
+            first = first_node.borrow().next.clone();
+            second = second_node.borrow().next.clone();
+        }
+        true
+    }
+}
+
+impl<T> SortedCollection<T> for List<T> {
+    type ElemRef<'a> = Ref<'a, T> where T: 'a;
+
+    fn insert(&mut self, elem: T) {
+        List::insert(self, elem)
+    }
+
+    fn pop_first(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn first(&self) -> Option<Ref<'_, T>> {
+        self.peek_front()
+    }
+
+    fn len(&self) -> usize {
+        List::len(self)
+    }
+}
+
+impl<T: PartialOrd> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T: PartialOrd> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        List::from_unsorted(iter)
+    }
+}
+
+// keeps whatever ordering the list was created with
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.extend_unsorted(iter)
+    }
+}
+
+// INTO ITER - pops the smallest element over and over
+pub struct IntoIter<T>(List<T>);
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+}
+
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_list(f, text::rc_nodes(&self.head, |node| &node.next), |f, node| write!(f, "{}", node.borrow().elem))
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_list(f, text::rc_nodes(&self.head, |node| &node.next), |f, node| write!(f, "{:?}", node.borrow().elem))
     }
+}
+
+// the text doesn't have to be sorted, the list sorts it with the natural ordering
+impl<T: PartialOrd + FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
 
-    // pub fn insert(&mut self, elem:T) {
-    //     match self.head.take() {
-    //         Some(mut old_head) => {
-    //             // inserting new head to non-empty list
-    //             //let mut mut_borrowed_old_head = old_head.borrow_mut();
-    //             if  old_head.borrow().elem > elem {
-    //                 let new_node = Node::new(elem);
-    //                 new_node.borrow_mut().next = Some(old_head.clone());
-    //                 old_head.borrow_mut().prev = Some(new_node.clone());
-    //                 self.head = Some(new_node);
-    //             }
-    //             // insert into the middle of the list
-    //             else {
-    //                 List::insert_impl(&mut old_head, elem);
-    //                 self.head = Some(old_head);
-    //             }
-    //         }
-    //         None => {
-    //             let new_node = Node::new(elem);
-    //             self.head = Some(new_node);
-    //         },
-    //     }
-    // }
-
-    // fn insert_impl(cur: &mut Rc<RefCell<Node<T>>>, elem:T) {
-    //     println!("cur: {:?}", cur.borrow().elem);
-    //     let mut mut_borrowd_cur = cur.borrow_mut();
-    //     match mut_borrowd_cur.next.take() {
-    //         Some(next) => {
-    //             let mut muttably_borrowed_next: RefMut<'_, Node<T>> = next.borrow_mut();
-    //             if mut_borrowd_cur.elem < elem && muttably_borrowed_next.elem >= elem {
-    //                 let new_node = Node::new(elem);
-    //                 let mut new_mut_borrow = new_node.borrow_mut();
-    //                 new_mut_borrow.next = mut_borrowd_cur.next.clone();
-    //                 new_mut_borrow.prev = Some(cur.clone());
-    //                 mut_borrowd_cur.next = Some(new_node.clone());
-    //                 muttably_borrowed_next.prev = Some(new_node.clone());
-    //             }
-    //             else {
-    //                 println!("travering further");
-    //                 List::insert_impl(&mut next.clone(), elem);
-    //             }
-    //         }
-    //         None => {
-    //             // insert at the end of list
-    //             let new_node = Node::new(elem);
-    //             new_node.borrow_mut().prev = Some(cur.clone());
-    //             mut_borrowd_cur.next = Some(new_node);
-    //             return;
-    //         },
-    //     }
-    // }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| List::from_unsorted(elems))
+    }
+}
+
+impl<T: Debug> List<T> {
+    pub fn print(&self) {
+        println!("{:?}", self);
+    }
+
+    pub fn to_dot(&self) -> String {
+        dot::doubly_linked("sorted_list", &self.head, &self.tail, |node| &node.elem, |node| &node.next, |node| &node.prev)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{List, EqualPlacement};
+    use crate::common::{drop_counter::DropCounter, random::Rng};
+    use std::rc::Rc;
     #[test]
     fn insert_at_beggining() {
         let mut list:List<i32> = List::new();
@@ -155,4 +390,207 @@ mod test {
         println!("List b");
         list.print();
     }
-}
\ No newline at end of file
+
+    fn drain(list: &mut List<i32>) -> Vec<i32> {
+        let mut elems = Vec::new();
+        while let Some(elem) = list.pop_front() {
+            elems.push(elem);
+        }
+        elems
+    }
+
+    #[test]
+    fn insert_keeps_order() {
+        let mut list = List::new();
+        for elem in [5, 1, 4, 1, 3, 9, 2, 6] {
+            list.insert(elem);
+        }
+        assert_eq!(list.len(), 8);
+        assert_eq!(&*list.peek_front().unwrap(), &1);
+        assert_eq!(drain(&mut list), vec![1, 1, 2, 3, 4, 5, 6, 9]);
+        assert!(list.is_empty());
+        assert!(list.peek_front().is_none());
+
+        // list must still be usable after it was emptied
+        list.insert(7);
+        list.insert(3);
+        assert_eq!(drain(&mut list), vec![3, 7]);
+    }
+
+    #[test]
+    fn insert_nan() {
+        let mut list = List::new();
+        for elem in [2.0, f64::NAN, 1.0, f64::NAN, 3.0] {
+            list.insert(elem);
+        }
+        let elems: Vec<f64> = list.into_iter().collect();
+        // the NaNs go behind everything, even behind what was inserted after them
+        assert_eq!(elems[..3], [1.0, 2.0, 3.0]);
+        assert!(elems[3..].iter().all(|elem| elem.is_nan()));
+        assert_eq!(elems.len(), 5);
+    }
+
+    #[test]
+    fn from_unsorted_nan() {
+        // sort_by notices comparators that aren't total on longer inputs and panics
+        let mut rng = Rng::new(27);
+        let values: Vec<f64> = (0..300).map(|_| match rng.gen_range(0..5) {
+            0 => f64::NAN,
+            _ => rng.gen_range(0..100) as f64
+        }).collect();
+        let nans = values.iter().filter(|value| value.is_nan()).count();
+        assert!(nans > 0);
+
+        let text = format!("{:?}", values);
+        for list in [List::from_unsorted(values), text.parse::<List<f64>>().unwrap()] {
+            let elems: Vec<f64> = list.into_iter().collect();
+            assert_eq!(elems.len(), 300);
+            let (numbers, tail) = elems.split_at(300 - nans);
+            assert!(numbers.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(tail.iter().all(|elem| elem.is_nan()));
+        }
+    }
+
+    #[test]
+    fn from_unsorted() {
+        let mut list = List::from_unsorted(vec![3, -2, 8, 0, 3, 1]);
+        assert_eq!(list.len(), 6);
+        list.insert(2);
+        assert_eq!(drain(&mut list), vec![-2, 0, 1, 2, 3, 3, 8]);
+
+        let mut empty = List::from_unsorted(Vec::new());
+        assert_eq!(drain(&mut empty), vec![]);
+    }
+
+    #[test]
+    fn insert_million_elements() {
+        const N: i32 = 1_000_000;
+        let mut list = List::new();
+        // alternate between a new maximum and a new minimum, so both ends of the list get exercised
+        for i in 0..N / 2 {
+            list.insert(i);
+            list.insert(-i - 1);
+        }
+        assert_eq!(list.len(), N as usize);
+        // one element landing in the middle still has to walk half the list without recursing
+        list.insert(0);
+
+        let elems = drain(&mut list);
+        assert_eq!(elems.len(), N as usize + 1);
+        assert!(elems.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn from_unsorted_million_elements() {
+        const N: i32 = 1_000_000;
+        // deterministic shuffle of 0..N, 7919 is a prime not dividing N
+        let elems = (0..N).map(|i| ((i as i64 * 7919) % N as i64) as i32);
+        let mut list = List::from_unsorted(elems);
+        assert_eq!(list.len(), N as usize);
+        assert_eq!(drain(&mut list), (0..N).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn custom_comparator() {
+        let mut list = List::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+        for elem in [2, 7, 1, 8, 2, 8] {
+            list.insert(elem);
+        }
+        assert_eq!(drain(&mut list), vec![8, 8, 7, 2, 2, 1]);
+    }
+
+    #[test]
+    fn key_function() {
+        let mut list = List::with_key(|word: &&str| word.len());
+        list.insert("three");
+        list.insert("a");
+        list.insert("be");
+        assert_eq!(list.pop_front(), Some("a"));
+        assert_eq!(list.pop_front(), Some("be"));
+        assert_eq!(list.pop_front(), Some("three"));
+        assert_eq!(list.pop_front(), None);
+    }
+
+    fn drain_pairs(list: &mut List<(i32, char)>) -> Vec<(i32, char)> {
+        let mut elems = Vec::new();
+        while let Some(elem) = list.pop_front() {
+            elems.push(elem);
+        }
+        elems
+    }
+
+    #[test]
+    fn stable_placement() {
+        let mut list = List::with_key(|pair: &(i32, char)| pair.0).with_placement(EqualPlacement::Stable);
+        assert_eq!(list.placement(), EqualPlacement::Stable);
+        for pair in [(1, 'a'), (0, 'b'), (1, 'c'), (2, 'd'), (1, 'e')] {
+            list.insert(pair);
+        }
+        assert_eq!(drain_pairs(&mut list), vec![(0, 'b'), (1, 'a'), (1, 'c'), (1, 'e'), (2, 'd')]);
+    }
+
+    #[test]
+    fn unstable_placement() {
+        // default placement puts a new element in front of the ones equal to it
+        let mut list = List::with_key(|pair: &(i32, char)| pair.0);
+        assert_eq!(list.placement(), EqualPlacement::Unstable);
+        for pair in [(1, 'a'), (0, 'b'), (1, 'c'), (2, 'd'), (1, 'e')] {
+            list.insert(pair);
+        }
+        assert_eq!(drain_pairs(&mut list), vec![(0, 'b'), (1, 'e'), (1, 'c'), (1, 'a'), (2, 'd')]);
+    }
+
+    #[test]
+    fn extend_unsorted_merges() {
+        let mut list = List::with_key(|pair: &(i32, char)| pair.0).with_placement(EqualPlacement::Stable);
+        list.extend_unsorted(vec![(5, 'a'), (1, 'b'), (3, 'c')]);
+        list.extend_unsorted(vec![(3, 'd'), (0, 'e'), (9, 'f'), (1, 'g')]);
+        assert_eq!(list.len(), 7);
+        assert_eq!(drain_pairs(&mut list),
+            vec![(0, 'e'), (1, 'b'), (1, 'g'), (3, 'c'), (3, 'd'), (5, 'a'), (9, 'f')]);
+    }
+
+    #[test]
+    fn drops_every_element_once() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+        for elem in [3, 1, 2, 5, 4] {
+            list.insert(counter.track(elem));
+        }
+        let popped = list.pop_front().unwrap();
+        assert_eq!(*popped, 1);
+        drop(popped);
+        assert_eq!(counter.dropped(), 1);
+
+        // no Rc cycle between the nodes survives dropping the list
+        let head = Rc::downgrade(list.head.as_ref().unwrap());
+        let tail = Rc::downgrade(list.tail.as_ref().unwrap());
+        drop(list);
+        assert!(head.upgrade().is_none());
+        assert!(tail.upgrade().is_none());
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn drops_rest_of_partially_consumed_iterator() {
+        let counter = DropCounter::new();
+        let list: List<_> = (0..10).map(|elem| counter.track(elem)).collect();
+        let taken: Vec<_> = list.into_iter().take(3).collect();
+        // the iterator owned the rest of the list and dropped it
+        assert_eq!(counter.alive(), 3);
+        // smallest first, so the remaining elements were dropped in ascending order
+        assert_eq!(counter.dropped_ids(), (3..10).collect::<Vec<_>>());
+        drop(taken);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn to_dot() {
+        let list = List::from_unsorted(vec![3, 1, 2]);
+        let dot = list.to_dot();
+        // ids go in list order, so the nodes come out sorted
+        assert!(dot.contains("n0 [label=\"1\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n2 [label=\"3\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n1 -> n0 [label=\"prev\", style=dashed];"), "{}", dot);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{cell::RefCell, error::Error, fmt::{self, Display, Formatter}, rc::Rc, str::FromStr};
+
+// Text format shared by all the lists: elements in iteration order, in square brackets,
+// separated by ", " - the same thing Vec's Debug prints, e.g. [1, 2, 3] or [].
+// Display writes the elements with Display and Debug with Debug, FromStr reads what Display wrote.
+//
+// The parser splits on commas that aren't nested in brackets, so lists of lists round-trip,
+// but elements whose own text contains a top level comma (like "a, b" strings) can't.
+
+// writes [a, b, c], fmt_elem writes a single element
+pub fn fmt_list<I: IntoIterator>(f: &mut Formatter<'_>, elems: I, mut fmt_elem: impl FnMut(&mut Formatter<'_>, I::Item) -> fmt::Result) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, elem) in elems.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        fmt_elem(f, elem)?;
+    }
+    write!(f, "]")
+}
+
+pub fn fmt_display<I: IntoIterator>(f: &mut Formatter<'_>, elems: I) -> fmt::Result where I::Item: Display {
+    fmt_list(f, elems, |f, elem| write!(f, "{}", elem))
+}
+
+// the Rc of every node of an Rc<RefCell> list, front to back, next picks the link to follow.
+// Elements of those lists can only be lent out through a Ref on the node, which is why they
+// format through this instead of an iterator
+pub fn rc_nodes<N>(head: &Option<Rc<RefCell<N>>>, next: impl Fn(&N) -> &Option<Rc<RefCell<N>>>) -> impl Iterator<Item = Rc<RefCell<N>>> {
+    std::iter::successors(head.clone(), move |node| next(&node.borrow()).clone())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseListError<E> {
+    // the text doesn't start with [ or end with ]
+    MissingBrackets,
+    // more closing than opening brackets somewhere in between
+    UnbalancedBrackets,
+    Elem { index: usize, error: E }
+}
+
+impl<E: Display> Display for ParseListError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseListError::MissingBrackets => write!(f, "a list has to be enclosed in [ and ]"),
+            ParseListError::UnbalancedBrackets => write!(f, "unbalanced brackets inside the list"),
+            ParseListError::Elem { index, error } => write!(f, "element {} can't be parsed: {}", index, error)
+        }
+    }
+}
+
+impl<E: Error> Error for ParseListError<E> {}
+
+// elements in the order they were written, every list builds itself from them the way its FromIterator does
+pub fn parse_elems<T: FromStr>(text: &str) -> Result<Vec<T>, ParseListError<T::Err>> {
+    let inner = text.trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or(ParseListError::MissingBrackets)?;
+    if inner.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    split_top_level(inner)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, elem)| elem.trim().parse().map_err(|error| ParseListError::Elem { index, error }))
+        .collect()
+}
+
+fn split_top_level<E>(inner: &str) -> Result<Vec<&str>, ParseListError<E>> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth = depth.checked_sub(1).ok_or(ParseListError::UnbalancedBrackets)?,
+            ',' if depth == 0 => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            },
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(ParseListError::UnbalancedBrackets);
+    }
+    parts.push(&inner[start..]);
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fmt::{Debug, Display}, num::ParseIntError, str::FromStr};
+
+    use super::{parse_elems, ParseListError};
+    use crate::common::{property::{check, gen_vec, shrink_vec, shrink_i64}, random::Rng};
+    use crate::linked_lists::{
+        bad_stack, ok_singly_linked_stack, persistent_singly_linked_stack, bad_but_safe_doubly_linked_deq,
+        sorted_doubly_linked_list, arena, skip_list, unrolled
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_elems::<i32>("[1, 2, 3]"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_elems::<i32>("  [ -1,2 ,3 ]\n"), Ok(vec![-1, 2, 3]));
+        assert_eq!(parse_elems::<i32>("[]"), Ok(vec![]));
+        assert_eq!(parse_elems::<i32>("[ ]"), Ok(vec![]));
+        assert_eq!(parse_elems::<String>("[a, bc]"), Ok(vec!["a".to_owned(), "bc".to_owned()]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse_elems::<i32>("1, 2"), Err(ParseListError::MissingBrackets));
+        assert_eq!(parse_elems::<i32>("[1, 2"), Err(ParseListError::MissingBrackets));
+        assert_eq!(parse_elems::<i32>("[1], 2]"), Err(ParseListError::UnbalancedBrackets));
+        assert!(matches!(parse_elems::<i32>("[1, x, 3]"), Err(ParseListError::Elem { index: 1, .. })));
+        // a trailing comma leaves an empty element behind
+        assert!(matches!(parse_elems::<i32>("[1, 2, ]"), Err(ParseListError::Elem { index: 2, .. })));
+        let error = parse_elems::<i32>("[1, x]").unwrap_err();
+        assert_eq!(error.to_string(), "element 1 can't be parsed: invalid digit found in string");
+    }
+
+    #[test]
+    fn nested_lists() {
+        let text = "[[1, 2], [], [3]]";
+        let lists: ok_singly_linked_stack::List<ok_singly_linked_stack::List<i32>> = text.parse().unwrap();
+        assert_eq!(lists.to_string(), text);
+        assert_eq!(lists.peek().unwrap().peek(), Some(&1));
+    }
+
+    // Display output parses back into an equal list, and Debug prints the same for numbers
+    fn round_trip<L>(elems: &[i64]) -> Result<(), String>
+    where L: FromIterator<i64> + FromStr<Err = ParseListError<ParseIntError>> + Display + Debug + PartialEq {
+        let list: L = elems.iter().copied().collect();
+        let text = list.to_string();
+        if text != format!("{:?}", list) {
+            return Err(format!("Display gave {} but Debug gave {:?}", text, list));
+        }
+        match text.parse::<L>() {
+            Ok(parsed) if parsed == list => Ok(()),
+            Ok(parsed) => Err(format!("{} parsed into {:?}", text, parsed)),
+            Err(error) => Err(format!("{} didn't parse: {}", text, error))
+        }
+    }
+
+    fn check_round_trip<L>(seed: u64)
+    where L: FromIterator<i64> + FromStr<Err = ParseListError<ParseIntError>> + Display + Debug + PartialEq {
+        check(seed, 200,
+            |rng: &mut Rng| gen_vec(rng, 30, |rng| rng.gen_range(0..2_000) as i64 - 1_000),
+            |elems| shrink_vec(elems, |elem| shrink_i64(*elem)),
+            |elems| round_trip::<L>(elems));
+    }
+
+    #[test]
+    fn round_trips() {
+        check_round_trip::<bad_stack::List<i64>>(1);
+        check_round_trip::<ok_singly_linked_stack::List<i64>>(2);
+        check_round_trip::<persistent_singly_linked_stack::List<i64>>(3);
+        check_round_trip::<persistent_singly_linked_stack::ArcList<i64>>(4);
+        check_round_trip::<bad_but_safe_doubly_linked_deq::List<i64>>(5);
+        check_round_trip::<sorted_doubly_linked_list::List<i64>>(6);
+        check_round_trip::<arena::ArenaList<i64>>(7);
+        check_round_trip::<skip_list::SkipList<i64>>(8);
+        check_round_trip::<unrolled::UnrolledList<i64>>(9);
+        check_round_trip::<unrolled::UnrolledList<i64, 2>>(10);
+    }
+
+    #[test]
+    fn formats() {
+        let stack: bad_stack::List<i32> = (1..=3).collect();
+        assert_eq!(stack.to_string(), "[1, 2, 3]");
+        let sorted: sorted_doubly_linked_list::List<i32> = "[3, 1, 2]".parse().unwrap();
+        assert_eq!(sorted.to_string(), "[1, 2, 3]");
+        let deq = bad_but_safe_doubly_linked_deq::List::<&str>::new();
+        assert_eq!(format!("{} {:?}", deq, deq), "[] []");
+        let strings: unrolled::UnrolledList<String> = "[a, b]".parse().unwrap();
+        assert_eq!(format!("{} {:?}", strings, strings), r#"[a, b] ["a", "b"]"#);
+    }
+}
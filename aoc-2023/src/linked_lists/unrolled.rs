@@ -0,0 +1,725 @@
+use std::{collections::VecDeque, fmt::{self, Debug, Display, Formatter}, ops::{Index, IndexMut}, str::FromStr};
+
+use super::{collection::Deque, text::{self, ParseListError}};
+use crate::common::dot::Dot;
+
+// Unrolled linked list: a doubly linked list of chunks, each chunk holds up to N elements
+// next to each other. Walking the list touches one node per N elements instead of one per
+// element, which is a lot friendlier to the cache, and indexing can skip whole chunks at once.
+//
+// Chunks live in a Vec and link to each other by index, the same way the arena lists do.
+// Every chunk is a VecDeque allocated with capacity N once, so pushing at either end of
+// a chunk never moves its elements around or reallocates.
+//
+// A full chunk is split in half when something is inserted into its middle, and a chunk
+// that gets less than half full after a removal is merged with the next one if they fit,
+// so the chunks don't degrade into one element nodes. Pushes and pops at the ends skip the
+// merging, a deque only ever touches the two end chunks anyway.
+
+pub const DEFAULT_CHUNK: usize = 32;
+
+#[derive(Clone)]
+struct Chunk<T> {
+    elems: VecDeque<T>,
+    prev: Option<usize>,
+    next: Option<usize>
+}
+
+// (chunk, offset in the chunk)
+type Pos = (usize, usize);
+
+#[derive(Clone)]
+pub struct UnrolledList<T, const N: usize = DEFAULT_CHUNK> {
+    chunks: Vec<Chunk<T>>,
+    free: Vec<usize>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    len: usize
+}
+
+impl<T> UnrolledList<T> {
+    pub fn new() -> Self {
+        UnrolledList::with_chunk_capacity()
+    }
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    // for other chunk sizes, e.g. UnrolledList::<u8, 64>::with_chunk_capacity()
+    pub fn with_chunk_capacity() -> Self {
+        assert!(N >= 2, "chunks have to hold at least two elements to be split");
+        UnrolledList { chunks: Vec::new(), free: Vec::new(), head: None, tail: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.and_then(|head| self.chunks[head].elems.front())
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.and_then(|tail| self.chunks[tail].elems.back())
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let head = match self.head {
+            Some(head) if self.chunks[head].elems.len() < N => head,
+            head => self.new_chunk(None, head)
+        };
+        self.chunks[head].elems.push_front(elem);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let tail = match self.tail {
+            Some(tail) if self.chunks[tail].elems.len() < N => tail,
+            tail => self.new_chunk(tail, None)
+        };
+        self.chunks[tail].elems.push_back(elem);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        let elem = self.chunks[head].elems.pop_front();
+        self.len -= 1;
+        if self.chunks[head].elems.is_empty() {
+            self.free_chunk(head);
+        }
+        elem
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        let elem = self.chunks[tail].elems.pop_back();
+        self.len -= 1;
+        if self.chunks[tail].elems.is_empty() {
+            self.free_chunk(tail);
+        }
+        elem
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.locate(index).map(|(chunk, offset)| &self.chunks[chunk].elems[offset])
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.locate(index).map(|(chunk, offset)| &mut self.chunks[chunk].elems[offset])
+    }
+
+    // index == len appends, anything further panics like Vec::insert
+    pub fn insert(&mut self, index: usize, elem: T) {
+        assert!(index <= self.len, "insertion index {} is out of bounds, len is {}", index, self.len);
+        if index == self.len {
+            self.push_back(elem);
+        } else {
+            let pos = self.locate(index).unwrap();
+            self.insert_at(pos, elem);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let pos = self.locate(index)?;
+        Some(self.remove_at(pos).0)
+    }
+
+    pub fn clear(&mut self) {
+        *self = UnrolledList::with_chunk_capacity();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            list: self,
+            front: self.head.map(|head| (head, 0)),
+            back: self.tail.map(|tail| (tail, self.chunks[tail].elems.len() - 1)),
+            remaining: self.len
+        }
+    }
+
+    // cursor on the element at index, or on the "ghost" position past the end for index == len
+    pub fn cursor_mut(&mut self, index: usize) -> CursorMut<'_, T, N> {
+        assert!(index <= self.len, "cursor index {} is out of bounds, len is {}", index, self.len);
+        let pos = self.locate(index);
+        CursorMut { list: self, pos, index }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T, N> {
+        self.cursor_mut(0)
+    }
+
+    // walks from whichever end is closer, skipping whole chunks
+    fn locate(&self, index: usize) -> Option<Pos> {
+        if index >= self.len {
+            return None;
+        }
+        if index < self.len / 2 {
+            let mut skipped = 0;
+            let mut cur = self.head?;
+            while skipped + self.chunks[cur].elems.len() <= index {
+                skipped += self.chunks[cur].elems.len();
+                cur = self.chunks[cur].next?;
+            }
+            Some((cur, index - skipped))
+        } else {
+            // counting from the back, behind is the number of elements after the current chunk
+            let mut behind = 0;
+            let mut cur = self.tail?;
+            while self.len - behind - self.chunks[cur].elems.len() > index {
+                behind += self.chunks[cur].elems.len();
+                cur = self.chunks[cur].prev?;
+            }
+            Some((cur, index - (self.len - behind - self.chunks[cur].elems.len())))
+        }
+    }
+
+    fn step_forward(&self, (chunk, offset): Pos) -> Option<Pos> {
+        if offset + 1 < self.chunks[chunk].elems.len() {
+            Some((chunk, offset + 1))
+        } else {
+            self.chunks[chunk].next.map(|next| (next, 0))
+        }
+    }
+
+    fn step_back(&self, (chunk, offset): Pos) -> Option<Pos> {
+        if offset > 0 {
+            Some((chunk, offset - 1))
+        } else {
+            self.chunks[chunk].prev.map(|prev| (prev, self.chunks[prev].elems.len() - 1))
+        }
+    }
+
+    // offset can be the chunk's len to append to the chunk, returns where the element ended up
+    fn insert_at(&mut self, (chunk, offset): Pos, elem: T) -> Pos {
+        self.len += 1;
+        if self.chunks[chunk].elems.len() < N {
+            self.chunks[chunk].elems.insert(offset, elem);
+            return (chunk, offset);
+        }
+        let half = N / 2;
+        let next = self.chunks[chunk].next;
+        let right = self.new_chunk(Some(chunk), next);
+        let moved: Vec<T> = self.chunks[chunk].elems.drain(half..).collect();
+        self.chunks[right].elems.extend(moved);
+        if offset <= half {
+            self.chunks[chunk].elems.insert(offset, elem);
+            (chunk, offset)
+        } else {
+            self.chunks[right].elems.insert(offset - half, elem);
+            (right, offset - half)
+        }
+    }
+
+    // returns the element and the position of the one that followed it
+    fn remove_at(&mut self, (chunk, offset): Pos) -> (T, Option<Pos>) {
+        let elem = self.chunks[chunk].elems.remove(offset).unwrap();
+        self.len -= 1;
+        let remaining = self.chunks[chunk].elems.len();
+        let next = self.chunks[chunk].next;
+        if remaining == 0 {
+            self.free_chunk(chunk);
+            return (elem, next.map(|next| (next, 0)));
+        }
+        if let Some(next) = next {
+            if remaining < N / 2 && remaining + self.chunks[next].elems.len() <= N {
+                let moved: Vec<T> = self.chunks[next].elems.drain(..).collect();
+                self.chunks[chunk].elems.extend(moved);
+                self.free_chunk(next);
+            }
+        }
+        let following = if offset < self.chunks[chunk].elems.len() {
+            Some((chunk, offset))
+        } else {
+            self.chunks[chunk].next.map(|next| (next, 0))
+        };
+        (elem, following)
+    }
+
+    // links a new empty chunk between prev and next
+    fn new_chunk(&mut self, prev: Option<usize>, next: Option<usize>) -> usize {
+        let chunk = Chunk { elems: VecDeque::with_capacity(N), prev, next };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.chunks[index] = chunk;
+                index
+            },
+            None => {
+                self.chunks.push(chunk);
+                self.chunks.len() - 1
+            }
+        };
+        match prev {
+            Some(prev) => self.chunks[prev].next = Some(index),
+            None => self.head = Some(index)
+        }
+        match next {
+            Some(next) => self.chunks[next].prev = Some(index),
+            None => self.tail = Some(index)
+        }
+        index
+    }
+
+    // unlinks an empty chunk, its slot (and allocation) gets reused by the next new chunk
+    fn free_chunk(&mut self, index: usize) {
+        let (prev, next) = (self.chunks[index].prev, self.chunks[index].next);
+        match prev {
+            Some(prev) => self.chunks[prev].next = next,
+            None => self.head = next
+        }
+        match next {
+            Some(next) => self.chunks[next].prev = prev,
+            None => self.tail = prev
+        }
+        self.free.push(index);
+    }
+}
+
+// Points either at an element or at the ghost position past the last element.
+// Moving past either end lands on the ghost, moving from the ghost wraps around (like LinkedList's cursors).
+pub struct CursorMut<'a, T, const N: usize> {
+    list: &'a mut UnrolledList<T, N>,
+    // None on the ghost
+    pos: Option<Pos>,
+    index: usize
+}
+
+impl<'a, T, const N: usize> CursorMut<'a, T, N> {
+    // len on the ghost
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.pos.map(|(chunk, offset)| &mut self.list.chunks[chunk].elems[offset])
+    }
+
+    pub fn move_next(&mut self) {
+        match self.pos {
+            Some(pos) => {
+                self.pos = self.list.step_forward(pos);
+                self.index += 1;
+            },
+            None => {
+                self.pos = self.list.head.map(|head| (head, 0));
+                self.index = 0;
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        match self.pos {
+            Some(pos) => {
+                self.pos = self.list.step_back(pos);
+                self.index = if self.pos.is_some() { self.index - 1 } else { self.list.len };
+            },
+            None => {
+                self.pos = self.list.locate(self.list.len.wrapping_sub(1));
+                self.index = self.list.len.saturating_sub(1);
+            }
+        }
+    }
+
+    // the cursor stays on the same element, on the ghost this appends
+    pub fn insert_before(&mut self, elem: T) {
+        match self.pos {
+            Some(pos) => {
+                let inserted = self.list.insert_at(pos, elem);
+                self.pos = self.list.step_forward(inserted);
+            },
+            None => self.list.push_back(elem)
+        }
+        self.index += 1;
+    }
+
+    // the cursor stays on the same element, on the ghost this prepends
+    pub fn insert_after(&mut self, elem: T) {
+        match self.pos {
+            Some((chunk, offset)) => {
+                let inserted = self.list.insert_at((chunk, offset + 1), elem);
+                self.pos = self.list.step_back(inserted);
+            },
+            None => {
+                self.list.push_front(elem);
+                self.index += 1;
+            }
+        }
+    }
+
+    // the cursor moves on to the element that followed the removed one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let pos = self.pos?;
+        let (elem, following) = self.list.remove_at(pos);
+        self.pos = following;
+        Some(elem)
+    }
+}
+
+pub struct Iter<'a, T, const N: usize> {
+    list: &'a UnrolledList<T, N>,
+    front: Option<Pos>,
+    back: Option<Pos>,
+    remaining: usize
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (chunk, offset) = self.front?;
+        self.remaining -= 1;
+        self.front = self.list.step_forward((chunk, offset));
+        Some(&self.list.chunks[chunk].elems[offset])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (chunk, offset) = self.back?;
+        self.remaining -= 1;
+        self.back = self.list.step_back((chunk, offset));
+        Some(&self.list.chunks[chunk].elems[offset])
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {}
+
+pub struct IntoIter<T, const N: usize>(UnrolledList<T, N>);
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for UnrolledList<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a UnrolledList<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Iter<'a, T, N> {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Index<usize> for UnrolledList<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(elem) => elem,
+            None => panic!("index {} is out of bounds, len is {}", index, self.len)
+        }
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for UnrolledList<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        match self.get_mut(index) {
+            Some(elem) => elem,
+            None => panic!("index {} is out of bounds, len is {}", index, len)
+        }
+    }
+}
+
+impl<T, const N: usize> Default for UnrolledList<T, N> {
+    fn default() -> Self {
+        UnrolledList::with_chunk_capacity()
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for UnrolledList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = UnrolledList::with_chunk_capacity();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T, const N: usize> Extend<T> for UnrolledList<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for UnrolledList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Display, const N: usize> Display for UnrolledList<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        text::fmt_display(f, self.iter())
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for UnrolledList<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: FromStr, const N: usize> FromStr for UnrolledList<T, N> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        text::parse_elems(s).map(|elems| elems.into_iter().collect())
+    }
+}
+
+impl<T, const N: usize> Deque<T> for UnrolledList<T, N> {
+    type ElemRef<'a> = &'a T where T: 'a;
+
+    fn push_front(&mut self, elem: T) {
+        UnrolledList::push_front(self, elem);
+    }
+
+    fn push_back(&mut self, elem: T) {
+        UnrolledList::push_back(self, elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        UnrolledList::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        UnrolledList::pop_back(self)
+    }
+
+    fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    fn peek_back(&self) -> Option<&T> {
+        self.back()
+    }
+
+    fn is_empty(&self) -> bool {
+        UnrolledList::is_empty(self)
+    }
+}
+
+impl<T: Debug, const N: usize> UnrolledList<T, N> {
+    // one node per chunk, labelled with its elements and how full it is
+    pub fn to_dot(&self) -> String {
+        let mut dot = Dot::new("unrolled_list");
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            let chunk = &self.chunks[index];
+            let id = format!("n{}", index);
+            dot.node(&id, &format!("{:?}\n{}/{}", chunk.elems, chunk.elems.len(), N));
+            if let Some(next) = chunk.next {
+                dot.edge(&id, &format!("n{}", next), "next");
+            }
+            if let Some(prev) = chunk.prev {
+                dot.edge_with(&id, &format!("n{}", prev), "prev", "style=dashed");
+            }
+            cur = chunk.next;
+        }
+        for (marker, end) in [("head", self.head), ("tail", self.tail)] {
+            if let Some(index) = end {
+                dot.marker(marker, marker);
+                dot.edge(marker, &format!("n{}", index), "");
+            }
+        }
+        dot.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnrolledList;
+    use crate::common::random::Rng;
+
+    // small chunks, so even short tests split and merge a lot
+    type Small<T> = UnrolledList<T, 4>;
+
+    fn elems<const N: usize>(list: &UnrolledList<i32, N>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = UnrolledList::new();
+        assert_eq!(list.pop_front(), None);
+        for elem in 0..100 {
+            list.push_back(elem);
+            list.push_front(-elem);
+        }
+        assert_eq!(list.len(), 200);
+        assert_eq!((list.front(), list.back()), (Some(&-99), Some(&99)));
+        assert_eq!(list.pop_back(), Some(99));
+        assert_eq!(list.pop_front(), Some(-99));
+        assert_eq!(list.iter().rev().take(2).collect::<Vec<_>>(), vec![&98, &97]);
+        assert_eq!(list.iter().len(), 198);
+    }
+
+    #[test]
+    fn indexing() {
+        let mut list: Small<i32> = (0..50).collect();
+        for index in 0..50 {
+            assert_eq!(list[index], index as i32);
+        }
+        list[10] = 100;
+        assert_eq!(list.get(10), Some(&100));
+        assert_eq!(list.get(50), None);
+        list.insert(0, -1);
+        list.insert(25, -2);
+        list.insert(52, -3);
+        assert_eq!((list[0], list[25], list[52]), (-1, -2, -3));
+        assert_eq!(list.remove(25), Some(-2));
+        assert_eq!(list.remove(100), None);
+        assert_eq!(list.len(), 52);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn index_out_of_bounds() {
+        let list: Small<i32> = (0..3).collect();
+        let _ = list[3];
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list: Small<i32> = (0..10).collect();
+        let mut cursor = list.cursor_mut(3);
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.insert_before(30);
+        cursor.insert_after(31);
+        assert_eq!((cursor.index(), cursor.current().copied()), (4, Some(3)));
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.current(), Some(&mut 31));
+        cursor.move_prev();
+        *cursor.current().unwrap() *= 10;
+        assert_eq!(elems(&list), vec![0, 1, 2, 300, 31, 4, 5, 6, 7, 8, 9]);
+
+        // past the end and back around
+        let mut cursor = list.cursor_mut(10);
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current()), (11, None));
+        cursor.insert_before(10);
+        cursor.insert_after(-1);
+        cursor.move_next();
+        assert_eq!((cursor.index(), cursor.current().copied()), (0, Some(-1)));
+        cursor.move_prev();
+        assert_eq!((cursor.index(), cursor.current()), (13, None));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 10));
+        assert_eq!(list.len(), 13);
+    }
+
+    #[test]
+    fn cursor_removes_everything() {
+        let mut list: Small<i32> = (0..100).collect();
+        let mut cursor = list.cursor_front_mut();
+        let mut removed = Vec::new();
+        while let Some(elem) = cursor.remove_current() {
+            removed.push(elem);
+        }
+        assert_eq!(removed, (0..100).collect::<Vec<_>>());
+        assert!(list.is_empty());
+        assert_eq!(list.head, None);
+    }
+
+    #[test]
+    fn chunks_stay_dense() {
+        let mut list: Small<i32> = (0..1_000).collect();
+        let mut rng = Rng::new(11);
+        for _ in 0..700 {
+            list.remove(rng.gen_range(0..list.len()));
+        }
+        let mut chunks = 0;
+        let mut cur = list.head;
+        while let Some(chunk) = cur {
+            chunks += 1;
+            cur = list.chunks[chunk].next;
+        }
+        // the merging keeps it well under one chunk per element
+        assert!(chunks * 2 <= list.len(), "{} chunks for {} elements", chunks, list.len());
+    }
+
+    // random inserts, removes and cursor edits, everything has to agree with Vec
+    #[test]
+    fn matches_vec() {
+        for seed in 0..20 {
+            let mut rng = Rng::new(seed);
+            let mut list: Small<i32> = UnrolledList::with_chunk_capacity();
+            let mut model = Vec::new();
+            for elem in 0..1_000 {
+                match rng.gen_range(0..8) {
+                    0 => { list.push_front(elem); model.insert(0, elem); },
+                    1 => { list.push_back(elem); model.push(elem); },
+                    2 => assert_eq!(list.pop_front(), if model.is_empty() { None } else { Some(model.remove(0)) }),
+                    3 => assert_eq!(list.pop_back(), model.pop()),
+                    4 => {
+                        let index = rng.gen_range(0..model.len() + 1);
+                        list.insert(index, elem);
+                        model.insert(index, elem);
+                    },
+                    5 if !model.is_empty() => {
+                        let index = rng.gen_range(0..model.len());
+                        assert_eq!(list.remove(index), Some(model.remove(index)));
+                    },
+                    6 => {
+                        let index = rng.gen_range(0..model.len() + 1);
+                        let mut cursor = list.cursor_mut(index);
+                        cursor.insert_before(elem);
+                        model.insert(index, elem);
+                        if cursor.remove_current().is_some() {
+                            model.remove(index + 1);
+                        }
+                    },
+                    _ => {
+                        let index = rng.gen_range(0..model.len() + 1);
+                        assert_eq!(list.get(index), model.get(index));
+                    }
+                }
+                assert_eq!(list.len(), model.len());
+            }
+            assert_eq!(elems(&list), model);
+            assert!(list.iter().rev().eq(model.iter().rev()));
+        }
+    }
+
+    #[test]
+    fn to_dot() {
+        let list: UnrolledList<i32, 4> = (1..=6).collect();
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"[1, 2, 3, 4]\\n4/4\"];"), "{}", dot);
+        assert!(dot.contains("n1 [label=\"[5, 6]\\n2/4\"];"), "{}", dot);
+        assert!(dot.contains("n0 -> n1 [label=\"next\"];") && dot.contains("tail -> n1;"), "{}", dot);
+    }
+}
@@ -1,8 +1,21 @@
-use aoc_2023::linked_lists::bad_stack::example;
+use aoc_2023::{common::task::{Executor, PanicPolicy, TaskQueue, ThreadPool}, day1, day2, day3};
 
 fn main() {
-    //day1();
-    //day2();
-    //day3();
-    example();
+    // every day on its own thread, a day that panics (say its input is missing) doesn't stop the others
+    let days = ["day1", "day2", "day3"];
+    let mut queue: TaskQueue<String> = TaskQueue::new();
+    queue.push(|| format!("Res: {}", day1::solve()));
+    queue.push(|| {
+        let (part1, part2) = day2::solve();
+        format!("part1 res: {part1}, part2 res: {part2}")
+    });
+    queue.push(|| format!("Res: {}", day3::solve()));
+
+    let results = ThreadPool::with_available_parallelism(PanicPolicy::Isolate).run(queue);
+    for (day, result) in days.iter().zip(results) {
+        match result {
+            Ok(answer) => println!("{}: {}", day, answer),
+            Err(panic) => println!("{} failed: {}", day, panic.message)
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use std::{ops::Add, fmt::{Debug, Display}, cell::RefCell, rc::Rc, collections::btree_map::Values};
+use std::{ops::Add, fmt::{Debug, Display}, cell::RefCell, rc::Rc, collections::{btree_map::Values, hash_map::Entry, HashMap, HashSet}, str::FromStr};
+
+use crate::{common::{dot::{self, Dot}, graph, search, task::Task}, linked_lists::text::{self, ParseListError}};
 
 // ex 1
 mod odd_module {
@@ -20,7 +22,7 @@ mod getter_function {
     }
 }
 
-// ex 2
+// ex 2, the integer helpers the solvers actually need are in common::num
 trait CloneAndDouble {
     fn clone_and_double(&self) -> Self;
 }
@@ -32,33 +34,10 @@ impl<T: Clone + Add<Output = T>> CloneAndDouble for T  {
     }
 }
 
-// ex 3
-trait Unknown {
-    fn serialize(&self) -> String;
-}
-
-impl Unknown for i32 {
-    fn serialize(&self) -> String {
-        format!("{self}")
-    }
-}
-
-impl Unknown for String {
-    fn serialize(&self) -> String {
-        self.clone()
-    }
-}
+// ex 3 is in unknown.rs, it grew into a format that parses back
+pub mod unknown;
 
-impl<T: Debug> Unknown for Vec<T> {
-    fn serialize(&self) -> String {
-        let mut res = "[".to_owned();
-        for item in self {
-            res.push_str(&format!("{:?}, ", item))
-        }
-        res.push_str("]");
-        res
-    }
-}
+use unknown::Unknown;
 
 fn get_vec() -> Vec<Box<dyn Unknown>> {
     let v: Vec<Box<dyn Unknown>> = Vec::new();
@@ -72,49 +51,7 @@ fn print_vec(vec: &Vec<Box<dyn Unknown>>) {
     }
 }
 
-// ex 4
-struct BinIter {
-    digits: Vec<bool>,
-    cur_idx: usize,
-    len: usize
-}
-
-impl BinIter {
-    fn new(number: u32, len: usize) -> BinIter {
-        let bin_string = format!("{:b}", number);
-        println!("bin string {:?}", bin_string);
-        let mut digits: Vec<bool> = Vec::new(); 
-        for c in bin_string.chars() {
-            if c == '1' {
-                digits.push(true)
-            }
-            else if c == '0' {
-                digits.push(false)
-            }
-            else {
-                panic!("unexpected digit {}", c);
-            }
-        }
-        digits.reverse();
-        println!("digits: {:?}", digits);
-        BinIter {digits, cur_idx:0, len:len}
-    }
-}
-
-impl  Iterator for BinIter {
-    type Item = bool;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cur_idx >= self.len {
-            return None;
-        }
-        else{
-            let item = self.digits[self.cur_idx];
-            self.cur_idx +=1;
-            return Some(item);
-        }
-    }
-}
+// ex 4 lives in common::bits now, BitIter walks the bits without building a string first
 
 // ex 5
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
@@ -143,27 +80,30 @@ impl<T: Display> Display for Node<T> {
     }
 }
 
+// same [a, b, c] format as the lists in linked_lists
 impl<T:Display>  Display for List<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[")?;
-        let mut cur_head = self.head.clone();
-        while let Some(cur_node) = cur_head {
-            write!(f, "{}, ", cur_node.borrow().elem)?;
-            cur_head = cur_node.borrow().next.clone();
-        }
-        write!(f, "]")
+        let nodes = std::iter::successors(self.head.clone(), |node| node.borrow().next.clone());
+        text::fmt_list(f, nodes, |f, node| write!(f, "{}", node.borrow()))
     }
 }
 
 impl<T: Debug> Debug for List<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-       write!(f, "[")?;
-       let mut cur_head = self.head.clone();
-       while let Some(cur_node) = cur_head {
-            write!(f, "{:?}, ", cur_node.borrow().elem)?;
-            cur_head = cur_node.borrow().next.clone()
-       }
-       write!(f, "]")
+        let nodes = std::iter::successors(self.head.clone(), |node| node.borrow().next.clone());
+        text::fmt_list(f, nodes, |f, node| write!(f, "{:?}", node.borrow().elem))
+    }
+}
+
+impl<T: Display + Clone + FromStr> FromStr for List<T> {
+    type Err = ParseListError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut list = List::new();
+        for elem in text::parse_elems(s)? {
+            list.push_back(elem);
+        }
+        Ok(list)
     }
 }
 
@@ -247,42 +187,66 @@ impl<T: Display + Clone> List<T> {
 
     fn pop(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
-            self.head = old_head.borrow().next.clone();
-            let value = old_head.borrow().elem.clone();
-            match old_head.clone().borrow_mut().next.take() {
+            match old_head.borrow_mut().next.take() {
                 Some(new_head) => {
                     new_head.borrow_mut().prev = None;
                     self.head = Some(new_head);
                 },
                 None => {
-                    self.head = None;
                     self.tail = None;
                 }
             }
             self.size -= 1;
-            value
+            // no other node points to the old head anymore, so the value can be moved out instead of cloned
+            Rc::try_unwrap(old_head).ok().unwrap().into_inner().elem
         })
     }
 
     fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
-            self.tail = old_tail.borrow().prev.clone();
-            let val = old_tail.borrow().elem.clone();
-            match old_tail.clone().borrow_mut().prev.take(){
+            match old_tail.borrow_mut().prev.take(){
                 Some(new_tail) => {
                     new_tail.borrow_mut().next = None;
                     self.tail = Some(new_tail)
                 },
                 None => {
                     self.head = None;
-                    self.tail = None;
                 }
             }
 
             self.size -=1;
-            val
+            Rc::try_unwrap(old_tail).ok().unwrap().into_inner().elem
         })
     }
+
+    fn to_dot(&self) -> String where T: Debug {
+        dot::doubly_linked("midterm_list", &self.head, &self.tail, |node| &node.elem, |node| &node.next, |node| &node.prev)
+    }
+}
+
+impl<T: Display + Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List::new();
+        let mut cur_head = self.head.clone();
+        while let Some(cur_node) = cur_head {
+            list.push_back(cur_node.borrow().elem.clone());
+            cur_head = cur_node.borrow().next.clone();
+        }
+        list
+    }
+}
+
+// nodes point to each other with strong prev and next links, so without unlinking
+// them the whole list would be kept alive by its own cycles
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        self.tail.take();
+        let mut cur_head = self.head.take();
+        while let Some(cur_node) = cur_head {
+            cur_node.borrow_mut().prev.take();
+            cur_head = cur_node.borrow_mut().next.take();
+        }
+    }
 }
 
 // ex 6
@@ -307,6 +271,14 @@ impl<T> GraphNode<T> {
     }
 }
 
+// like the list nodes, only the elements are compared - following the neighbors
+// would never end on a cyclic graph
+impl<T: PartialEq> PartialEq for GraphNode<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.elem == other.elem
+    }
+}
+
 impl<T: Debug> Debug for GraphNode<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[value:{:?}, adjacents: [", self.elem)?;
@@ -322,68 +294,175 @@ impl<T: Debug> Graph<T> {
         Graph { nodes: nodes }
     }
 
+    // nodes reachable from start_node in depth first pre-order. Nodes are told apart by address,
+    // so every node is visited once even if the graph has cycles or equal elements
     fn dfs(&self, start_node: GraphLink<T>) -> Vec<GraphLink<T>> {
-        let mut visited: Vec<GraphLink<T>> = Vec::new();
-        Graph::visit_node(start_node, &mut visited);
-        visited
+        let linked = Self::linked_graph([&start_node]);
+        linked.dfs(0).into_iter().map(|id| linked.node(id).clone()).collect()
+    }
+
+    // pre is called when a node is first reached, post when everything reachable from it is done.
+    // Iterative with its own stack, so long paths don't overflow the call stack. Nodes are
+    // borrowed only while their neighbors are read, the callbacks can borrow_mut them
+    fn dfs_with(&self, start_node: GraphLink<T>, mut pre: impl FnMut(&GraphLink<T>), mut post: impl FnMut(&GraphLink<T>)) -> Vec<GraphLink<T>> {
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        // every node on the path from the start, with the index of its next neighbor to look at
+        let mut stack: Vec<(GraphLink<T>, usize)> = Vec::new();
+        visited.insert(Rc::as_ptr(&start_node));
+        pre(&start_node);
+        order.push(start_node.clone());
+        stack.push((start_node, 0));
+        while let Some((node, next_neighbor)) = stack.last_mut() {
+            let neighbor = node.borrow().neighbors.get(*next_neighbor).cloned();
+            *next_neighbor += 1;
+            match neighbor {
+                Some(neighbor) => {
+                    if visited.insert(Rc::as_ptr(&neighbor)) {
+                        pre(&neighbor);
+                        order.push(neighbor.clone());
+                        stack.push((neighbor, 0));
+                    }
+                },
+                None => {
+                    let (done, _) = stack.pop().unwrap();
+                    post(&done);
+                }
+            }
+        }
+        order
     }
 
-    fn visit_node(node: GraphLink<T>, visited: &mut Vec<GraphLink<T>>){
-        println!("visiting {:?}", node.borrow().elem);
-        visited.push(node.clone());
-        for adjacent in node.borrow().neighbors.iter() {
-            Graph::visit_node(adjacent.clone(), visited);
+    // nodes reachable from start_node, closest first and in neighbor order within a distance
+    fn bfs(&self, start_node: GraphLink<T>) -> Vec<GraphLink<T>> {
+        let linked = Self::linked_graph([&start_node]);
+        linked.bfs(0).into_iter().map(|id| linked.node(id).clone()).collect()
+    }
+
+    // fewest hops from start_node to goal, both included
+    fn shortest_path(&self, start_node: GraphLink<T>, goal: &GraphLink<T>) -> Option<Vec<GraphLink<T>>> {
+        let linked = Self::linked_graph([&start_node]);
+        let goal = linked.find_node(|node| Rc::ptr_eq(node, goal))?;
+        let path = search::bfs(0, |&id| linked.neighbors(id).collect::<Vec<_>>(), |&id| id == goal)?;
+        Some(path.states.into_iter().map(|id| linked.node(id).clone()).collect())
+    }
+
+    // The traversals and searches run on common::graph, which is what new code should use.
+    // This is the part of the graph reachable from roots with the nodes themselves as node data,
+    // numbered in the order roots lists them, then the ones only reachable through neighbors
+    // in the order they are found. Nodes are told apart by address
+    fn linked_graph<'a>(roots: impl IntoIterator<Item = &'a GraphLink<T>>) -> graph::Graph<GraphLink<T>> where T: 'a {
+        let mut linked = graph::Graph::directed();
+        let mut ids = HashMap::new();
+        for node in roots {
+            if let Entry::Vacant(entry) = ids.entry(Rc::as_ptr(node)) {
+                entry.insert(linked.add_node(node.clone()));
+            }
         }
+        let mut i = 0;
+        while i < linked.node_count() {
+            let node = linked.node(i).clone();
+            for neighbor in node.borrow().neighbors.iter() {
+                let to = *ids.entry(Rc::as_ptr(neighbor)).or_insert_with(|| linked.add_node(neighbor.clone()));
+                linked.add_edge(i, to, ());
+            }
+            i += 1;
+        }
+        linked
+    }
+
+    // the same graph on common::graph with copies of the elements, the whole graph this time
+    fn to_index_graph(&self) -> graph::Graph<T> where T: Clone {
+        let linked = Self::linked_graph(&self.nodes);
+        let mut index_graph = graph::Graph::directed();
+        for (_, node) in linked.nodes() {
+            index_graph.add_node(node.borrow().elem.clone());
+        }
+        for (from, to, _) in linked.edges() {
+            index_graph.add_edge(from, to, ());
+        }
+        index_graph
+    }
+
+    // every node reachable from the graph's nodes, with an edge per neighbor.
+    // Strong counts are read when a node is first seen, before the walk clones it
+    fn to_dot(&self) -> String {
+        let mut dot = Dot::new("midterm_graph");
+        let mut seen = HashSet::new();
+        let mut found: Vec<(GraphLink<T>, usize)> = Vec::new();
+        for node in &self.nodes {
+            if seen.insert(Rc::as_ptr(node)) {
+                let strong = Rc::strong_count(node);
+                found.push((node.clone(), strong));
+            }
+        }
+        let mut i = 0;
+        while i < found.len() {
+            let node = found[i].0.clone();
+            let id = dot.id_of(Rc::as_ptr(&node));
+            dot.node(&id, &format!("{:?}\nstrong: {}", node.borrow().elem, found[i].1));
+            for neighbor in &node.borrow().neighbors {
+                if seen.insert(Rc::as_ptr(neighbor)) {
+                    let strong = Rc::strong_count(neighbor);
+                    found.push((neighbor.clone(), strong));
+                }
+                let neighbor_id = dot.id_of(Rc::as_ptr(neighbor));
+                dot.edge(&id, &neighbor_id, "");
+            }
+            i += 1;
+        }
+        dot.finish()
     }
 }
 
-// ex 7
-// trait Task {
-//     fn execute(&self) -> usize;
-// }
-
-// struct SumTask{
-//     n1: usize,
-//     n2: usize
-// }
-
-// impl SumTask {
-//     fn new(n1: usize, n2: usize) -> Self {
-//         SumTask {n1: n1, n2: n2}
-//     }
-// }
-
-// impl  Task for SumTask {
-//     fn execute(&self) -> usize {
-//         self.n1 + self.n2
-//     }
-// }
-
-// struct LenTask {
-//     string: String
-// }
-
-// impl LenTask {
-//     fn new(s: String) ->Self {
-//         LenTask {string:s}
-//     }
-// }
-
-// impl Task for LenTask {
-//     fn execute(&self) -> usize {
-//         self.string.len()
-//     }
-// }
+// ex 7, the Task trait itself lives in common::task, with the executors that run it
+pub struct SumTask {
+    n1: usize,
+    n2: usize
+}
+
+impl SumTask {
+    pub fn new(n1: usize, n2: usize) -> Self {
+        SumTask { n1, n2 }
+    }
+}
+
+impl Task for SumTask {
+    type Output = usize;
+
+    fn execute(&self) -> usize {
+        self.n1 + self.n2
+    }
+}
+
+pub struct LenTask {
+    string: String
+}
+
+impl LenTask {
+    pub fn new(s: String) -> Self {
+        LenTask { string: s }
+    }
+}
+
+impl Task for LenTask {
+    type Output = usize;
+
+    fn execute(&self) -> usize {
+        self.string.len()
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     struct TestData {
         value: i32,
     }
 
     use std::io::Write;
     use super::*;
+    use crate::common::{bits::BitIter, drop_counter::DropCounter, task::{Executor, PanicPolicy, TaskQueue, ThreadPool}};
     
     #[test]
     fn test_graph_dfs() {
@@ -404,10 +483,165 @@ mod tests {
     
         // Perform DFS starting from node1
         let result = graph.dfs(Rc::clone(&node1));
-        println!("{:?}", result);
     
         // Verify the order of visited nodes
-        //assert_eq!(result, vec![Rc::clone(&node1), Rc::clone(&node2), Rc::clone(&node4), Rc::clone(&node3), Rc::clone(&node5)]);
+        assert_eq!(result, vec![Rc::clone(&node1), Rc::clone(&node2), Rc::clone(&node4), Rc::clone(&node3), Rc::clone(&node5)]);
+        assert!(result.iter().zip([&node1, &node2, &node4, &node3, &node5]).all(|(visited, node)| Rc::ptr_eq(visited, node)));
+
+        let result = graph.bfs(Rc::clone(&node1));
+        assert_eq!(result, vec![Rc::clone(&node1), Rc::clone(&node2), Rc::clone(&node3), Rc::clone(&node4), Rc::clone(&node5)]);
+        // only what is reachable from the start
+        assert_eq!(graph.dfs(Rc::clone(&node3)).len(), 2);
+    }
+
+    fn graph_node(value: i32) -> GraphLink<i32> {
+        Rc::new(RefCell::new(GraphNode::new(value, Vec::new())))
+    }
+
+    fn values(nodes: &[GraphLink<i32>]) -> Vec<i32> {
+        nodes.iter().map(|node| node.borrow().elem).collect()
+    }
+
+    // the nodes keep each other alive through the cycles, unlink them so they get freed
+    fn unlink(nodes: &[GraphLink<i32>]) {
+        for node in nodes {
+            node.borrow_mut().neighbors.clear();
+        }
+    }
+
+    #[test]
+    fn test_graph_cycles() {
+        let nodes: Vec<_> = (0..4).map(graph_node).collect();
+        // 0 -> 1 -> 2 -> 0, 2 -> 3 -> 3
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (2, 3), (3, 3)] {
+            nodes[from].borrow_mut().neighbors.push(Rc::clone(&nodes[to]));
+        }
+        let graph = Graph::new(nodes.clone());
+
+        assert_eq!(values(&graph.dfs(Rc::clone(&nodes[0]))), vec![0, 1, 2, 3], "{}", graph.to_dot());
+        assert_eq!(values(&graph.dfs(Rc::clone(&nodes[2]))), vec![2, 0, 1, 3], "{}", graph.to_dot());
+        assert_eq!(values(&graph.bfs(Rc::clone(&nodes[1]))), vec![1, 2, 0, 3], "{}", graph.to_dot());
+        assert_eq!(values(&graph.bfs(Rc::clone(&nodes[3]))), vec![3], "{}", graph.to_dot());
+        unlink(&nodes);
+    }
+
+    #[test]
+    fn test_graph_equal_elements_are_different_nodes() {
+        let (first, second) = (graph_node(7), graph_node(7));
+        first.borrow_mut().neighbors.push(Rc::clone(&second));
+        second.borrow_mut().neighbors.push(Rc::clone(&first));
+        let graph = Graph::new(vec![Rc::clone(&first), Rc::clone(&second)]);
+        assert_eq!(values(&graph.dfs(Rc::clone(&first))), vec![7, 7]);
+        assert_eq!(values(&graph.bfs(Rc::clone(&first))), vec![7, 7]);
+        unlink(&[first, second]);
+    }
+
+    #[test]
+    fn test_graph_shortest_path() {
+        let nodes: Vec<_> = (0..5).map(graph_node).collect();
+        // 0 -> 1 -> 2 -> 3 -> 4, with a shortcut 1 -> 3 and a way back 4 -> 0
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 4), (1, 3), (4, 0)] {
+            nodes[from].borrow_mut().neighbors.push(Rc::clone(&nodes[to]));
+        }
+        let graph = Graph::new(nodes.clone());
+        let path = graph.shortest_path(Rc::clone(&nodes[0]), &nodes[4]).unwrap();
+        assert_eq!(values(&path), vec![0, 1, 3, 4], "{}", graph.to_dot());
+        let path = graph.shortest_path(Rc::clone(&nodes[2]), &nodes[1]).unwrap();
+        assert_eq!(values(&path), vec![2, 3, 4, 0, 1]);
+        nodes[4].borrow_mut().neighbors.clear();
+        assert!(graph.shortest_path(Rc::clone(&nodes[4]), &nodes[0]).is_none());
+        unlink(&nodes);
+    }
+
+    #[test]
+    fn test_graph_to_index_graph() {
+        let nodes: Vec<_> = (0..4).map(graph_node).collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (2, 3), (3, 3)] {
+            nodes[from].borrow_mut().neighbors.push(Rc::clone(&nodes[to]));
+        }
+        // node 3 is only reachable through node 2
+        let graph = Graph::new(nodes[..3].to_vec());
+        let index_graph = graph.to_index_graph();
+        assert_eq!(index_graph.nodes().map(|(_, elem)| *elem).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(index_graph.edges().map(|(from, to, _)| (from, to)).collect::<Vec<_>>(), vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 3)]);
+        // both traversals see the same graph
+        assert_eq!(index_graph.dfs(2), vec![2, 0, 1, 3]);
+        assert_eq!(values(&graph.dfs(Rc::clone(&nodes[2]))), vec![2, 0, 1, 3]);
+        unlink(&nodes);
+    }
+
+    #[test]
+    fn test_graph_topology() {
+        // the graph from test_graph_dfs is a tree, so it sorts
+        let node1 = Rc::new(RefCell::new(GraphNode::new(TestData { value: 1 }, Vec::new())));
+        let node2 = Rc::new(RefCell::new(GraphNode::new(TestData { value: 2 }, Vec::new())));
+        let node3 = Rc::new(RefCell::new(GraphNode::new(TestData { value: 3 }, Vec::new())));
+        let node4 = Rc::new(RefCell::new(GraphNode::new(TestData { value: 4 }, Vec::new())));
+        let node5 = Rc::new(RefCell::new(GraphNode::new(TestData { value: 5 }, Vec::new())));
+        node1.borrow_mut().neighbors.push(Rc::clone(&node2));
+        node1.borrow_mut().neighbors.push(Rc::clone(&node3));
+        node2.borrow_mut().neighbors.push(Rc::clone(&node4));
+        node3.borrow_mut().neighbors.push(Rc::clone(&node5));
+        let graph = Graph::new(vec![Rc::clone(&node1), Rc::clone(&node2), Rc::clone(&node3), Rc::clone(&node4), Rc::clone(&node5)]);
+
+        let index_graph = graph.to_index_graph();
+        assert_eq!(index_graph.topological_sort_kahn(), Ok(vec![0, 1, 2, 3, 4]));
+        assert_eq!(index_graph.topological_sort_dfs(), Ok(vec![0, 2, 4, 1, 3]));
+        assert_eq!(index_graph.tarjan_scc().len(), 5);
+
+        // 5 -> 3 closes the cycle 3 -> 5 -> 3, 4 -> 1 closes 1 -> 2 -> 4 -> 1
+        node5.borrow_mut().neighbors.push(Rc::clone(&node3));
+        let index_graph = graph.to_index_graph();
+        let cycle = index_graph.topological_sort_dfs().unwrap_err();
+        assert_eq!(cycle.iter().map(|&id| index_graph.node(id).value).collect::<Vec<_>>(), vec![3, 5], "{}", graph.to_dot());
+        node4.borrow_mut().neighbors.push(Rc::clone(&node1));
+        let index_graph = graph.to_index_graph();
+        let components: Vec<Vec<i32>> = index_graph.kosaraju_scc().into_iter()
+            .map(|component| component.into_iter().map(|id| index_graph.node(id).value).collect())
+            .collect();
+        assert_eq!(components, vec![vec![1, 2, 4], vec![3, 5]], "{}", graph.to_dot());
+        let condensation = index_graph.condensation();
+        assert_eq!(condensation.dag.edges().map(|(from, to, _)| (from, to)).collect::<Vec<_>>(), vec![(0, 1)]);
+
+        for node in [&node1, &node2, &node3, &node4, &node5] {
+            node.borrow_mut().neighbors.clear();
+        }
+    }
+
+    #[test]
+    fn test_graph_pre_and_post_order() {
+        let nodes: Vec<_> = (0..5).map(graph_node).collect();
+        // 0 -> 1 -> 3, 0 -> 2 -> 4 -> 0
+        for (from, to) in [(0, 1), (1, 3), (0, 2), (2, 4), (4, 0)] {
+            nodes[from].borrow_mut().neighbors.push(Rc::clone(&nodes[to]));
+        }
+        let graph = Graph::new(nodes.clone());
+        let (mut pre, mut post) = (Vec::new(), Vec::new());
+        let order = graph.dfs_with(Rc::clone(&nodes[0]),
+            |node| pre.push(node.borrow().elem),
+            |node| post.push(node.borrow().elem));
+        assert_eq!(pre, vec![0, 1, 3, 2, 4]);
+        assert_eq!(values(&order), pre);
+        assert_eq!(post, vec![3, 1, 4, 2, 0]);
+
+        // the callbacks can change the nodes while the walk is going on
+        graph.dfs_with(Rc::clone(&nodes[0]), |node| node.borrow_mut().elem *= 10, |_| {});
+        assert_eq!(values(&nodes), vec![0, 10, 20, 30, 40]);
+        unlink(&nodes);
+    }
+
+    #[test]
+    fn test_graph_long_path() {
+        const N: usize = 200_000;
+        let nodes: Vec<_> = (0..N as i32).map(graph_node).collect();
+        for pair in nodes.windows(2) {
+            pair[0].borrow_mut().neighbors.push(Rc::clone(&pair[1]));
+        }
+        let graph = Graph::new(nodes.clone());
+        let mut post_count = 0;
+        let order = graph.dfs_with(Rc::clone(&nodes[0]), |_| {}, |_| post_count += 1);
+        assert_eq!(order.len(), N);
+        assert_eq!(post_count, N);
     }
 
     #[test]
@@ -470,38 +704,43 @@ mod tests {
 
     #[test]
     fn test_bin_iter() {
-        let bin_iter = BinIter::new(10, 4);
+        let bin_iter = BitIter::new(10u32, 4);
         let result: Vec<bool> = bin_iter.collect();
         assert_eq!(result, vec![false, true, false, true]);
 
-        let bin_iter = BinIter::new(42, 6);
+        let bin_iter = BitIter::new(42u32, 6);
         let result: Vec<bool> = bin_iter.collect();
         assert_eq!(result, vec![false, true, false, true, false, true]);
 
-        let bin_iter = BinIter::new(7, 3);
+        let bin_iter = BitIter::new(7u32, 3);
         let result: Vec<bool> = bin_iter.collect();
         assert_eq!(result, vec![true, true, true]);
+
+        // used to panic, 1 has a single significant bit
+        let bin_iter = BitIter::new(1u32, 4);
+        let result: Vec<bool> = bin_iter.collect();
+        assert_eq!(result, vec![true, false, false, false]);
     }
 
     #[test]
     fn test_i32_serialize() {
         let value: i32 = 42;
-        assert_eq!(value.serialize(), "42");
+        assert_eq!(value.serialize(), "42i32");
     }
 
     #[test]
     fn test_string_serialize() {
         let value: String = "hello".to_owned();
-        assert_eq!(value.serialize(), "hello");
+        assert_eq!(value.serialize(), "\"hello\"");
     }
 
     #[test]
     fn test_vec_serialize() {
         let vec: Vec<i32> = vec![1, 2, 3];
-        assert_eq!(vec.serialize(), "[1, 2, 3, ]");
+        assert_eq!(vec.serialize(), "[1i32, 2i32, 3i32]");
 
         let vec: Vec<String> = vec!["a".to_owned(), "b".to_owned()];
-        assert_eq!(vec.serialize(), "[\"a\", \"b\", ]");
+        assert_eq!(vec.serialize(), "[\"a\", \"b\"]");
     }
 
     #[test]
@@ -521,4 +760,116 @@ mod tests {
         // Since we are printing, we'll just visually verify the output
         print_vec(&vec);
     }
+
+    #[test]
+    fn test_list_drops_every_element_once() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+        for value in 0..4 {
+            list.push(counter.track(value));
+            list.push_back(counter.track(value + 10));
+        }
+        assert_eq!(list.pop().map(|value| value.id()), Some(6));
+        assert_eq!(list.pop_back().map(|value| value.id()), Some(7));
+        // popping moves the values out, nothing gets cloned
+        assert_eq!(counter.created(), 8);
+
+        let head = Rc::downgrade(list.head.as_ref().unwrap());
+        let tail = Rc::downgrade(list.tail.as_ref().unwrap());
+        drop(list);
+        assert!(head.upgrade().is_none());
+        assert!(tail.upgrade().is_none());
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn test_list_pop_until_empty_drops_nodes() {
+        let counter = DropCounter::new();
+        let mut list = List::new();
+        list.push(counter.track("a"));
+        list.push(counter.track("b"));
+        let node = Rc::downgrade(list.head.as_ref().unwrap());
+        while list.pop().is_some() {}
+        assert!(node.upgrade().is_none());
+        assert_eq!(list.size, 0);
+        counter.assert_all_dropped_once();
+    }
+
+    #[test]
+    fn test_list_deep_clone_eq_drop() {
+        const N: usize = 1_000_000;
+        let mut list = List::new();
+        for value in 0..N {
+            list.push_back(value);
+        }
+        let clone = list.clone();
+        assert_eq!(clone.size, N);
+        assert_eq!(list, clone);
+        list.push(N);
+        assert_ne!(list, clone);
+        drop(list);
+        drop(clone);
+    }
+
+    #[test]
+    fn test_list_format_round_trip() {
+        let mut list = List::new();
+        assert_eq!(list.to_string(), "[]");
+        list.push_back(1);
+        list.push_back(2);
+        list.push(0);
+        assert_eq!(list.to_string(), "[0, 1, 2]");
+        assert_eq!(format!("{:?}", list), "[0, 1, 2]");
+        let parsed: List<i32> = list.to_string().parse().unwrap();
+        assert_eq!(parsed, list);
+        assert_eq!(parsed.size, 3);
+        assert!("[0, 1,".parse::<List<i32>>().is_err());
+
+        let words: List<String> = "[a, b]".parse().unwrap();
+        assert_eq!(format!("{} {:?}", words, words), r#"[a, b] ["a", "b"]"#);
+    }
+
+    #[test]
+    fn test_list_to_dot() {
+        let list: List<i32> = "[1, 2]".parse().unwrap();
+        let dot = list.to_dot();
+        // both nodes are held by one neighbor link and one of head and tail
+        assert!(dot.contains("n0 [label=\"1\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n1 [label=\"2\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n1 -> n0 [label=\"prev\", style=dashed];"), "{}", dot);
+        assert!(dot.contains("tail -> n1;"), "{}", dot);
+    }
+
+    #[test]
+    fn test_graph_to_dot() {
+        let node1 = Rc::new(RefCell::new(GraphNode::new(1, Vec::new())));
+        let node2 = Rc::new(RefCell::new(GraphNode::new(2, Vec::new())));
+        // not in the graph's own list, only reachable through node2
+        let node3 = Rc::new(RefCell::new(GraphNode::new(3, Vec::new())));
+        node1.borrow_mut().neighbors.push(Rc::clone(&node2));
+        node2.borrow_mut().neighbors.push(Rc::clone(&node3));
+        node2.borrow_mut().neighbors.push(Rc::clone(&node1));
+        let graph = Graph::new(vec![Rc::clone(&node1), Rc::clone(&node2)]);
+
+        let dot = graph.to_dot();
+        // local + graph + node2's neighbor list
+        assert!(dot.contains("n0 [label=\"1\\nstrong: 3\"];"), "{}", dot);
+        assert!(dot.contains("n1 [label=\"2\\nstrong: 3\"];"), "{}", dot);
+        assert!(dot.contains("n2 [label=\"3\\nstrong: 2\"];"), "{}", dot);
+        assert!(dot.contains("n0 -> n1;") && dot.contains("n1 -> n2;") && dot.contains("n1 -> n0;"), "{}", dot);
+        // the cycle between node1 and node2 would leak, break it
+        node2.borrow_mut().neighbors.clear();
+    }
+
+    #[test]
+    fn test_tasks() {
+        let mut queue: TaskQueue<usize> = TaskQueue::new();
+        queue.push(SumTask::new(1, 2));
+        queue.push(LenTask::new("hello".to_owned()));
+        queue.push_boxed(Box::new(SumTask::new(40, 2)));
+        queue.push(LenTask::new(String::new()));
+        let results: Vec<usize> = ThreadPool::new(2, PanicPolicy::Propagate).run(queue).into_iter().map(Result::unwrap).collect();
+        assert_eq!(results, vec![3, 5, 42, 0]);
+        assert_eq!(SumTask::new(2, 2).execute(), 4);
+    }
 }
@@ -0,0 +1,725 @@
+use std::{collections::{BTreeMap, HashMap}, error::Error, fmt::{self, Display, Formatter}, hash::Hash, str::FromStr};
+
+// ex 3, grown into a small text format that describes itself and parses back.
+//
+//   integers   42i32, -7i64, 255u8        the type is part of the literal, so 1u8 and 1i64 differ
+//   strings    "a \"quoted\" word\n"       \" \\ \n \r \t \0 and \u{7f} for other control characters
+//   vectors    [1i32, 2i32]
+//   tuples     (1i32, "a"), (1i32,) with a trailing comma for a single element, like in Rust
+//   options    None, Some(1i32)
+//   maps       {"a": 1i32, "b": 2i32}
+//
+// There are no trailing separators and no untyped values, so every text has one meaning.
+// Anything Unknown turns into a Value first and Value knows how to write itself, parsing gives
+// a Value back and Deserialize checks it has the expected shape. A Value is Unknown as well,
+// which is how a Vec<Box<dyn Unknown>> with mixed elements comes back from text.
+
+pub trait Unknown {
+    fn to_value(&self) -> Value;
+
+    fn serialize(&self) -> String {
+        self.to_value().to_string()
+    }
+}
+
+pub trait Deserialize: Sized {
+    fn from_value(value: Value) -> Result<Self, FormatError>;
+
+    fn deserialize(text: &str) -> Result<Self, FormatError> {
+        Self::from_value(text.parse()?)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Int(Int),
+    Str(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+    Option(Option<Box<Value>>),
+    // entries in the order they were written
+    Map(Vec<(Value, Value)>)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatError {
+    UnexpectedEnd,
+    Unexpected { position: usize, found: char, expected: &'static str },
+    // digits without a known type suffix, or a number that doesn't fit its type
+    BadInt { position: usize, text: String },
+    BadEscape { position: usize },
+    // a single value was parsed but the text goes on
+    TrailingText { position: usize },
+    // the text is fine, but it's not the type that was asked for
+    Mismatch { expected: &'static str, found: String },
+    DuplicateKey(String),
+    // more than MAX_DEPTH values nested in each other, the value starting at position is one too many
+    TooDeep { position: usize }
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnexpectedEnd => write!(f, "the text ends in the middle of a value"),
+            FormatError::Unexpected { position, found, expected } => write!(f, "expected {} at {} but found {:?}", expected, position, found),
+            FormatError::BadInt { position, text } => write!(f, "{:?} at {} isn't an integer with a type suffix like 42i32", text, position),
+            FormatError::BadEscape { position } => write!(f, "unknown escape sequence at {}", position),
+            FormatError::TrailingText { position } => write!(f, "unexpected text after the value at {}", position),
+            FormatError::Mismatch { expected, found } => write!(f, "expected {} but found {}", expected, found),
+            FormatError::DuplicateKey(key) => write!(f, "the key {} appears more than once", key),
+            FormatError::TooDeep { position } => write!(f, "the value at {} is nested more than {} levels deep", position, MAX_DEPTH)
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+fn mismatch(expected: &'static str, found: &Value) -> FormatError {
+    FormatError::Mismatch { expected, found: found.to_string() }
+}
+
+// one variant per integer type, generated together with their Unknown and Deserialize impls
+macro_rules! integers {
+    ($($variant:ident $t:ident),*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Int {
+            $($variant($t)),*
+        }
+
+        impl Int {
+            fn parse(digits: &str, suffix: &str) -> Option<Int> {
+                match suffix {
+                    $(stringify!($t) => digits.parse().ok().map(Int::$variant),)*
+                    _ => None
+                }
+            }
+        }
+
+        impl Display for Int {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Int::$variant(value) => write!(f, "{}{}", value, stringify!($t))),*
+                }
+            }
+        }
+
+        $(
+            impl Unknown for $t {
+                fn to_value(&self) -> Value {
+                    Value::Int(Int::$variant(*self))
+                }
+            }
+
+            impl Deserialize for $t {
+                fn from_value(value: Value) -> Result<Self, FormatError> {
+                    match value {
+                        Value::Int(Int::$variant(value)) => Ok(value),
+                        other => Err(mismatch(stringify!($t), &other))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integers!(I8 i8, I16 i16, I32 i32, I64 i64, I128 i128, Isize isize, U8 u8, U16 u16, U32 u32, U64 u64, U128 u128, Usize usize);
+
+fn write_separated<'a, T: 'a>(f: &mut Formatter<'_>, items: impl IntoIterator<Item = &'a T>, mut write_item: impl FnMut(&mut Formatter<'_>, &T) -> fmt::Result) -> fmt::Result {
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
+
+fn write_string(f: &mut Formatter<'_>, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\0' => write!(f, "\\0")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{}", c)?
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(int) => write!(f, "{}", int),
+            Value::Str(text) => write_string(f, text),
+            Value::List(items) => {
+                write!(f, "[")?;
+                write_separated(f, items, |f, item| write!(f, "{}", item))?;
+                write!(f, "]")
+            },
+            Value::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0]),
+            Value::Tuple(items) => {
+                write!(f, "(")?;
+                write_separated(f, items, |f, item| write!(f, "{}", item))?;
+                write!(f, ")")
+            },
+            Value::Option(None) => write!(f, "None"),
+            Value::Option(Some(value)) => write!(f, "Some({})", value),
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                write_separated(f, entries, |f, (key, value)| write!(f, "{}: {}", key, value))?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// The parser calls itself once per nesting level, without a limit a few kilobytes of [[[[
+// would overflow the stack and take the whole process down instead of returning an error
+pub const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    // how many values the parser is inside of right now
+    depth: usize
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Result<char, FormatError> {
+        let c = self.peek().ok_or(FormatError::UnexpectedEnd)?;
+        self.position += c.len_utf8();
+        Ok(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn unexpected(&self, expected: &'static str) -> FormatError {
+        match self.peek() {
+            Some(found) => FormatError::Unexpected { position: self.position, found, expected },
+            None => FormatError::UnexpectedEnd
+        }
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), FormatError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.unexpected(expected));
+        }
+        self.position += c.len_utf8();
+        Ok(())
+    }
+
+    // the run of characters matching is_part, starting at the current position
+    fn take_while(&mut self, is_part: impl Fn(char) -> bool) -> &str {
+        let start = self.position;
+        while let Some(c) = self.peek().filter(|&c| is_part(c)) {
+            self.position += c.len_utf8();
+        }
+        &self.text[start..self.position]
+    }
+
+    fn value(&mut self) -> Result<Value, FormatError> {
+        self.skip_whitespace();
+        if self.depth == MAX_DEPTH {
+            return Err(FormatError::TooDeep { position: self.position });
+        }
+        self.depth += 1;
+        let value = self.nested_value();
+        self.depth -= 1;
+        value
+    }
+
+    // value without the depth check, whitespace is already skipped
+    fn nested_value(&mut self) -> Result<Value, FormatError> {
+        match self.peek() {
+            None => Err(FormatError::UnexpectedEnd),
+            Some('"') => self.string().map(Value::Str),
+            Some('[') => {
+                self.position += 1;
+                let (items, trailing) = self.separated(']', Parser::value)?;
+                if trailing {
+                    return Err(FormatError::Unexpected { position: self.position - 1, found: ']', expected: "a value after the comma" });
+                }
+                Ok(Value::List(items))
+            },
+            Some('(') => {
+                self.position += 1;
+                let (items, trailing) = self.separated(')', Parser::value)?;
+                // (x,) is a tuple of one, (x) and (x, y,) are mistakes
+                if trailing != (items.len() == 1) {
+                    return Err(FormatError::Unexpected { position: self.position - 1, found: ')', expected: "a trailing comma only after a single element" });
+                }
+                Ok(Value::Tuple(items))
+            },
+            Some('{') => {
+                self.position += 1;
+                let (entries, trailing) = self.separated('}', |parser| {
+                    let key = parser.value()?;
+                    parser.expect(':', "a colon after the key")?;
+                    Ok((key, parser.value()?))
+                })?;
+                if trailing {
+                    return Err(FormatError::Unexpected { position: self.position - 1, found: '}', expected: "an entry after the comma" });
+                }
+                Ok(Value::Map(entries))
+            },
+            Some('-' | '0'..='9') => self.int(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.position;
+                match self.take_while(|c| c.is_ascii_alphanumeric()) {
+                    "None" => Ok(Value::Option(None)),
+                    "Some" => {
+                        self.expect('(', "an opening parenthesis after Some")?;
+                        let value = self.value()?;
+                        self.expect(')', "a closing parenthesis")?;
+                        Ok(Value::Option(Some(Box::new(value))))
+                    },
+                    _ => {
+                        self.position = start;
+                        Err(self.unexpected("a value"))
+                    }
+                }
+            },
+            Some(_) => Err(self.unexpected("a value"))
+        }
+    }
+
+    // items up to the closing bracket, the opening one is already read.
+    // Also says if there was a comma right before the closing bracket
+    fn separated<T>(&mut self, close: char, mut item: impl FnMut(&mut Self) -> Result<T, FormatError>) -> Result<(Vec<T>, bool), FormatError> {
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.position += 1;
+            return Ok((items, false));
+        }
+        loop {
+            items.push(item(self)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    if self.peek() == Some(close) {
+                        self.position += 1;
+                        return Ok((items, true));
+                    }
+                },
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok((items, false));
+                },
+                _ => return Err(self.unexpected("a comma or a closing bracket"))
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<Value, FormatError> {
+        let start = self.position;
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+        let digits_end = self.position + self.take_while(|c| c.is_ascii_digit()).len();
+        self.take_while(|c| c.is_ascii_alphanumeric());
+        let (digits, suffix) = (&self.text[start..digits_end], &self.text[digits_end..self.position]);
+        Int::parse(digits, suffix)
+            .map(Value::Int)
+            .ok_or_else(|| FormatError::BadInt { position: start, text: self.text[start..self.position].to_owned() })
+    }
+
+    fn string(&mut self) -> Result<String, FormatError> {
+        self.expect('"', "a string")?;
+        let mut text = String::new();
+        loop {
+            match self.bump()? {
+                '"' => return Ok(text),
+                '\\' => {
+                    let position = self.position - 1;
+                    let c = match self.bump()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        '0' => '\0',
+                        'u' => {
+                            self.expect('{', "an opening brace after \\u")?;
+                            let code = self.take_while(|c| c.is_ascii_hexdigit());
+                            let c = u32::from_str_radix(code, 16).ok().and_then(char::from_u32);
+                            self.expect('}', "a closing brace after the code")?;
+                            c.ok_or(FormatError::BadEscape { position })?
+                        },
+                        _ => return Err(FormatError::BadEscape { position })
+                    };
+                    text.push(c);
+                },
+                c => text.push(c)
+            }
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = FormatError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { text, position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(FormatError::TrailingText { position: parser.position });
+        }
+        Ok(value)
+    }
+}
+
+impl Unknown for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl Deserialize for Value {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        Ok(value)
+    }
+}
+
+// whatever the text holds, as a Value behind the box
+impl Deserialize for Box<dyn Unknown> {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        Ok(Box::new(value))
+    }
+}
+
+impl<T: Unknown + ?Sized> Unknown for Box<T> {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: Unknown + ?Sized> Unknown for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl Unknown for str {
+    fn to_value(&self) -> Value {
+        Value::Str(self.to_owned())
+    }
+}
+
+impl Unknown for String {
+    fn to_value(&self) -> Value {
+        Value::Str(self.clone())
+    }
+}
+
+impl Deserialize for String {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        match value {
+            Value::Str(text) => Ok(text),
+            other => Err(mismatch("a string", &other))
+        }
+    }
+}
+
+impl<T: Unknown> Unknown for Vec<T> {
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(Unknown::to_value).collect())
+    }
+}
+
+impl<T: Deserialize> Deserialize for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        match value {
+            Value::List(items) => items.into_iter().map(T::from_value).collect(),
+            other => Err(mismatch("a vector", &other))
+        }
+    }
+}
+
+impl<T: Unknown> Unknown for Option<T> {
+    fn to_value(&self) -> Value {
+        Value::Option(self.as_ref().map(|value| Box::new(value.to_value())))
+    }
+}
+
+impl<T: Deserialize> Deserialize for Option<T> {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        match value {
+            Value::Option(value) => value.map(|value| T::from_value(*value)).transpose(),
+            other => Err(mismatch("an option", &other))
+        }
+    }
+}
+
+macro_rules! tuples {
+    ($($len:literal => ($($t:ident $index:tt),*)),*) => {$(
+        impl<$($t: Unknown),*> Unknown for ($($t,)*) {
+            fn to_value(&self) -> Value {
+                Value::Tuple(vec![$(self.$index.to_value()),*])
+            }
+        }
+
+        impl<$($t: Deserialize),*> Deserialize for ($($t,)*) {
+            fn from_value(value: Value) -> Result<Self, FormatError> {
+                match value {
+                    Value::Tuple(items) if items.len() == $len => {
+                        let mut items = items.into_iter();
+                        Ok(($($t::from_value(items.next().unwrap())?,)*))
+                    },
+                    other => Err(mismatch(concat!("a tuple of ", $len), &other))
+                }
+            }
+        }
+    )*};
+}
+
+tuples!(1 => (A 0), 2 => (A 0, B 1), 3 => (A 0, B 1, C 2), 4 => (A 0, B 1, C 2, D 3));
+
+// entries sorted by their text, so the same map always gives the same text
+impl<K: Unknown, V: Unknown> Unknown for HashMap<K, V> {
+    fn to_value(&self) -> Value {
+        let mut entries: Vec<(String, Value, Value)> = self.iter()
+            .map(|(key, value)| (key.serialize(), key.to_value(), value.to_value()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Value::Map(entries.into_iter().map(|(_, key, value)| (key, value)).collect())
+    }
+}
+
+impl<K: Deserialize + Eq + Hash, V: Deserialize> Deserialize for HashMap<K, V> {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        map_entries(value, HashMap::new(), |map, key, value| map.insert(key, value).is_none())
+    }
+}
+
+impl<K: Unknown, V: Unknown> Unknown for BTreeMap<K, V> {
+    fn to_value(&self) -> Value {
+        Value::Map(self.iter().map(|(key, value)| (key.to_value(), value.to_value())).collect())
+    }
+}
+
+impl<K: Deserialize + Ord, V: Deserialize> Deserialize for BTreeMap<K, V> {
+    fn from_value(value: Value) -> Result<Self, FormatError> {
+        map_entries(value, BTreeMap::new(), |map, key, value| map.insert(key, value).is_none())
+    }
+}
+
+// insert says if the key was new
+fn map_entries<K: Deserialize, V: Deserialize, M>(value: Value, mut map: M, insert: impl Fn(&mut M, K, V) -> bool) -> Result<M, FormatError> {
+    let Value::Map(entries) = value else {
+        return Err(mismatch("a map", &value));
+    };
+    for (key, value) in entries {
+        let text = key.to_string();
+        if !insert(&mut map, K::from_value(key)?, V::from_value(value)?) {
+            return Err(FormatError::DuplicateKey(text));
+        }
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::common::{property::{check, gen_vec, shrink_vec}, random::Rng};
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(42i32.serialize(), "42i32");
+        assert_eq!((-7i64).serialize(), "-7i64");
+        assert_eq!(u128::MAX.serialize(), "340282366920938463463374607431768211455u128");
+        assert_eq!("say \"hi\"\\\n".serialize(), r#""say \"hi\"\\\n""#);
+        assert_eq!("\u{1}é".serialize(), r#""\u{1}é""#);
+        assert_eq!(vec![1u8, 2, 3].serialize(), "[1u8, 2u8, 3u8]");
+        assert_eq!(Vec::<i32>::new().serialize(), "[]");
+        assert_eq!((1i32, "a".to_owned()).serialize(), r#"(1i32, "a")"#);
+        assert_eq!((5usize,).serialize(), "(5usize,)");
+        assert_eq!(Some(vec![None, Some(1i8)]).serialize(), "Some([None, Some(1i8)])");
+        let map: HashMap<String, i32> = [("b".to_owned(), 2), ("a".to_owned(), 1)].into_iter().collect();
+        assert_eq!(map.serialize(), r#"{"a": 1i32, "b": 2i32}"#);
+    }
+
+    #[test]
+    fn test_typed_round_trips() {
+        let text = r#" [ (1i64, "one", None) , (-2i64,"two\t",Some( [3u16] )) ] "#;
+        let parsed: Vec<(i64, String, Option<Vec<u16>>)> = Deserialize::deserialize(text).unwrap();
+        assert_eq!(parsed, vec![(1, "one".to_owned(), None), (-2, "two\t".to_owned(), Some(vec![3]))]);
+        assert_eq!(Vec::<(i64, String, Option<Vec<u16>>)>::deserialize(&parsed.serialize()), Ok(parsed));
+
+        let map: BTreeMap<u32, (String,)> = [(2, ("x".to_owned(),)), (1, ("\"".to_owned(),))].into_iter().collect();
+        assert_eq!(map.serialize(), r#"{1u32: ("\"",), 2u32: ("x",)}"#);
+        assert_eq!(BTreeMap::deserialize(&map.serialize()), Ok(map));
+        assert_eq!(i8::deserialize("-128i8"), Ok(i8::MIN));
+        assert_eq!(String::deserialize(r#""\u{1F600}""#), Ok("😀".to_owned()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(i32::deserialize("42"), Err(FormatError::BadInt { position: 0, text: "42".to_owned() }));
+        assert_eq!(u8::deserialize("256u8"), Err(FormatError::BadInt { position: 0, text: "256u8".to_owned() }));
+        assert_eq!(i64::deserialize("42i32"), Err(FormatError::Mismatch { expected: "i64", found: "42i32".to_owned() }));
+        // the old output with its trailing separator
+        assert!(matches!(Vec::<i32>::deserialize("[1i32, 2i32, ]"), Err(FormatError::Unexpected { position: 13, found: ']', .. })));
+        assert!(matches!(Value::from_str("{1u8: 2u8,}"), Err(FormatError::Unexpected { found: '}', .. })));
+        assert!(matches!(Value::from_str("(1i32)"), Err(FormatError::Unexpected { position: 5, .. })));
+        assert!(matches!(Value::from_str("(1i32, 2i32,)"), Err(FormatError::Unexpected { .. })));
+        assert_eq!(Value::from_str(r#""open"#), Err(FormatError::UnexpectedEnd));
+        assert_eq!(Value::from_str(r#""\q""#), Err(FormatError::BadEscape { position: 1 }));
+        assert_eq!(Value::from_str(r#""\u{d800}""#), Err(FormatError::BadEscape { position: 1 }));
+        assert_eq!(Value::from_str("1u8 2u8"), Err(FormatError::TrailingText { position: 4 }));
+        assert_eq!(Value::from_str("Nothing"), Err(FormatError::Unexpected { position: 0, found: 'N', expected: "a value" }));
+        assert_eq!(Value::from_str(""), Err(FormatError::UnexpectedEnd));
+        assert_eq!(HashMap::<u8, u8>::deserialize("{1u8: 2u8, 1u8: 3u8}"), Err(FormatError::DuplicateKey("1u8".to_owned())));
+        assert_eq!(<(u8, u8)>::deserialize("(1u8,)").unwrap_err().to_string(), "expected a tuple of 2 but found (1u8,)");
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        // this used to overflow the stack
+        let deep = "[".repeat(10_000) + &"]".repeat(10_000);
+        assert_eq!(Value::from_str(&deep), Err(FormatError::TooDeep { position: MAX_DEPTH }));
+        let deep = "Some(".repeat(10_000) + "None" + &")".repeat(10_000);
+        assert_eq!(Value::from_str(&deep), Err(FormatError::TooDeep { position: 5 * MAX_DEPTH }));
+
+        // right at the limit is fine
+        let at_limit = "[".repeat(MAX_DEPTH - 1) + "1u8" + &"]".repeat(MAX_DEPTH - 1);
+        assert_eq!(Value::from_str(&at_limit).map(|value| value.to_string()), Ok(at_limit));
+        let over = "[".repeat(MAX_DEPTH) + "1u8" + &"]".repeat(MAX_DEPTH);
+        assert_eq!(Value::from_str(&over), Err(FormatError::TooDeep { position: MAX_DEPTH }));
+    }
+
+    #[test]
+    fn test_heterogeneous_vec() {
+        let items: Vec<Box<dyn Unknown>> = vec![
+            Box::new(42),
+            Box::new("hello, [world]".to_owned()),
+            Box::new(vec![1, 2, 3]),
+            Box::new((Some(7u64), None::<String>)),
+            Box::new(HashMap::from([(1u8, vec!["x".to_owned()])]))
+        ];
+        let text = items.serialize();
+        assert_eq!(text, r#"[42i32, "hello, [world]", [1i32, 2i32, 3i32], (Some(7u64), None), {1u8: ["x"]}]"#);
+        let parsed = Vec::<Box<dyn Unknown>>::deserialize(&text).unwrap();
+        assert_eq!(parsed.len(), 5);
+        assert_eq!(parsed.serialize(), text);
+        // the elements are self describing, each one can be read back as its own type
+        assert_eq!(Vec::<i32>::deserialize(&parsed[2].serialize()), Ok(vec![1, 2, 3]));
+    }
+
+    fn gen_string(rng: &mut Rng) -> String {
+        const CHARS: [char; 12] = ['a', 'Z', '0', ' ', '"', '\\', '\n', '\t', '\u{7}', 'é', '😀', ','];
+        (0..rng.gen_range(0..8)).map(|_| CHARS[rng.gen_range(0..CHARS.len())]).collect()
+    }
+
+    fn gen_int(rng: &mut Rng) -> Int {
+        let bits = rng.next_u64();
+        match rng.gen_range(0..6) {
+            0 => Int::I8(bits as i8),
+            1 => Int::U16(bits as u16),
+            2 => Int::I32(bits as i32),
+            3 => Int::I64(bits as i64),
+            4 => Int::U128((bits as u128) << 64 | rng.next_u64() as u128),
+            _ => Int::Usize(bits as usize)
+        }
+    }
+
+    fn gen_value(rng: &mut Rng, depth: usize) -> Value {
+        let kind = if depth == 0 { rng.gen_range(0..2) } else { rng.gen_range(0..6) };
+        match kind {
+            0 => Value::Int(gen_int(rng)),
+            1 => Value::Str(gen_string(rng)),
+            2 => Value::List(gen_vec(rng, 4, |rng| gen_value(rng, depth - 1))),
+            3 => Value::Tuple(gen_vec(rng, 4, |rng| gen_value(rng, depth - 1))),
+            4 => Value::Option((rng.gen_range(0..2) == 0).then(|| Box::new(gen_value(rng, depth - 1)))),
+            _ => Value::Map(gen_vec(rng, 3, |rng| (gen_value(rng, depth - 1), gen_value(rng, depth - 1))))
+        }
+    }
+
+    // the parts of a value, and the value with fewer parts
+    fn shrink_value(value: &Value) -> Vec<Value> {
+        match value {
+            Value::Int(_) | Value::Option(None) => Vec::new(),
+            Value::Str(text) if text.is_empty() => Vec::new(),
+            Value::Str(text) => vec![Value::Str(text[text.char_indices().nth(1).map_or(text.len(), |(i, _)| i)..].to_owned())],
+            Value::List(items) => items.iter().cloned().chain(shrink_vec(items, shrink_value).into_iter().map(Value::List)).collect(),
+            Value::Tuple(items) => items.iter().cloned().chain(shrink_vec(items, shrink_value).into_iter().map(Value::Tuple)).collect(),
+            Value::Option(Some(inner)) => vec![(**inner).clone(), Value::Option(None)],
+            Value::Map(entries) => entries.iter().flat_map(|(key, value)| [key.clone(), value.clone()])
+                .chain(shrink_vec(entries, |_| Vec::new()).into_iter().map(Value::Map))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_values_round_trip() {
+        check(48, 500,
+            |rng| gen_value(rng, 3),
+            shrink_value,
+            |value| {
+                let text = value.to_string();
+                match text.parse::<Value>() {
+                    Ok(parsed) if parsed == *value => Ok(()),
+                    Ok(parsed) => Err(format!("{} parsed into {:?}", text, parsed)),
+                    Err(error) => Err(format!("{} didn't parse: {}", text, error))
+                }
+            });
+    }
+
+    #[test]
+    fn test_typed_values_round_trip() {
+        type Row = (i64, String, Option<Vec<u8>>);
+        check(49, 300,
+            |rng| gen_vec(rng, 6, |rng| {
+                let bytes = (rng.gen_range(0..2) == 0).then(|| gen_vec(rng, 4, |rng| rng.next_u64() as u8));
+                (rng.next_u64() as i64, gen_string(rng), bytes)
+            }),
+            |rows| shrink_vec(rows, |_| Vec::new()),
+            |rows: &Vec<Row>| {
+                let text = rows.serialize();
+                match Vec::<Row>::deserialize(&text) {
+                    Ok(parsed) if parsed == *rows => Ok(()),
+                    other => Err(format!("{} gave {:?}", text, other))
+                }
+            });
+    }
+
+    #[test]
+    fn test_dyn_round_trip() {
+        // random mixes of differently typed values behind Box<dyn Unknown>
+        check(50, 300,
+            |rng| gen_vec(rng, 6, |rng| rng.next_u64()),
+            |seeds| shrink_vec(seeds, |_| Vec::new()),
+            |seeds| {
+                let items: Vec<Box<dyn Unknown>> = seeds.iter().map(|&seed| -> Box<dyn Unknown> {
+                    let mut rng = Rng::new(seed);
+                    match rng.gen_range(0..5) {
+                        0 => Box::new(rng.next_u64() as i16),
+                        1 => Box::new(gen_string(&mut rng)),
+                        2 => Box::new(gen_vec(&mut rng, 3, |rng| rng.next_u64() as u32)),
+                        3 => Box::new((gen_string(&mut rng), Some(rng.next_u64() as i128))),
+                        _ => Box::new(gen_value(&mut rng, 2))
+                    }
+                }).collect();
+                let text = items.serialize();
+                let parsed = Vec::<Box<dyn Unknown>>::deserialize(&text).map_err(|error| format!("{} didn't parse: {}", text, error))?;
+                if parsed.serialize() == text { Ok(()) } else { Err(format!("{} came back as {}", text, parsed.serialize())) }
+            });
+    }
+}